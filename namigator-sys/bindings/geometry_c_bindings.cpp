@@ -0,0 +1,168 @@
+// C bindings for reading the collision geometry of a map from the client
+// data, which is the geometry the map builder builds the navmesh from.
+//
+// Built maps only keep the navmesh and the gameobject models, so terrain and
+// liquid have to be read from the ADTs again, the same way the vendored
+// MapViewer does.
+//
+// The result codes and layers used by these functions must be kept in sync
+// with the constants in `src/lib.rs`.
+
+#include "parser/Adt/Adt.hpp"
+#include "parser/Adt/AdtChunk.hpp"
+#include "parser/Doodad/DoodadInstance.hpp"
+#include "parser/Map/Map.hpp"
+#include "parser/MpqManager.hpp"
+#include "parser/Wmo/WmoInstance.hpp"
+#include "utility/Vector.hpp"
+
+#include <cstdint>
+#include <unordered_set>
+#include <vector>
+
+namespace
+{
+constexpr std::uint8_t SUCCESS = 0;
+constexpr std::uint8_t MAP_DOES_NOT_HAVE_ADT = 87;
+constexpr std::uint8_t UNKNOWN_EXCEPTION = 0xFF;
+
+constexpr std::uint8_t GEOMETRY_TERRAIN = 0;
+constexpr std::uint8_t GEOMETRY_LIQUID = 1;
+constexpr std::uint8_t GEOMETRY_WMO = 2;
+constexpr std::uint8_t GEOMETRY_DOODAD = 3;
+
+constexpr int CHUNKS_PER_ADT = 16;
+
+using GeometryCallback = void (*)(void* state, std::uint8_t layer,
+                                  const float* vertices,
+                                  unsigned int amount_of_vertices,
+                                  const int* indices,
+                                  unsigned int amount_of_indices);
+
+struct Output
+{
+    GeometryCallback callback;
+    void* state;
+
+    // Passes the triangles to the callback with the vertices as consecutive
+    // x, y and z floats in world coordinates.
+    void emit(std::uint8_t layer, const std::vector<math::Vertex>& vertices,
+              const std::vector<int>& indices) const
+    {
+        if (indices.empty())
+            return;
+
+        std::vector<float> positions;
+        positions.reserve(vertices.size() * 3);
+        for (const auto& vertex : vertices)
+        {
+            positions.push_back(vertex.X);
+            positions.push_back(vertex.Y);
+            positions.push_back(vertex.Z);
+        }
+
+        callback(state, layer, positions.data(),
+                 static_cast<unsigned int>(vertices.size()), indices.data(),
+                 static_cast<unsigned int>(indices.size()));
+    }
+
+    void emit_wmo(const parser::WmoInstance& wmo) const
+    {
+        std::vector<math::Vertex> vertices;
+        std::vector<int> indices;
+
+        wmo.BuildTriangles(vertices, indices);
+        emit(GEOMETRY_WMO, vertices, indices);
+
+        vertices.clear();
+        indices.clear();
+        wmo.BuildLiquidTriangles(vertices, indices);
+        emit(GEOMETRY_LIQUID, vertices, indices);
+
+        vertices.clear();
+        indices.clear();
+        wmo.BuildDoodadTriangles(vertices, indices);
+        emit(GEOMETRY_DOODAD, vertices, indices);
+    }
+};
+} // namespace
+
+extern "C" {
+
+// Calls `callback` with the triangles of the terrain, liquid, WMOs and
+// doodads of the ADTs of `map_name`, given as `amount_of_adts` pairs of x and
+// y in `adts`.
+//
+// Models that are in more than one of the ADTs are only included once. Maps
+// without ADTs consist of a single WMO, which is included instead of `adts`.
+std::uint8_t mapbuild_collision_geometry(const char* data_path,
+                                         const char* map_name, const int* adts,
+                                         unsigned int amount_of_adts,
+                                         GeometryCallback callback,
+                                         void* state)
+{
+    try
+    {
+        parser::sMpqManager.Initialize(data_path);
+        const parser::Map map(map_name);
+        const Output output {callback, state};
+
+        if (const auto* wmo = map.GetGlobalWmoInstance())
+        {
+            output.emit_wmo(*wmo);
+            return SUCCESS;
+        }
+
+        std::unordered_set<std::uint32_t> wmos;
+        std::unordered_set<std::uint32_t> doodads;
+
+        for (auto i = 0u; i < amount_of_adts; ++i)
+        {
+            const auto x = adts[i * 2];
+            const auto y = adts[i * 2 + 1];
+
+            if (!map.HasAdt(x, y))
+                return MAP_DOES_NOT_HAVE_ADT;
+
+            const auto* adt = map.GetAdt(x, y);
+            for (auto chunk_y = 0; chunk_y < CHUNKS_PER_ADT; ++chunk_y)
+                for (auto chunk_x = 0; chunk_x < CHUNKS_PER_ADT; ++chunk_x)
+                {
+                    const auto* chunk = adt->GetChunk(chunk_x, chunk_y);
+
+                    output.emit(GEOMETRY_TERRAIN, chunk->m_terrainVertices,
+                                chunk->m_terrainIndices);
+                    output.emit(GEOMETRY_LIQUID, chunk->m_liquidVertices,
+                                chunk->m_liquidIndices);
+
+                    wmos.insert(chunk->m_wmoInstances.begin(),
+                                chunk->m_wmoInstances.end());
+                    doodads.insert(chunk->m_doodadInstances.begin(),
+                                   chunk->m_doodadInstances.end());
+                }
+        }
+
+        for (const auto id : wmos)
+            if (const auto* wmo = map.GetWmoInstance(id))
+                output.emit_wmo(*wmo);
+
+        for (const auto id : doodads)
+        {
+            const auto* doodad = map.GetDoodadInstance(id);
+            if (!doodad)
+                continue;
+
+            std::vector<math::Vertex> vertices;
+            std::vector<int> indices;
+            doodad->BuildTriangles(vertices, indices);
+            output.emit(GEOMETRY_DOODAD, vertices, indices);
+        }
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+}
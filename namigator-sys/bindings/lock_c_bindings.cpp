@@ -0,0 +1,108 @@
+// C bindings for advisory file locks, used to stop two processes from
+// building the same map into the same output.
+//
+// The lock is held by the operating system for the open file, so it is
+// released when the process exits, even if it is killed. The lock file itself
+// is left in place since removing it would let another process lock a new
+// file while a third still holds the lock on the removed one.
+//
+// The result codes returned from these functions must be kept in sync with
+// the constants in `src/lib.rs`.
+
+#ifdef _WIN32
+#define WIN32_LEAN_AND_MEAN
+#include <windows.h>
+#else
+#include <cerrno>
+#include <fcntl.h>
+#include <sys/file.h>
+#include <unistd.h>
+#endif
+
+#include <cstdint>
+
+namespace
+{
+constexpr std::uint8_t SUCCESS = 0;
+constexpr std::uint8_t FILE_IS_LOCKED = 93;
+constexpr std::uint8_t COULD_NOT_OPEN_LOCK_FILE = 94;
+constexpr std::uint8_t UNKNOWN_EXCEPTION = 0xFF;
+
+struct Lock
+{
+#ifdef _WIN32
+    HANDLE file;
+#else
+    int file;
+#endif
+};
+} // namespace
+
+extern "C" {
+
+// Creates `path` if it does not exist and locks it without waiting.
+//
+// Returns nullptr with `FILE_IS_LOCKED` when another open file holds the lock.
+void* lock_file_acquire(const char* path, std::uint8_t* out_result)
+{
+    try
+    {
+#ifdef _WIN32
+        const auto file = CreateFileA(
+            path, GENERIC_READ | GENERIC_WRITE,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE, nullptr,
+            OPEN_ALWAYS, FILE_ATTRIBUTE_NORMAL, nullptr);
+        if (file == INVALID_HANDLE_VALUE)
+        {
+            *out_result = COULD_NOT_OPEN_LOCK_FILE;
+            return nullptr;
+        }
+
+        OVERLAPPED overlapped = {};
+        if (!LockFileEx(file,
+                        LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY, 0,
+                        MAXDWORD, MAXDWORD, &overlapped))
+        {
+            CloseHandle(file);
+            *out_result = FILE_IS_LOCKED;
+            return nullptr;
+        }
+#else
+        const auto file = open(path, O_RDWR | O_CREAT | O_CLOEXEC, 0644);
+        if (file < 0)
+        {
+            *out_result = COULD_NOT_OPEN_LOCK_FILE;
+            return nullptr;
+        }
+
+        if (flock(file, LOCK_EX | LOCK_NB) != 0)
+        {
+            const auto locked = errno == EWOULDBLOCK;
+            close(file);
+            *out_result = locked ? FILE_IS_LOCKED : COULD_NOT_OPEN_LOCK_FILE;
+            return nullptr;
+        }
+#endif
+
+        *out_result = SUCCESS;
+        return new Lock {file};
+    }
+    catch (...)
+    {
+        *out_result = UNKNOWN_EXCEPTION;
+        return nullptr;
+    }
+}
+
+// Releases the lock by closing the file, which is left in place.
+void lock_file_release(void* lock)
+{
+    const auto* l = static_cast<Lock*>(lock);
+#ifdef _WIN32
+    CloseHandle(l->file);
+#else
+    close(l->file);
+#endif
+    delete l;
+}
+}
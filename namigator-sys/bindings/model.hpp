@@ -0,0 +1,45 @@
+// Reading of the gameobject models written to the `BVH` directory by
+// `mapbuild_build_bvh`, shared by every binding that loads them so that
+// verifying a model checks exactly what loading it does.
+
+#pragma once
+
+#include "utility/AABBTree.hpp"
+#include "utility/BinaryStream.hpp"
+
+#include <cstdint>
+#include <fstream>
+#include <iterator>
+#include <vector>
+
+namespace model
+{
+// Deserializes the model at `path` into `tree`.
+//
+// Returns false when the file can not be read, can not be deserialized or
+// has triangles that are incomplete or refer to vertices it does not have.
+inline bool read(const char* path, utility::AABBTree& tree)
+{
+    std::ifstream in(path, std::ifstream::binary);
+    if (!in)
+        return false;
+
+    std::vector<std::uint8_t> buffer((std::istreambuf_iterator<char>(in)),
+                                     std::istreambuf_iterator<char>());
+    utility::BinaryStream stream(buffer);
+
+    if (!tree.Deserialize(stream))
+        return false;
+
+    const auto& indices = tree.Indices();
+    if (indices.size() % 3 != 0)
+        return false;
+
+    const auto amount_of_vertices = tree.Vertices().size();
+    for (const auto index : indices)
+        if (index < 0 || static_cast<std::size_t>(index) >= amount_of_vertices)
+            return false;
+
+    return true;
+}
+} // namespace model
@@ -0,0 +1,152 @@
+// C bindings for reading single MPQ archives with the vendored StormLib.
+//
+// StormLib types such as `TCHAR` and `SFILE_FIND_DATA` differ between
+// platforms, so they are kept on this side of the boundary.
+//
+// The result codes returned from these functions must be kept in sync with
+// the constants in `src/lib.rs`.
+
+#include "StormLib.h"
+
+#include <cstdint>
+#include <cstring>
+#include <string>
+#include <vector>
+
+namespace
+{
+constexpr std::uint8_t SUCCESS = 0;
+constexpr std::uint8_t COULD_NOT_OPEN_MPQ = 72;
+constexpr std::uint8_t ERROR_IN_SFILEOPENFILEX = 76;
+constexpr std::uint8_t ERROR_IN_SFILEREADFILE = 77;
+constexpr std::uint8_t BUFFER_TOO_SMALL = 82;
+constexpr std::uint8_t UNKNOWN_EXCEPTION = 0xFF;
+} // namespace
+
+extern "C" {
+
+void* mpq_open_archive(const char* path, std::uint8_t* out_result)
+{
+    try
+    {
+        HANDLE archive = nullptr;
+        if (!SFileOpenArchive(path, 0, MPQ_OPEN_READ_ONLY, &archive))
+        {
+            *out_result = COULD_NOT_OPEN_MPQ;
+            return nullptr;
+        }
+
+        *out_result = SUCCESS;
+        return archive;
+    }
+    catch (...)
+    {
+        *out_result = UNKNOWN_EXCEPTION;
+        return nullptr;
+    }
+}
+
+void mpq_close_archive(void* archive)
+{
+    SFileCloseArchive(archive);
+}
+
+std::uint8_t mpq_has_file(void* archive, const char* name,
+                          std::uint8_t* out_has_file)
+{
+    try
+    {
+        *out_has_file = SFileHasFile(archive, name) ? 1 : 0;
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+// Writes the contents of `name` into `buffer`.
+//
+// `out_size` is always set to the size of the file, so that the buffer can be
+// resized when `BUFFER_TOO_SMALL` is returned.
+std::uint8_t mpq_read_file(void* archive, const char* name,
+                           std::uint8_t* buffer, unsigned int buffer_length,
+                           unsigned int* out_size)
+{
+    try
+    {
+        HANDLE file = nullptr;
+        if (!SFileOpenFileEx(archive, name, SFILE_OPEN_FROM_MPQ, &file))
+            return ERROR_IN_SFILEOPENFILEX;
+
+        const auto size = SFileGetFileSize(file, nullptr);
+        if (size == SFILE_INVALID_SIZE)
+        {
+            SFileCloseFile(file);
+            return ERROR_IN_SFILEREADFILE;
+        }
+
+        *out_size = size;
+        if (size > buffer_length)
+        {
+            SFileCloseFile(file);
+            return BUFFER_TOO_SMALL;
+        }
+
+        DWORD read = 0;
+        const auto success =
+            size == 0 || SFileReadFile(file, buffer, size, &read, nullptr);
+        SFileCloseFile(file);
+
+        if (!success || read != size)
+            return ERROR_IN_SFILEREADFILE;
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+// Writes the names of the files matching the wildcard `mask` into `buffer`,
+// each followed by a nul byte.
+//
+// `out_length` is always set to the length of all names, so that the buffer
+// can be resized when `BUFFER_TOO_SMALL` is returned.
+std::uint8_t mpq_list_files(void* archive, const char* mask, char* buffer,
+                            unsigned int buffer_length,
+                            unsigned int* out_length)
+{
+    try
+    {
+        std::vector<char> names;
+
+        SFILE_FIND_DATA data;
+        auto find = SFileFindFirstFile(archive, mask, &data, nullptr);
+        if (find != nullptr)
+        {
+            do
+            {
+                names.insert(names.end(), data.cFileName,
+                             data.cFileName + std::strlen(data.cFileName) + 1);
+            } while (SFileFindNextFile(find, &data));
+
+            SFileFindClose(find);
+        }
+
+        *out_length = static_cast<unsigned int>(names.size());
+        if (names.size() > buffer_length)
+            return BUFFER_TOO_SMALL;
+
+        if (!names.empty())
+            std::memcpy(buffer, names.data(), names.size());
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+}
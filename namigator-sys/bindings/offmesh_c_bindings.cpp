@@ -0,0 +1,358 @@
+// C bindings for adding off-mesh connections to the loaded navmesh tiles of a
+// map.
+//
+// namigator does not build off-mesh connections into its tiles, so a tile is
+// rebuilt here with the connections that start in it appended in the same
+// layout as `dtCreateNavMeshData` uses. The rebuilt tile is added back with
+// the reference of the old tile, so namigator still unloads it as usual and
+// Detour frees the rebuilt data when that happens.
+//
+// The result codes returned from these functions must be kept in sync with
+// the constants in `src/lib.rs`.
+
+#include "Common.hpp"
+#include "pathfind/Map.hpp"
+
+#include "DetourAlloc.h"
+#include "DetourCommon.h"
+#include "DetourNavMesh.h"
+
+#include <cstdint>
+#include <cstring>
+#include <vector>
+
+namespace
+{
+constexpr std::uint8_t SUCCESS = 0;
+constexpr std::uint8_t FAILED_TO_ADD_OFF_MESH_CONNECTIONS = 92;
+constexpr std::uint8_t UNKNOWN_EXCEPTION = 0xFF;
+
+// Must be kept in sync with `OffMeshConnection` in `src/lib.rs`.
+struct OffMeshConnection
+{
+    std::uint32_t id;
+    float from_x;
+    float from_y;
+    float from_z;
+    float to_x;
+    float to_y;
+    float to_z;
+    float radius;
+    std::uint8_t bidirectional;
+};
+
+// Connection in Recast coordinates.
+struct Connection
+{
+    float pos[6];
+    float radius;
+    std::uint32_t id;
+    bool bidirectional;
+};
+
+void to_recast(float x, float y, float z, float* out)
+{
+    out[0] = -y;
+    out[1] = z;
+    out[2] = -x;
+}
+
+// Same as `classifyOffMeshPoint` in DetourNavMeshBuilder.cpp, 0xff when the
+// point is inside of the tile and the side of the tile it is on otherwise.
+unsigned char classify(const float* point, const float* bmin,
+                       const float* bmax)
+{
+    constexpr unsigned char XP = 1 << 0;
+    constexpr unsigned char ZP = 1 << 1;
+    constexpr unsigned char XM = 1 << 2;
+    constexpr unsigned char ZM = 1 << 3;
+
+    unsigned char outcode = 0;
+    outcode |= point[0] >= bmax[0] ? XP : 0;
+    outcode |= point[2] >= bmax[2] ? ZP : 0;
+    outcode |= point[0] < bmin[0] ? XM : 0;
+    outcode |= point[2] < bmin[2] ? ZM : 0;
+
+    switch (outcode)
+    {
+        case XP:
+            return 0;
+        case XP | ZP:
+            return 1;
+        case ZP:
+            return 2;
+        case XM | ZP:
+            return 3;
+        case XM:
+            return 4;
+        case XM | ZM:
+            return 5;
+        case ZM:
+            return 6;
+        case XP | ZM:
+            return 7;
+    }
+
+    return 0xff;
+}
+
+// Links needed by the polygons that are not off-mesh connections, counted
+// the same way as by `dtCreateNavMeshData`.
+int ground_link_count(const dtMeshTile* tile)
+{
+    auto count = 0;
+    for (auto i = 0; i < tile->header->offMeshBase; ++i)
+    {
+        const auto& poly = tile->polys[i];
+        for (auto v = 0; v < poly.vertCount; ++v)
+        {
+            ++count;
+            if (poly.neis[v] & DT_EXT_LINK)
+                count += 2;
+        }
+    }
+
+    return count;
+}
+
+bool has_connections(const dtMeshTile* tile,
+                     const std::vector<Connection>& connections,
+                     int max_link_count)
+{
+    if (tile->header->maxLinkCount != max_link_count ||
+        tile->header->offMeshConCount !=
+            static_cast<int>(connections.size()))
+        return false;
+
+    for (auto i = 0u; i < connections.size(); ++i)
+    {
+        const auto& existing = tile->offMeshCons[i];
+        const auto& connection = connections[i];
+
+        if (existing.userId != connection.id ||
+            existing.rad != connection.radius ||
+            ((existing.flags & DT_OFFMESH_CON_BIDIR) != 0) !=
+                connection.bidirectional ||
+            std::memcmp(existing.pos, connection.pos, sizeof(existing.pos)) !=
+                0)
+            return false;
+    }
+
+    return true;
+}
+
+template <typename T>
+void copy_section(unsigned char*& out, const T* in, int count, int size)
+{
+    if (count > 0)
+        std::memcpy(out, in, sizeof(T) * count);
+    out += size;
+}
+
+// Copy of the tile data without the off-mesh connections it had, with
+// `connections` appended and room for `max_link_count` links.
+unsigned char* rebuild_tile(const dtMeshTile* tile,
+                            const std::vector<Connection>& connections,
+                            int max_link_count, int* out_size)
+{
+    const auto& old = *tile->header;
+    const auto ground_polys = old.offMeshBase;
+    const auto ground_verts = old.vertCount - old.offMeshConCount * 2;
+    const auto count = static_cast<int>(connections.size());
+
+    auto header = old;
+    header.vertCount = ground_verts + count * 2;
+    header.polyCount = ground_polys + count;
+    header.maxLinkCount = max_link_count;
+    header.offMeshConCount = count;
+
+    const auto header_size = dtAlign4(sizeof(dtMeshHeader));
+    const auto verts_size = dtAlign4(sizeof(float) * 3 * header.vertCount);
+    const auto polys_size = dtAlign4(sizeof(dtPoly) * header.polyCount);
+    const auto links_size = dtAlign4(sizeof(dtLink) * header.maxLinkCount);
+    const auto detail_meshes_size =
+        dtAlign4(sizeof(dtPolyDetail) * header.detailMeshCount);
+    const auto detail_verts_size =
+        dtAlign4(sizeof(float) * 3 * header.detailVertCount);
+    const auto detail_tris_size =
+        dtAlign4(sizeof(unsigned char) * 4 * header.detailTriCount);
+    const auto bv_tree_size = dtAlign4(sizeof(dtBVNode) * header.bvNodeCount);
+    const auto off_mesh_cons_size =
+        dtAlign4(sizeof(dtOffMeshConnection) * header.offMeshConCount);
+
+    const auto size = header_size + verts_size + polys_size + links_size +
+                      detail_meshes_size + detail_verts_size +
+                      detail_tris_size + bv_tree_size + off_mesh_cons_size;
+
+    auto* data = static_cast<unsigned char*>(dtAlloc(size, DT_ALLOC_PERM));
+    if (data == nullptr)
+        return nullptr;
+    std::memset(data, 0, size);
+
+    auto* out = data;
+    copy_section(out, &header, 1, header_size);
+
+    auto* verts = reinterpret_cast<float*>(out);
+    copy_section(out, tile->verts, ground_verts * 3, verts_size);
+
+    auto* polys = reinterpret_cast<dtPoly*>(out);
+    copy_section(out, tile->polys, ground_polys, polys_size);
+
+    // Links are created by Detour when the tile is added.
+    out += links_size;
+
+    copy_section(out, tile->detailMeshes, header.detailMeshCount,
+                 detail_meshes_size);
+    copy_section(out, tile->detailVerts, header.detailVertCount * 3,
+                 detail_verts_size);
+    copy_section(out, tile->detailTris, header.detailTriCount * 4,
+                 detail_tris_size);
+    copy_section(out, tile->bvTree, header.bvNodeCount, bv_tree_size);
+
+    auto* cons = reinterpret_cast<dtOffMeshConnection*>(out);
+
+    for (auto i = 0; i < count; ++i)
+    {
+        const auto& connection = connections[i];
+        const auto vert = ground_verts + i * 2;
+
+        dtVcopy(&verts[vert * 3], &connection.pos[0]);
+        dtVcopy(&verts[(vert + 1) * 3], &connection.pos[3]);
+
+        auto& poly = polys[ground_polys + i];
+        poly.verts[0] = static_cast<unsigned short>(vert);
+        poly.verts[1] = static_cast<unsigned short>(vert + 1);
+        poly.vertCount = 2;
+        poly.flags = static_cast<unsigned short>(PolyFlags::Walkable);
+        poly.setArea(0);
+        poly.setType(DT_POLYTYPE_OFFMESH_CONNECTION);
+
+        auto& con = cons[i];
+        dtVcopy(&con.pos[0], &connection.pos[0]);
+        dtVcopy(&con.pos[3], &connection.pos[3]);
+        con.rad = connection.radius;
+        con.poly = static_cast<unsigned short>(ground_polys + i);
+        con.flags = connection.bidirectional ? DT_OFFMESH_CON_BIDIR : 0;
+        con.side = classify(&connection.pos[3], header.bmin, header.bmax);
+        con.userId = connection.id;
+    }
+
+    *out_size = size;
+    return data;
+}
+
+bool replace_tile(dtNavMesh& mesh, const dtMeshTile* tile,
+                  const std::vector<Connection>& connections,
+                  int max_link_count)
+{
+    int size;
+    auto* data = rebuild_tile(tile, connections, max_link_count, &size);
+    if (data == nullptr)
+        return false;
+
+    const auto ref = mesh.getTileRef(tile);
+    const auto flags = tile->flags;
+
+    // Detour must not free the old data when removing the tile, so that it
+    // can be added back if adding the rebuilt tile fails.
+    const_cast<dtMeshTile*>(tile)->flags &= ~DT_TILE_FREE_DATA;
+
+    unsigned char* old_data;
+    int old_size;
+    if (dtStatusFailed(mesh.removeTile(ref, &old_data, &old_size)))
+    {
+        const_cast<dtMeshTile*>(tile)->flags = flags;
+        dtFree(data);
+        return false;
+    }
+
+    if (dtStatusFailed(
+            mesh.addTile(data, size, DT_TILE_FREE_DATA, ref, nullptr)))
+    {
+        dtFree(data);
+        mesh.addTile(old_data, old_size, flags, ref, nullptr);
+        return false;
+    }
+
+    if (flags & DT_TILE_FREE_DATA)
+        dtFree(old_data);
+
+    return true;
+}
+} // namespace
+
+extern "C" {
+
+// Replaces the off-mesh connections of every loaded tile with the ones in
+// `connections` that start inside of the tile.
+//
+// Only tiles whose connections change are rebuilt, so this is cheap to call
+// again after loading more ADTs. Detour only attaches the end of a connection
+// to polygons in the same or a neighbouring tile.
+std::uint8_t
+pathfind_set_off_mesh_connections(const pathfind::Map* map,
+                                  const OffMeshConnection* connections,
+                                  unsigned int amount_of_connections)
+{
+    try
+    {
+        // The map itself is never const, namigator only hands out const
+        // references to its navmesh.
+        auto& mesh = const_cast<dtNavMesh&>(map->GetNavMesh());
+
+        std::vector<Connection> all;
+        all.reserve(amount_of_connections);
+        for (auto i = 0u; i < amount_of_connections; ++i)
+        {
+            const auto& c = connections[i];
+
+            Connection connection;
+            to_recast(c.from_x, c.from_y, c.from_z, &connection.pos[0]);
+            to_recast(c.to_x, c.to_y, c.to_z, &connection.pos[3]);
+            connection.radius = c.radius;
+            connection.id = c.id;
+            connection.bidirectional = c.bidirectional != 0;
+            all.push_back(connection);
+        }
+
+        std::vector<Connection> starting;
+        for (auto i = 0; i < mesh.getMaxTiles(); ++i)
+        {
+            const auto* tile = mesh.getTile(i);
+            if (tile == nullptr || tile->header == nullptr)
+                continue;
+
+            const auto* bmin = tile->header->bmin;
+            const auto* bmax = tile->header->bmax;
+
+            // Like `dtCreateNavMeshData`, every end of a connection inside of
+            // the tile needs two links.
+            starting.clear();
+            auto ends = 0;
+            for (const auto& connection : all)
+            {
+                if (classify(&connection.pos[0], bmin, bmax) == 0xff)
+                {
+                    starting.push_back(connection);
+                    ++ends;
+                }
+                if (classify(&connection.pos[3], bmin, bmax) == 0xff)
+                    ++ends;
+            }
+
+            const auto max_link_count = ground_link_count(tile) + ends * 2;
+            if (has_connections(tile, starting, max_link_count))
+                continue;
+
+            if (!replace_tile(mesh, tile, starting, max_link_count))
+                return FAILED_TO_ADD_OFF_MESH_CONNECTIONS;
+        }
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+}
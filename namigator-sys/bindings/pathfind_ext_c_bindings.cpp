@@ -0,0 +1,568 @@
+// C bindings for functionality that the vendored namigator does not expose
+// through `pathfind/pathfind_c_bindings.cpp`.
+//
+// The result codes returned from these functions must be kept in sync with
+// the constants in `src/lib.rs`.
+
+#include "Common.hpp"
+#include "pathfind/Map.hpp"
+
+#include "DetourNavMesh.h"
+#include "DetourNavMeshQuery.h"
+
+#include <algorithm>
+#include <cfloat>
+#include <cstdint>
+#include <limits>
+#include <vector>
+
+namespace
+{
+constexpr std::uint8_t SUCCESS = 0;
+constexpr std::uint8_t BUFFER_TOO_SMALL = 82;
+constexpr std::uint8_t UNKNOWN_PATH = 83;
+constexpr std::uint8_t UNABLE_TO_FIND_RANDOM_POINT_IN_CIRCLE = 88;
+constexpr std::uint8_t NO_POLYGON_NEAR_POINT = 90;
+constexpr std::uint8_t NAVMESH_QUERY_FAILED = 91;
+constexpr std::uint8_t UNKNOWN_EXCEPTION = 0xFF;
+
+// Extents used when snapping a position to the navmesh, same as namigator.
+constexpr float SEARCH_EXTENTS[] = {5.f, 5.f, 5.f};
+
+void to_recast(float x, float y, float z, float* out)
+{
+    out[0] = -y;
+    out[1] = z;
+    out[2] = -x;
+}
+
+void from_recast(const float* in, float* out_x, float* out_y, float* out_z)
+{
+    *out_x = -in[2];
+    *out_y = -in[0];
+    *out_z = in[1];
+}
+
+dtQueryFilter query_filter()
+{
+    dtQueryFilter filter;
+    filter.setIncludeFlags(static_cast<unsigned short>(PolyFlags::Walkable));
+    filter.setExcludeFlags(static_cast<unsigned short>(PolyFlags::Steep));
+    return filter;
+}
+
+// Maximum amount of polygons visited by a single raycast.
+constexpr int MAX_RAYCAST_POLYGONS = 256;
+
+// Maximum amount of polygons in a path corridor and points in a path.
+constexpr int MAX_PATH_POLYGONS = 4096;
+
+// Must be kept in sync with `PathPoint` in `src/lib.rs`.
+struct PathPoint
+{
+    float x;
+    float y;
+    float z;
+    std::uint64_t polygon;
+    std::uint16_t flags;
+    std::uint8_t area;
+    std::uint8_t path_flags;
+    std::uint32_t connection;
+};
+
+// Must be kept in sync with `NavmeshTileInfo` in `src/lib.rs`.
+struct NavmeshTileInfo
+{
+    std::uint32_t index;
+    std::int32_t x;
+    std::int32_t y;
+    std::int32_t layer;
+    std::uint32_t vertex_count;
+    std::uint32_t polygon_count;
+};
+
+// Must be kept in sync with `NavmeshPolygon` in `src/lib.rs`.
+struct NavmeshPolygon
+{
+    std::uint64_t polygon;
+    std::uint64_t neighbours[DT_VERTS_PER_POLYGON];
+    std::uint16_t vertices[DT_VERTS_PER_POLYGON];
+    std::uint16_t flags;
+    std::uint8_t vertex_count;
+    std::uint8_t area;
+    std::uint8_t off_mesh_connection;
+};
+
+constexpr std::uint8_t TILE_NOT_FOUND_FOR_REQUESTED = 49;
+
+const dtMeshTile* loaded_tile(const dtNavMesh& mesh, std::uint32_t index)
+{
+    if (index >= static_cast<std::uint32_t>(mesh.getMaxTiles()))
+        return nullptr;
+
+    const auto* tile = mesh.getTile(static_cast<int>(index));
+    if (tile == nullptr || tile->header == nullptr)
+        return nullptr;
+
+    return tile;
+}
+
+// Detour only accepts a plain function pointer as random source, so the
+// caller provided callback is stored for the duration of a single query.
+using random_callback = float (*)(void*);
+
+thread_local random_callback t_random = nullptr;
+thread_local void* t_random_state = nullptr;
+
+float random_trampoline()
+{
+    return t_random(t_random_state);
+}
+
+struct RandomScope
+{
+    RandomScope(random_callback random, void* random_state)
+    {
+        t_random = random;
+        t_random_state = random_state;
+    }
+
+    ~RandomScope()
+    {
+        t_random = nullptr;
+        t_random_state = nullptr;
+    }
+};
+
+std::uint8_t random_point_around_circle(const pathfind::Map* map, float x,
+                                        float y, float z, float radius,
+                                        random_callback random,
+                                        void* random_state, float* out_x,
+                                        float* out_y, float* out_z)
+{
+    const auto& query = map->GetNavMeshQuery();
+    const auto filter = query_filter();
+
+    float center[3];
+    to_recast(x, y, z, center);
+
+    dtPolyRef start_ref;
+    float nearest[3];
+    if (dtStatusFailed(query.findNearestPoly(center, SEARCH_EXTENTS, &filter,
+                                             &start_ref, nearest)) ||
+        start_ref == 0)
+        return UNABLE_TO_FIND_RANDOM_POINT_IN_CIRCLE;
+
+    RandomScope scope(random, random_state);
+
+    dtPolyRef random_ref;
+    float random_point[3];
+    if (dtStatusFailed(query.findRandomPointAroundCircle(
+            start_ref, nearest, radius, &filter, random_trampoline,
+            &random_ref, random_point)))
+        return UNABLE_TO_FIND_RANDOM_POINT_IN_CIRCLE;
+
+    from_recast(random_point, out_x, out_y, out_z);
+
+    return SUCCESS;
+}
+} // namespace
+
+extern "C" {
+
+// Same query as `pathfind_find_random_point_around_circle` except that the
+// randomness comes from the caller instead of the C library's `rand`.
+//
+// Detour only visits polygons connected to the polygon nearest to the start,
+// so the point can always be walked to from the start.
+std::uint8_t pathfind_find_random_point_around_circle_with_random(
+    const pathfind::Map* map, float x, float y, float z, float radius,
+    random_callback random, void* random_state, float* out_x, float* out_y,
+    float* out_z)
+{
+    try
+    {
+        return random_point_around_circle(map, x, y, z, radius, random,
+                                          random_state, out_x, out_y, out_z);
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+std::uint8_t pathfind_raycast(const pathfind::Map* map, float start_x,
+                              float start_y, float start_z, float stop_x,
+                              float stop_y, float stop_z, float* out_x,
+                              float* out_y, float* out_z, std::uint8_t* out_hit)
+{
+    try
+    {
+        const auto& query = map->GetNavMeshQuery();
+        const auto filter = query_filter();
+
+        float start[3];
+        float stop[3];
+        to_recast(start_x, start_y, start_z, start);
+        to_recast(stop_x, stop_y, stop_z, stop);
+
+        dtPolyRef start_ref;
+        float nearest[3];
+        if (dtStatusFailed(query.findNearestPoly(start, SEARCH_EXTENTS,
+                                                 &filter, &start_ref,
+                                                 nearest)) ||
+            start_ref == 0)
+            return NO_POLYGON_NEAR_POINT;
+
+        float t;
+        float hit_normal[3];
+        dtPolyRef polygons[MAX_RAYCAST_POLYGONS];
+        int polygon_count;
+        if (dtStatusFailed(query.raycast(start_ref, nearest, stop, &filter, &t,
+                                         hit_normal, polygons,
+                                         &polygon_count,
+                                         MAX_RAYCAST_POLYGONS)) ||
+            polygon_count == 0)
+            return NAVMESH_QUERY_FAILED;
+
+        // t is FLT_MAX when the ray reached the stop position
+        const auto hit = t <= 1.f;
+        if (!hit)
+            t = 1.f;
+
+        float position[3];
+        dtVlerp(position, nearest, stop, t);
+
+        // The ray is cast in 2D, so the height has to be found on the
+        // polygon that it ended on.
+        float height;
+        if (dtStatusSucceed(query.getPolyHeight(polygons[polygon_count - 1],
+                                                position, &height)))
+            position[1] = height;
+
+        from_recast(position, out_x, out_y, out_z);
+        *out_hit = hit ? 1 : 0;
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+std::uint8_t pathfind_find_path_points(const pathfind::Map* map, float start_x,
+                                       float start_y, float start_z,
+                                       float stop_x, float stop_y,
+                                       float stop_z, PathPoint* buffer,
+                                       unsigned int buffer_length,
+                                       unsigned int* amount_of_points)
+{
+    try
+    {
+        const auto& mesh = map->GetNavMesh();
+        const auto& query = map->GetNavMeshQuery();
+        const auto filter = query_filter();
+
+        float start[3];
+        float stop[3];
+        to_recast(start_x, start_y, start_z, start);
+        to_recast(stop_x, stop_y, stop_z, stop);
+
+        dtPolyRef start_ref;
+        dtPolyRef stop_ref;
+        float nearest_start[3];
+        float nearest_stop[3];
+        if (dtStatusFailed(query.findNearestPoly(start, SEARCH_EXTENTS,
+                                                 &filter, &start_ref,
+                                                 nearest_start)) ||
+            start_ref == 0 ||
+            dtStatusFailed(query.findNearestPoly(stop, SEARCH_EXTENTS, &filter,
+                                                 &stop_ref, nearest_stop)) ||
+            stop_ref == 0)
+            return NO_POLYGON_NEAR_POINT;
+
+        static thread_local dtPolyRef polygons[MAX_PATH_POLYGONS];
+        int polygon_count;
+        if (dtStatusFailed(query.findPath(start_ref, stop_ref, nearest_start,
+                                          nearest_stop, &filter, polygons,
+                                          &polygon_count,
+                                          MAX_PATH_POLYGONS)) ||
+            polygon_count == 0 || polygons[polygon_count - 1] != stop_ref)
+            return UNKNOWN_PATH;
+
+        static thread_local float points[MAX_PATH_POLYGONS * 3];
+        static thread_local unsigned char path_flags[MAX_PATH_POLYGONS];
+        static thread_local dtPolyRef point_polygons[MAX_PATH_POLYGONS];
+        int point_count;
+        if (dtStatusFailed(query.findStraightPath(
+                nearest_start, nearest_stop, polygons, polygon_count, points,
+                path_flags, point_polygons, &point_count, MAX_PATH_POLYGONS,
+                DT_STRAIGHTPATH_AREA_CROSSINGS)))
+            return UNKNOWN_PATH;
+
+        *amount_of_points = static_cast<unsigned int>(point_count);
+        if (static_cast<unsigned int>(point_count) > buffer_length)
+            return BUFFER_TOO_SMALL;
+
+        for (auto i = 0; i < point_count; ++i)
+        {
+            auto& point = buffer[i];
+            from_recast(&points[i * 3], &point.x, &point.y, &point.z);
+            point.polygon = point_polygons[i];
+            point.path_flags = path_flags[i];
+            point.flags = 0;
+            point.area = 0;
+            point.connection = 0;
+
+            const dtMeshTile* tile;
+            const dtPoly* poly;
+            if (point.polygon != 0 &&
+                dtStatusSucceed(mesh.getTileAndPolyByRef(point.polygon, &tile,
+                                                         &poly)))
+            {
+                point.flags = poly->flags;
+                point.area = poly->getArea();
+
+                if (poly->getType() == DT_POLYTYPE_OFFMESH_CONNECTION)
+                {
+                    const auto index = static_cast<int>(poly - tile->polys) -
+                                       tile->header->offMeshBase;
+                    point.connection = tile->offMeshCons[index].userId;
+                }
+            }
+        }
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+std::uint8_t pathfind_distance_to_wall(const pathfind::Map* map, float x,
+                                       float y, float z, float max_radius,
+                                       float* out_distance, float* out_hit_x,
+                                       float* out_hit_y, float* out_hit_z,
+                                       float* out_normal_x,
+                                       float* out_normal_y,
+                                       float* out_normal_z)
+{
+    try
+    {
+        const auto& query = map->GetNavMeshQuery();
+        const auto filter = query_filter();
+
+        float center[3];
+        to_recast(x, y, z, center);
+
+        dtPolyRef start_ref;
+        float nearest[3];
+        if (dtStatusFailed(query.findNearestPoly(center, SEARCH_EXTENTS,
+                                                 &filter, &start_ref,
+                                                 nearest)) ||
+            start_ref == 0)
+            return NO_POLYGON_NEAR_POINT;
+
+        float hit_position[3];
+        float hit_normal[3];
+        if (dtStatusFailed(query.findDistanceToWall(
+                start_ref, nearest, max_radius, &filter, out_distance,
+                hit_position, hit_normal)))
+            return NAVMESH_QUERY_FAILED;
+
+        from_recast(hit_position, out_hit_x, out_hit_y, out_hit_z);
+        from_recast(hit_normal, out_normal_x, out_normal_y, out_normal_z);
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+std::uint8_t pathfind_navmesh_tiles(const pathfind::Map* map,
+                                    NavmeshTileInfo* buffer,
+                                    unsigned int buffer_length,
+                                    unsigned int* amount_of_tiles)
+{
+    try
+    {
+        const auto& mesh = map->GetNavMesh();
+
+        unsigned int count = 0;
+        for (auto i = 0; i < mesh.getMaxTiles(); ++i)
+        {
+            const auto* tile = loaded_tile(mesh, static_cast<std::uint32_t>(i));
+            if (tile == nullptr)
+                continue;
+
+            if (count < buffer_length)
+            {
+                auto& info = buffer[count];
+                info.index = static_cast<std::uint32_t>(i);
+                info.x = tile->header->x;
+                info.y = tile->header->y;
+                info.layer = tile->header->layer;
+                info.vertex_count =
+                    static_cast<std::uint32_t>(tile->header->vertCount);
+                info.polygon_count =
+                    static_cast<std::uint32_t>(tile->header->polyCount);
+            }
+
+            ++count;
+        }
+
+        *amount_of_tiles = count;
+        if (count > buffer_length)
+            return BUFFER_TOO_SMALL;
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+std::uint8_t pathfind_navmesh_tile_vertices(const pathfind::Map* map,
+                                            std::uint32_t tile_index,
+                                            float* buffer,
+                                            unsigned int buffer_length,
+                                            unsigned int* amount_of_vertices)
+{
+    try
+    {
+        const auto* tile = loaded_tile(map->GetNavMesh(), tile_index);
+        if (tile == nullptr)
+            return TILE_NOT_FOUND_FOR_REQUESTED;
+
+        const auto count =
+            static_cast<unsigned int>(tile->header->vertCount);
+
+        *amount_of_vertices = count;
+        if (count > buffer_length)
+            return BUFFER_TOO_SMALL;
+
+        for (auto i = 0u; i < count; ++i)
+            from_recast(&tile->verts[i * 3], &buffer[i * 3],
+                        &buffer[i * 3 + 1], &buffer[i * 3 + 2]);
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+std::uint8_t pathfind_navmesh_tile_polygons(const pathfind::Map* map,
+                                            std::uint32_t tile_index,
+                                            NavmeshPolygon* buffer,
+                                            unsigned int buffer_length,
+                                            unsigned int* amount_of_polygons)
+{
+    try
+    {
+        const auto& mesh = map->GetNavMesh();
+        const auto* tile = loaded_tile(mesh, tile_index);
+        if (tile == nullptr)
+            return TILE_NOT_FOUND_FOR_REQUESTED;
+
+        const auto count =
+            static_cast<unsigned int>(tile->header->polyCount);
+
+        *amount_of_polygons = count;
+        if (count > buffer_length)
+            return BUFFER_TOO_SMALL;
+
+        const auto base = mesh.getPolyRefBase(tile);
+
+        for (auto i = 0u; i < count; ++i)
+        {
+            const auto& poly = tile->polys[i];
+            auto& out = buffer[i];
+
+            out.polygon = base | static_cast<dtPolyRef>(i);
+            out.flags = poly.flags;
+            out.vertex_count = poly.vertCount;
+            out.area = poly.getArea();
+            out.off_mesh_connection =
+                poly.getType() == DT_POLYTYPE_OFFMESH_CONNECTION ? 1 : 0;
+
+            for (auto v = 0; v < DT_VERTS_PER_POLYGON; ++v)
+            {
+                out.vertices[v] = poly.verts[v];
+                out.neighbours[v] = 0;
+            }
+
+            for (auto link = poly.firstLink; link != DT_NULL_LINK;
+                 link = tile->links[link].next)
+            {
+                const auto edge = tile->links[link].edge;
+                if (edge < DT_VERTS_PER_POLYGON)
+                    out.neighbours[edge] = tile->links[link].ref;
+            }
+        }
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+// Writes the highest height at `rows` by `columns` positions into `buffer`,
+// row by row, or NaN for positions without any heights.
+//
+// The position of `row`, `column` is `start_x + row * step_x`,
+// `start_y + column * step_y`, so that a whole ADT can be sampled in a single
+// call instead of one call per position.
+std::uint8_t pathfind_find_highest_heights(const pathfind::Map* map,
+                                           float start_x, float start_y,
+                                           float step_x, float step_y,
+                                           unsigned int rows,
+                                           unsigned int columns, float* buffer)
+{
+    try
+    {
+        std::vector<float> heights;
+
+        for (auto row = 0u; row < rows; ++row)
+        {
+            for (auto column = 0u; column < columns; ++column)
+            {
+                const auto x = start_x + static_cast<float>(row) * step_x;
+                const auto y = start_y + static_cast<float>(column) * step_y;
+                auto& out = buffer[row * columns + column];
+
+                heights.clear();
+                // Positions outside of the loaded ADTs throw, which only means
+                // that they have no heights.
+                try
+                {
+                    if (!map->FindHeights(x, y, heights))
+                        heights.clear();
+                }
+                catch (...)
+                {
+                    heights.clear();
+                }
+
+                out = heights.empty()
+                          ? std::numeric_limits<float>::quiet_NaN()
+                          : *std::max_element(heights.begin(), heights.end());
+            }
+        }
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+}
@@ -0,0 +1,36 @@
+// C binding for atomically swapping two directories, used to replace a built
+// map without a moment where it is missing from the output.
+
+#if defined(__linux__)
+#include <fcntl.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+#elif defined(__APPLE__)
+#include <cstdio>
+#endif
+
+extern "C" {
+
+// Atomically exchanges `a` and `b`, which must both exist.
+//
+// Returns false without changing anything when the platform or file system can
+// not exchange paths atomically, in which case the caller has to fall back to
+// separate renames.
+bool rename_exchange(const char* a, const char* b)
+{
+#if defined(__linux__) && defined(SYS_renameat2)
+    // The glibc wrapper for renameat2 was only added in 2.28, so the system
+    // call is used directly. Older kernels fail with ENOSYS and file systems
+    // without support with EINVAL, both leave the paths untouched.
+    constexpr unsigned int RENAME_EXCHANGE_FLAG = 1 << 1;
+    return syscall(SYS_renameat2, AT_FDCWD, a, AT_FDCWD, b,
+                   RENAME_EXCHANGE_FLAG) == 0;
+#elif defined(__APPLE__)
+    return renamex_np(a, b, RENAME_SWAP) == 0;
+#else
+    (void)a;
+    (void)b;
+    return false;
+#endif
+}
+}
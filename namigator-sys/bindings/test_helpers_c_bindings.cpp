@@ -0,0 +1,84 @@
+// C bindings only used by the tests of the namigator crate, compiled with the
+// `test-helpers` feature.
+//
+// The result codes returned from these functions must be kept in sync with
+// the constants in `src/lib.rs`.
+
+#include "StormLib.h"
+#include "parser/MpqManager.hpp"
+#include "utility/BinaryStream.hpp"
+
+#include <cstdint>
+#include <memory>
+
+namespace
+{
+constexpr std::uint8_t SUCCESS = 0;
+constexpr std::uint8_t ERROR_IN_SFILEOPENFILEX = 76;
+constexpr std::uint8_t COULD_NOT_CREATE_MPQ = 95;
+constexpr std::uint8_t UNKNOWN_EXCEPTION = 0xFF;
+} // namespace
+
+extern "C" {
+
+// Creates the archive `path` containing only `name` with the contents of
+// `data`, replacing any existing file.
+std::uint8_t mpq_create_archive(const char* path, const char* name,
+                                const std::uint8_t* data,
+                                unsigned int data_length)
+{
+    try
+    {
+        HANDLE archive = nullptr;
+        if (!SFileCreateArchive(path,
+                                MPQ_CREATE_ARCHIVE_V1 | MPQ_CREATE_LISTFILE,
+                                16, &archive))
+            return COULD_NOT_CREATE_MPQ;
+
+        HANDLE file = nullptr;
+        auto success = SFileCreateFile(archive, name, 0, data_length, 0,
+                                       MPQ_FILE_COMPRESS, &file);
+        if (success)
+        {
+            success = data_length == 0 ||
+                      SFileWriteFile(file, data, data_length,
+                                     MPQ_COMPRESSION_ZLIB);
+            success = SFileFinishFile(file) && success;
+        }
+
+        success = SFileCloseArchive(archive) && success;
+
+        return success ? SUCCESS : COULD_NOT_CREATE_MPQ;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+// Reads the first four bytes of `name` from the archive that takes
+// precedence among the archives of `data_path`.
+//
+// Only meant for checking that the archives are searched in the same order
+// as by `MpqArchiveSet` in the namigator crate.
+std::uint8_t mpq_manager_read_u32(const char* data_path, const char* name,
+                                  std::uint32_t* out_value)
+{
+    try
+    {
+        parser::MpqManager manager;
+        manager.Initialize(data_path);
+
+        std::unique_ptr<utility::BinaryStream> file(manager.OpenFile(name));
+        if (!file)
+            return ERROR_IN_SFILEOPENFILEX;
+
+        *out_value = file->Read<std::uint32_t>();
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+}
@@ -0,0 +1,464 @@
+// C bindings for navmeshes of transports, such as boats and zeppelins.
+//
+// Transports move around the world so their decks can not be part of the
+// tiled map navmesh. Instead a small single tile navmesh is built from the
+// triangles of the transport model, in coordinates local to the transport.
+//
+// The result codes returned from these functions must be kept in sync with
+// the constants in `src/lib.rs`.
+
+#include "Common.hpp"
+#include "model.hpp"
+#include "utility/AABBTree.hpp"
+
+#include "DetourNavMesh.h"
+#include "DetourNavMeshBuilder.h"
+#include "DetourNavMeshQuery.h"
+#include "Recast.h"
+
+#include <cfloat>
+#include <cmath>
+#include <cstdint>
+#include <cstring>
+#include <memory>
+#include <vector>
+
+// Opaque to Rust, so outside of the anonymous namespace only because it is
+// part of the signatures of the exported functions.
+namespace transport_bindings
+{
+struct PathVertex
+{
+    float x;
+    float y;
+    float z;
+};
+
+struct Transport
+{
+    dtNavMesh* mesh = nullptr;
+    dtNavMeshQuery query;
+
+    // Model triangles in Recast coordinates, used for line of sight.
+    std::vector<float> vertices;
+    std::vector<int> indices;
+
+    ~Transport() { dtFreeNavMesh(mesh); }
+};
+} // namespace transport_bindings
+
+using transport_bindings::PathVertex;
+using transport_bindings::Transport;
+
+namespace
+{
+constexpr std::uint8_t SUCCESS = 0;
+constexpr std::uint8_t COULD_NOT_DESERIALIZE_DOODAD = 46;
+constexpr std::uint8_t RECAST_FAILURE = 81;
+constexpr std::uint8_t BUFFER_TOO_SMALL = 82;
+constexpr std::uint8_t UNKNOWN_PATH = 83;
+constexpr std::uint8_t NO_POLYGON_NEAR_POINT = 90;
+constexpr std::uint8_t UNKNOWN_EXCEPTION = 0xFF;
+
+constexpr float SEARCH_EXTENTS[] = {2.f, 4.f, 2.f};
+
+// Transports are small, so they use a finer grid than the map tiles.
+constexpr float CELL_SIZE = 0.2f;
+constexpr float CELL_HEIGHT = 0.2f;
+constexpr float WALKABLE_SLOPE = 50.f;
+constexpr float WALKABLE_HEIGHT = 1.6f;
+constexpr float WALKABLE_CLIMB = 1.f;
+constexpr float WALKABLE_RADIUS = 0.3f;
+
+// Same as `AreaFlags::WMO` in namigator.
+constexpr unsigned char AREA_WMO = 0x04;
+
+constexpr int MAX_NODES = 2048;
+constexpr int MAX_PATH_POLYGONS = 1024;
+
+void to_recast(float x, float y, float z, float* out)
+{
+    out[0] = -y;
+    out[1] = z;
+    out[2] = -x;
+}
+
+void from_recast(const float* in, float* out_x, float* out_y, float* out_z)
+{
+    *out_x = -in[2];
+    *out_y = -in[0];
+    *out_z = in[1];
+}
+
+dtQueryFilter query_filter()
+{
+    dtQueryFilter filter;
+    filter.setIncludeFlags(static_cast<unsigned short>(PolyFlags::Walkable));
+    return filter;
+}
+
+std::uint8_t build(Transport& transport)
+{
+    const auto* verts = transport.vertices.data();
+    const auto nverts = static_cast<int>(transport.vertices.size() / 3);
+    const auto* tris = transport.indices.data();
+    const auto ntris = static_cast<int>(transport.indices.size() / 3);
+
+    if (nverts == 0 || ntris == 0)
+        return RECAST_FAILURE;
+
+    rcContext ctx(false);
+
+    rcConfig config;
+    std::memset(&config, 0, sizeof(config));
+    config.cs = CELL_SIZE;
+    config.ch = CELL_HEIGHT;
+    config.walkableSlopeAngle = WALKABLE_SLOPE;
+    config.walkableHeight =
+        static_cast<int>(std::ceil(WALKABLE_HEIGHT / CELL_HEIGHT));
+    config.walkableClimb =
+        static_cast<int>(std::floor(WALKABLE_CLIMB / CELL_HEIGHT));
+    config.walkableRadius =
+        static_cast<int>(std::ceil(WALKABLE_RADIUS / CELL_SIZE));
+    config.maxEdgeLen = static_cast<int>(12.f / CELL_SIZE);
+    config.maxSimplificationError = 1.3f;
+    config.minRegionArea = 8 * 8;
+    config.mergeRegionArea = 20 * 20;
+    config.maxVertsPerPoly = DT_VERTS_PER_POLYGON;
+    config.detailSampleDist = CELL_SIZE * 6.f;
+    config.detailSampleMaxError = CELL_HEIGHT;
+
+    rcCalcBounds(verts, nverts, config.bmin, config.bmax);
+    rcCalcGridSize(config.bmin, config.bmax, config.cs, &config.width,
+                   &config.height);
+
+    std::unique_ptr<rcHeightfield, decltype(&rcFreeHeightField)> solid(
+        rcAllocHeightfield(), rcFreeHeightField);
+    if (!solid ||
+        !rcCreateHeightfield(&ctx, *solid, config.width, config.height,
+                             config.bmin, config.bmax, config.cs, config.ch))
+        return RECAST_FAILURE;
+
+    std::vector<unsigned char> areas(ntris, 0);
+    rcMarkWalkableTriangles(&ctx, config.walkableSlopeAngle, verts, nverts,
+                            tris, ntris, areas.data());
+    if (!rcRasterizeTriangles(&ctx, verts, nverts, tris, areas.data(), ntris,
+                              *solid, config.walkableClimb))
+        return RECAST_FAILURE;
+
+    rcFilterLowHangingWalkableObstacles(&ctx, config.walkableClimb, *solid);
+    rcFilterLedgeSpans(&ctx, config.walkableHeight, config.walkableClimb,
+                       *solid);
+    rcFilterWalkableLowHeightSpans(&ctx, config.walkableHeight, *solid);
+
+    std::unique_ptr<rcCompactHeightfield, decltype(&rcFreeCompactHeightfield)>
+        compact(rcAllocCompactHeightfield(), rcFreeCompactHeightfield);
+    if (!compact ||
+        !rcBuildCompactHeightfield(&ctx, config.walkableHeight,
+                                   config.walkableClimb, *solid, *compact) ||
+        !rcErodeWalkableArea(&ctx, config.walkableRadius, *compact) ||
+        !rcBuildDistanceField(&ctx, *compact) ||
+        !rcBuildRegions(&ctx, *compact, 0, config.minRegionArea,
+                        config.mergeRegionArea))
+        return RECAST_FAILURE;
+
+    std::unique_ptr<rcContourSet, decltype(&rcFreeContourSet)> contours(
+        rcAllocContourSet(), rcFreeContourSet);
+    if (!contours ||
+        !rcBuildContours(&ctx, *compact, config.maxSimplificationError,
+                         config.maxEdgeLen, *contours))
+        return RECAST_FAILURE;
+
+    std::unique_ptr<rcPolyMesh, decltype(&rcFreePolyMesh)> poly_mesh(
+        rcAllocPolyMesh(), rcFreePolyMesh);
+    if (!poly_mesh || !rcBuildPolyMesh(&ctx, *contours, config.maxVertsPerPoly,
+                                       *poly_mesh))
+        return RECAST_FAILURE;
+
+    std::unique_ptr<rcPolyMeshDetail, decltype(&rcFreePolyMeshDetail)>
+        detail_mesh(rcAllocPolyMeshDetail(), rcFreePolyMeshDetail);
+    if (!detail_mesh ||
+        !rcBuildPolyMeshDetail(&ctx, *poly_mesh, *compact,
+                               config.detailSampleDist,
+                               config.detailSampleMaxError, *detail_mesh))
+        return RECAST_FAILURE;
+
+    for (auto i = 0; i < poly_mesh->npolys; ++i)
+    {
+        poly_mesh->areas[i] = AREA_WMO;
+        poly_mesh->flags[i] =
+            static_cast<unsigned short>(PolyFlags::Walkable);
+    }
+
+    dtNavMeshCreateParams params;
+    std::memset(&params, 0, sizeof(params));
+    params.verts = poly_mesh->verts;
+    params.vertCount = poly_mesh->nverts;
+    params.polys = poly_mesh->polys;
+    params.polyAreas = poly_mesh->areas;
+    params.polyFlags = poly_mesh->flags;
+    params.polyCount = poly_mesh->npolys;
+    params.nvp = poly_mesh->nvp;
+    params.detailMeshes = detail_mesh->meshes;
+    params.detailVerts = detail_mesh->verts;
+    params.detailVertsCount = detail_mesh->nverts;
+    params.detailTris = detail_mesh->tris;
+    params.detailTriCount = detail_mesh->ntris;
+    params.walkableHeight = WALKABLE_HEIGHT;
+    params.walkableRadius = WALKABLE_RADIUS;
+    params.walkableClimb = WALKABLE_CLIMB;
+    rcVcopy(params.bmin, poly_mesh->bmin);
+    rcVcopy(params.bmax, poly_mesh->bmax);
+    params.cs = config.cs;
+    params.ch = config.ch;
+    params.buildBvTree = true;
+
+    unsigned char* data = nullptr;
+    int data_size = 0;
+    if (!dtCreateNavMeshData(&params, &data, &data_size))
+        return RECAST_FAILURE;
+
+    transport.mesh = dtAllocNavMesh();
+    if (!transport.mesh ||
+        dtStatusFailed(
+            transport.mesh->init(data, data_size, DT_TILE_FREE_DATA)))
+    {
+        dtFree(data);
+        return RECAST_FAILURE;
+    }
+
+    if (dtStatusFailed(transport.query.init(transport.mesh, MAX_NODES)))
+        return RECAST_FAILURE;
+
+    return SUCCESS;
+}
+
+Transport* finish(std::unique_ptr<Transport> transport,
+                  std::uint8_t* out_result)
+{
+    *out_result = build(*transport);
+    if (*out_result != SUCCESS)
+        return nullptr;
+
+    return transport.release();
+}
+
+// Möller-Trumbore, returns whether the segment from `start` to `stop`
+// intersects the triangle strictly between the two points.
+bool segment_hits_triangle(const float* start, const float* stop,
+                           const float* v0, const float* v1, const float* v2)
+{
+    constexpr float EPSILON = 1e-6f;
+
+    float dir[3];
+    float edge1[3];
+    float edge2[3];
+    rcVsub(dir, stop, start);
+    rcVsub(edge1, v1, v0);
+    rcVsub(edge2, v2, v0);
+
+    float p[3];
+    rcVcross(p, dir, edge2);
+    const auto det = rcVdot(edge1, p);
+    if (std::fabs(det) < EPSILON)
+        return false;
+
+    const auto inv_det = 1.f / det;
+
+    float s[3];
+    rcVsub(s, start, v0);
+    const auto u = rcVdot(s, p) * inv_det;
+    if (u < 0.f || u > 1.f)
+        return false;
+
+    float q[3];
+    rcVcross(q, s, edge1);
+    const auto v = rcVdot(dir, q) * inv_det;
+    if (v < 0.f || u + v > 1.f)
+        return false;
+
+    const auto t = rcVdot(edge2, q) * inv_det;
+    return t > EPSILON && t < 1.f - EPSILON;
+}
+} // namespace
+
+extern "C" {
+
+Transport* pathfind_transport_new(const float* vertices,
+                                  unsigned int amount_of_vertices,
+                                  const unsigned int* indices,
+                                  unsigned int amount_of_indices,
+                                  std::uint8_t* out_result)
+{
+    try
+    {
+        if (amount_of_indices % 3 != 0)
+        {
+            *out_result = RECAST_FAILURE;
+            return nullptr;
+        }
+
+        auto transport = std::make_unique<Transport>();
+
+        transport->vertices.resize(amount_of_vertices * 3);
+        for (auto i = 0u; i < amount_of_vertices; ++i)
+            to_recast(vertices[i * 3], vertices[i * 3 + 1],
+                      vertices[i * 3 + 2], &transport->vertices[i * 3]);
+
+        transport->indices.reserve(amount_of_indices);
+        for (auto i = 0u; i < amount_of_indices; ++i)
+        {
+            if (indices[i] >= amount_of_vertices)
+            {
+                *out_result = RECAST_FAILURE;
+                return nullptr;
+            }
+
+            transport->indices.push_back(static_cast<int>(indices[i]));
+        }
+
+        return finish(std::move(transport), out_result);
+    }
+    catch (...)
+    {
+        *out_result = UNKNOWN_EXCEPTION;
+        return nullptr;
+    }
+}
+
+// Loads a gameobject model written to the `BVH` directory by
+// `mapbuild_build_bvh`.
+Transport* pathfind_transport_new_from_model(const char* model_path,
+                                             std::uint8_t* out_result)
+{
+    try
+    {
+        utility::AABBTree tree;
+        if (!model::read(model_path, tree))
+        {
+            *out_result = COULD_NOT_DESERIALIZE_DOODAD;
+            return nullptr;
+        }
+
+        auto transport = std::make_unique<Transport>();
+
+        // The tree stores vertices in game coordinates.
+        const auto& vertices = tree.Vertices();
+        transport->vertices.resize(vertices.size() * 3);
+        for (auto i = 0u; i < vertices.size(); ++i)
+            to_recast(vertices[i].X, vertices[i].Y, vertices[i].Z,
+                      &transport->vertices[i * 3]);
+
+        const auto& indices = tree.Indices();
+        transport->indices.assign(indices.begin(), indices.end());
+
+        return finish(std::move(transport), out_result);
+    }
+    catch (...)
+    {
+        *out_result = UNKNOWN_EXCEPTION;
+        return nullptr;
+    }
+}
+
+void pathfind_transport_free(Transport* transport)
+{
+    delete transport;
+}
+
+std::uint8_t pathfind_transport_find_path(
+    const Transport* transport, float start_x, float start_y, float start_z,
+    float stop_x, float stop_y, float stop_z, PathVertex* buffer,
+    unsigned int buffer_length, unsigned int* amount_of_vertices)
+{
+    try
+    {
+        const auto& query = transport->query;
+        const auto filter = query_filter();
+
+        float start[3];
+        float stop[3];
+        to_recast(start_x, start_y, start_z, start);
+        to_recast(stop_x, stop_y, stop_z, stop);
+
+        dtPolyRef start_ref;
+        dtPolyRef stop_ref;
+        float nearest_start[3];
+        float nearest_stop[3];
+        if (dtStatusFailed(query.findNearestPoly(start, SEARCH_EXTENTS,
+                                                 &filter, &start_ref,
+                                                 nearest_start)) ||
+            start_ref == 0 ||
+            dtStatusFailed(query.findNearestPoly(stop, SEARCH_EXTENTS, &filter,
+                                                 &stop_ref, nearest_stop)) ||
+            stop_ref == 0)
+            return NO_POLYGON_NEAR_POINT;
+
+        dtPolyRef polygons[MAX_PATH_POLYGONS];
+        int polygon_count;
+        if (dtStatusFailed(query.findPath(start_ref, stop_ref, nearest_start,
+                                          nearest_stop, &filter, polygons,
+                                          &polygon_count,
+                                          MAX_PATH_POLYGONS)) ||
+            polygon_count == 0 || polygons[polygon_count - 1] != stop_ref)
+            return UNKNOWN_PATH;
+
+        float points[MAX_PATH_POLYGONS * 3];
+        int point_count;
+        if (dtStatusFailed(query.findStraightPath(
+                nearest_start, nearest_stop, polygons, polygon_count, points,
+                nullptr, nullptr, &point_count, MAX_PATH_POLYGONS)))
+            return UNKNOWN_PATH;
+
+        *amount_of_vertices = static_cast<unsigned int>(point_count);
+        if (static_cast<unsigned int>(point_count) > buffer_length)
+            return BUFFER_TOO_SMALL;
+
+        for (auto i = 0; i < point_count; ++i)
+            from_recast(&points[i * 3], &buffer[i].x, &buffer[i].y,
+                        &buffer[i].z);
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+
+std::uint8_t pathfind_transport_line_of_sight(const Transport* transport,
+                                              float start_x, float start_y,
+                                              float start_z, float stop_x,
+                                              float stop_y, float stop_z,
+                                              std::uint8_t* out_line_of_sight)
+{
+    try
+    {
+        float start[3];
+        float stop[3];
+        to_recast(start_x, start_y, start_z, start);
+        to_recast(stop_x, stop_y, stop_z, stop);
+
+        const auto& vertices = transport->vertices;
+        const auto& indices = transport->indices;
+
+        *out_line_of_sight = 1;
+        for (auto i = 0u; i + 2 < indices.size(); i += 3)
+        {
+            if (segment_hits_triangle(start, stop, &vertices[indices[i] * 3],
+                                      &vertices[indices[i + 1] * 3],
+                                      &vertices[indices[i + 2] * 3]))
+            {
+                *out_line_of_sight = 0;
+                break;
+            }
+        }
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+}
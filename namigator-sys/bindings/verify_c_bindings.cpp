@@ -0,0 +1,38 @@
+// C bindings for checking built files without loading them into a map.
+//
+// The result codes returned from these functions must be kept in sync with
+// the constants in `src/lib.rs`.
+
+#include "model.hpp"
+#include "utility/AABBTree.hpp"
+
+#include <cstdint>
+
+namespace
+{
+constexpr std::uint8_t SUCCESS = 0;
+constexpr std::uint8_t COULD_NOT_DESERIALIZE_DOODAD = 46;
+constexpr std::uint8_t UNKNOWN_EXCEPTION = 0xFF;
+} // namespace
+
+extern "C" {
+
+// Reads a gameobject model written to the `BVH` directory by
+// `mapbuild_build_bvh` with the same checks as
+// `pathfind_transport_new_from_model`, without building anything from it.
+std::uint8_t pathfind_verify_model(const char* model_path)
+{
+    try
+    {
+        utility::AABBTree tree;
+        if (!model::read(model_path, tree))
+            return COULD_NOT_DESERIALIZE_DOODAD;
+
+        return SUCCESS;
+    }
+    catch (...)
+    {
+        return UNKNOWN_EXCEPTION;
+    }
+}
+}
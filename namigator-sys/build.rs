@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 fn main() {
     println!("cargo:rerun-if-changed=vendor");
+    println!("cargo:rerun-if-changed=bindings");
 
     let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
     let c_lib_dir = out_dir.join("c_libs");
@@ -30,6 +31,10 @@ fn main() {
         .includes(cpp_includes())
         .out_dir(namigator_dir);
 
+    if std::env::var_os("CARGO_FEATURE_TEST_HELPERS").is_some() {
+        namigator.file(bindings_dir().join("test_helpers_c_bindings.cpp"));
+    }
+
     for def in defines() {
         c_build.define(def, None);
         pathfind.define(def, None);
@@ -68,6 +73,10 @@ fn vendor_dir() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("vendor")
 }
 
+fn bindings_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("bindings")
+}
+
 fn stormlib_src_dir() -> PathBuf {
     vendor_dir().join("stormlib").join("src")
 }
@@ -105,6 +114,10 @@ fn pathfind_files() -> Vec<PathBuf> {
         vendor_dir().join("pathfind/TemporaryObstacle.cpp"),
         vendor_dir().join("pathfind/Tile.cpp"),
         vendor_dir().join("pathfind/pathfind_c_bindings.cpp"),
+        bindings_dir().join("pathfind_ext_c_bindings.cpp"),
+        bindings_dir().join("offmesh_c_bindings.cpp"),
+        bindings_dir().join("transport_c_bindings.cpp"),
+        bindings_dir().join("verify_c_bindings.cpp"),
     ]
 }
 
@@ -202,6 +215,10 @@ fn cpp_files() -> Vec<PathBuf> {
         vendor_dir().join("stormlib/src/adpcm/adpcm.cpp"),
         vendor_dir().join("stormlib/src/huffman/huff.cpp"),
         vendor_dir().join("stormlib/src/sparse/sparse.cpp"),
+        bindings_dir().join("mpq_c_bindings.cpp"),
+        bindings_dir().join("lock_c_bindings.cpp"),
+        bindings_dir().join("rename_c_bindings.cpp"),
+        bindings_dir().join("geometry_c_bindings.cpp"),
     ]
 }
 
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod test;
 
-use core::ffi::{c_char, c_float, c_int, c_uchar, c_uint};
+use core::ffi::{c_char, c_float, c_int, c_uchar, c_uint, c_void};
 
 #[repr(C)]
 #[derive(Debug)]
@@ -10,6 +10,30 @@ pub struct Map {
     _marker: core::marker::PhantomData<(*mut u8, core::marker::PhantomPinned)>,
 }
 
+/// Navmesh of a transport such as a boat or zeppelin, in coordinates local to the transport.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Transport {
+    _data: [u8; 0],
+    _marker: core::marker::PhantomData<(*mut u8, core::marker::PhantomPinned)>,
+}
+
+/// Advisory lock on a file, held until released or until the process exits.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FileLock {
+    _data: [u8; 0],
+    _marker: core::marker::PhantomData<(*mut u8, core::marker::PhantomPinned)>,
+}
+
+/// StormLib handle of an opened MPQ archive.
+#[repr(C)]
+#[derive(Debug)]
+pub struct MpqArchive {
+    _data: [u8; 0],
+    _marker: core::marker::PhantomData<(*mut u8, core::marker::PhantomPinned)>,
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Default)]
 pub struct Vertex {
@@ -18,6 +42,89 @@ pub struct Vertex {
     pub z: f32,
 }
 
+/// Point on a path along with the navmesh polygon that the path continues through.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct PathPoint {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    /// Detour `dtPolyRef`, 0 for the last point.
+    pub polygon: u64,
+    /// Polygon flags, see the `POLY_FLAG_` constants.
+    pub flags: u16,
+    /// Polygon area, see the `AREA_` constants.
+    pub area: u8,
+    /// Detour `dtStraightPathFlags`, see the `STRAIGHT_PATH_` constants.
+    pub path_flags: u8,
+    /// Id of the [`OffMeshConnection`] when `path_flags` contains [`STRAIGHT_PATH_OFFMESH_CONNECTION`].
+    pub connection: u32,
+}
+
+/// Link between two points that is added to the navmesh by [`pathfind_set_off_mesh_connections`].
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct OffMeshConnection {
+    pub id: u32,
+    pub from_x: f32,
+    pub from_y: f32,
+    pub from_z: f32,
+    pub to_x: f32,
+    pub to_y: f32,
+    pub to_z: f32,
+    /// Distance from both ends that polygons are searched for.
+    pub radius: f32,
+    /// 1 if the connection can also be used from `to` to `from`, 0 otherwise.
+    pub bidirectional: u8,
+}
+
+/// Version of this crate, which decides the version of the bundled namigator.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the format of the nav and BVH files written by the bundled namigator.
+///
+/// Must be increased whenever an update of the bundled namigator changes what is written by
+/// `mapbuild_build_map` or `mapbuild_build_bvh`, since maps built before can no longer be loaded.
+pub const FILE_FORMAT_VERSION: u32 = 1;
+
+/// Maximum amount of vertices in a navmesh polygon, Detour `DT_VERTS_PER_POLYGON`.
+pub const VERTS_PER_POLYGON: usize = 6;
+
+/// Loaded navmesh tile.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct NavmeshTileInfo {
+    /// Index used to query the vertices and polygons of the tile.
+    pub index: u32,
+    /// Detour tile coordinates.
+    pub x: i32,
+    pub y: i32,
+    pub layer: i32,
+    pub vertex_count: u32,
+    pub polygon_count: u32,
+}
+
+/// Polygon of a navmesh tile.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct NavmeshPolygon {
+    /// Detour `dtPolyRef`.
+    pub polygon: u64,
+    /// Polygon on the other side of every edge, 0 if the edge is not connected.
+    ///
+    /// Edge `i` goes from vertex `i` to vertex `i + 1`.
+    pub neighbours: [u64; VERTS_PER_POLYGON],
+    /// Indices into the vertices of the tile, only the first `vertex_count` are valid.
+    pub vertices: [u16; VERTS_PER_POLYGON],
+    /// Polygon flags, see the `POLY_FLAG_` constants.
+    pub flags: u16,
+    pub vertex_count: u8,
+    /// Polygon area, see the `AREA_` constants.
+    pub area: u8,
+    /// 1 if the polygon is an off-mesh connection, 0 otherwise.
+    pub off_mesh_connection: u8,
+}
+
 pub const SUCCESS: u8 = 0;
 pub const UNRECOGNIZED_EXTENSION: u8 = 1;
 pub const NO_MOGP_CHUNK: u8 = 2;
@@ -132,8 +239,54 @@ pub const UNABLE_TO_FIND_RANDOM_POINT_IN_CIRCLE: u8 = 88;
 
 pub const FAILED_TO_FIND_POINT_BETWEEN_VECTORS: u8 = 89;
 
+pub const NO_POLYGON_NEAR_POINT: u8 = 90;
+pub const NAVMESH_QUERY_FAILED: u8 = 91;
+pub const FAILED_TO_ADD_OFF_MESH_CONNECTIONS: u8 = 92;
+pub const FILE_IS_LOCKED: u8 = 93;
+pub const COULD_NOT_OPEN_LOCK_FILE: u8 = 94;
+pub const COULD_NOT_CREATE_MPQ: u8 = 95;
+
 pub const UNKNOWN_EXCEPTION: u8 = 0xFF;
 
+// Mirrors `AreaFlags` from namigator's `Common.hpp`.
+pub const AREA_ADT: u8 = 0x01;
+pub const AREA_LIQUID: u8 = 0x02;
+pub const AREA_WMO: u8 = 0x04;
+pub const AREA_DOODAD: u8 = 0x08;
+
+// Mirrors `PolyFlags` from namigator's `Common.hpp`.
+pub const POLY_FLAG_WALKABLE: u16 = 0x01;
+pub const POLY_FLAG_STEEP: u16 = 0x02;
+
+// Mirrors `dtStraightPathFlags` from Detour.
+pub const STRAIGHT_PATH_START: u8 = 0x01;
+pub const STRAIGHT_PATH_END: u8 = 0x02;
+pub const STRAIGHT_PATH_OFFMESH_CONNECTION: u8 = 0x04;
+
+/// Source of randomness for queries that need it.
+///
+/// Must return a value in the range `[0.0, 1.0)`. The `state` is the pointer that is passed alongside the callback.
+pub type RandomCallback = extern "C" fn(state: *mut c_void) -> c_float;
+
+// Layers of the collision geometry passed to a `GeometryCallback`.
+pub const GEOMETRY_TERRAIN: u8 = 0;
+pub const GEOMETRY_LIQUID: u8 = 1;
+pub const GEOMETRY_WMO: u8 = 2;
+pub const GEOMETRY_DOODAD: u8 = 3;
+
+/// Receiver of the triangles of [`mapbuild_collision_geometry`].
+///
+/// `vertices` holds `amount_of_vertices` consecutive x, y and z world coordinates and `indices` holds three indices into them per triangle.
+/// Both are only valid for the duration of the call. The `state` is the pointer that is passed alongside the callback.
+pub type GeometryCallback = extern "C" fn(
+    state: *mut c_void,
+    layer: c_uchar,
+    vertices: *const c_float,
+    amount_of_vertices: c_uint,
+    indices: *const c_int,
+    amount_of_indices: c_uint,
+);
+
 #[link(name = "namigator")]
 extern "C" {
     pub fn pathfind_new_map(
@@ -249,6 +402,145 @@ extern "C" {
         out_random_z: *mut c_float,
     ) -> c_uchar;
 
+    pub fn pathfind_find_random_point_around_circle_with_random(
+        map: *const Map,
+        x: c_float,
+        y: c_float,
+        z: c_float,
+        radius: c_float,
+        random: RandomCallback,
+        random_state: *mut c_void,
+        out_random_x: *mut c_float,
+        out_random_y: *mut c_float,
+        out_random_z: *mut c_float,
+    ) -> c_uchar;
+
+    pub fn pathfind_raycast(
+        map: *const Map,
+        start_x: c_float,
+        start_y: c_float,
+        start_z: c_float,
+        stop_x: c_float,
+        stop_y: c_float,
+        stop_z: c_float,
+        out_x: *mut c_float,
+        out_y: *mut c_float,
+        out_z: *mut c_float,
+        out_hit: *mut u8,
+    ) -> c_uchar;
+
+    pub fn pathfind_find_path_points(
+        map: *const Map,
+        start_x: c_float,
+        start_y: c_float,
+        start_z: c_float,
+        stop_x: c_float,
+        stop_y: c_float,
+        stop_z: c_float,
+        buffer: *mut PathPoint,
+        buffer_length: c_uint,
+        amount_of_points: *mut c_uint,
+    ) -> c_uchar;
+
+    /// Replace the off-mesh connections of every loaded tile with the ones in `connections` that start in the tile.
+    ///
+    /// Must be called again after loading ADTs for the connections to be added to the new tiles.
+    pub fn pathfind_set_off_mesh_connections(
+        map: *const Map,
+        connections: *const OffMeshConnection,
+        amount_of_connections: c_uint,
+    ) -> c_uchar;
+
+    pub fn pathfind_distance_to_wall(
+        map: *const Map,
+        x: c_float,
+        y: c_float,
+        z: c_float,
+        max_radius: c_float,
+        out_distance: *mut c_float,
+        out_hit_x: *mut c_float,
+        out_hit_y: *mut c_float,
+        out_hit_z: *mut c_float,
+        out_normal_x: *mut c_float,
+        out_normal_y: *mut c_float,
+        out_normal_z: *mut c_float,
+    ) -> c_uchar;
+
+    pub fn pathfind_navmesh_tiles(
+        map: *const Map,
+        buffer: *mut NavmeshTileInfo,
+        buffer_length: c_uint,
+        amount_of_tiles: *mut c_uint,
+    ) -> c_uchar;
+
+    pub fn pathfind_navmesh_tile_vertices(
+        map: *const Map,
+        tile_index: u32,
+        buffer: *mut c_float,
+        buffer_length: c_uint,
+        amount_of_vertices: *mut c_uint,
+    ) -> c_uchar;
+
+    pub fn pathfind_navmesh_tile_polygons(
+        map: *const Map,
+        tile_index: u32,
+        buffer: *mut NavmeshPolygon,
+        buffer_length: c_uint,
+        amount_of_polygons: *mut c_uint,
+    ) -> c_uchar;
+
+    pub fn pathfind_find_highest_heights(
+        map: *const Map,
+        start_x: c_float,
+        start_y: c_float,
+        step_x: c_float,
+        step_y: c_float,
+        rows: c_uint,
+        columns: c_uint,
+        buffer: *mut c_float,
+    ) -> c_uchar;
+
+    pub fn pathfind_transport_new(
+        vertices: *const c_float,
+        amount_of_vertices: c_uint,
+        indices: *const c_uint,
+        amount_of_indices: c_uint,
+        out_result: *mut c_uchar,
+    ) -> *mut Transport;
+
+    pub fn pathfind_transport_new_from_model(
+        model_path: *const c_char,
+        out_result: *mut c_uchar,
+    ) -> *mut Transport;
+
+    pub fn pathfind_transport_free(transport: *mut Transport);
+
+    pub fn pathfind_transport_find_path(
+        transport: *const Transport,
+        start_x: c_float,
+        start_y: c_float,
+        start_z: c_float,
+        stop_x: c_float,
+        stop_y: c_float,
+        stop_z: c_float,
+        buffer: *mut Vertex,
+        buffer_length: c_uint,
+        amount_of_vertices: *mut c_uint,
+    ) -> c_uchar;
+
+    pub fn pathfind_transport_line_of_sight(
+        transport: *const Transport,
+        start_x: c_float,
+        start_y: c_float,
+        start_z: c_float,
+        stop_x: c_float,
+        stop_y: c_float,
+        stop_z: c_float,
+        line_of_sight: *mut u8,
+    ) -> c_uchar;
+
+    pub fn pathfind_verify_model(model_path: *const c_char) -> c_uchar;
+
     pub fn mapbuild_build_bvh(
         data_path: *const c_char,
         output_path: *const c_char,
@@ -271,4 +563,62 @@ extern "C" {
         map_name: *const c_char,
         exists: *mut u8,
     ) -> c_uchar;
+
+    pub fn mpq_open_archive(path: *const c_char, out_result: *mut c_uchar) -> *mut MpqArchive;
+
+    pub fn mpq_close_archive(archive: *mut MpqArchive);
+
+    pub fn mpq_has_file(
+        archive: *mut MpqArchive,
+        name: *const c_char,
+        out_has_file: *mut u8,
+    ) -> c_uchar;
+
+    pub fn mpq_read_file(
+        archive: *mut MpqArchive,
+        name: *const c_char,
+        buffer: *mut u8,
+        buffer_length: c_uint,
+        out_size: *mut c_uint,
+    ) -> c_uchar;
+
+    pub fn mpq_list_files(
+        archive: *mut MpqArchive,
+        mask: *const c_char,
+        buffer: *mut c_char,
+        buffer_length: c_uint,
+        out_length: *mut c_uint,
+    ) -> c_uchar;
+
+    pub fn lock_file_acquire(path: *const c_char, out_result: *mut c_uchar) -> *mut FileLock;
+
+    pub fn lock_file_release(lock: *mut FileLock);
+
+    pub fn rename_exchange(a: *const c_char, b: *const c_char) -> bool;
+
+    pub fn mapbuild_collision_geometry(
+        data_path: *const c_char,
+        map_name: *const c_char,
+        adts: *const c_int,
+        amount_of_adts: c_uint,
+        callback: GeometryCallback,
+        state: *mut c_void,
+    ) -> c_uchar;
+}
+
+// Functions only used by the tests of the `namigator` crate.
+#[cfg(feature = "test-helpers")]
+extern "C" {
+    pub fn mpq_create_archive(
+        path: *const c_char,
+        name: *const c_char,
+        data: *const u8,
+        data_length: c_uint,
+    ) -> c_uchar;
+
+    pub fn mpq_manager_read_u32(
+        data_path: *const c_char,
+        name: *const c_char,
+        out_value: *mut u32,
+    ) -> c_uchar;
 }
@@ -1,12 +1,20 @@
 use crate::{
     mapbuild_build_bvh, mapbuild_build_map, mapbuild_bvh_files_exist, mapbuild_map_files_exist,
-    pathfind_find_height, pathfind_find_heights, pathfind_find_path,
+    pathfind_distance_to_wall, pathfind_find_height, pathfind_find_heights,
+    pathfind_find_highest_heights, pathfind_find_path, pathfind_find_path_points,
     pathfind_find_point_in_between_vectors, pathfind_find_random_point_around_circle,
-    pathfind_free_map, pathfind_get_zone_and_area, pathfind_has_adts, pathfind_is_adt_loaded,
-    pathfind_line_of_sight, pathfind_load_adt, pathfind_load_adt_at, pathfind_load_all_adts,
-    pathfind_new_map, pathfind_unload_adt, Map, Vertex, FAILED_TO_OPEN_DBC, SUCCESS,
+    pathfind_find_random_point_around_circle_with_random, pathfind_free_map,
+    pathfind_get_zone_and_area, pathfind_has_adts, pathfind_is_adt_loaded, pathfind_line_of_sight,
+    pathfind_load_adt, pathfind_load_adt_at, pathfind_load_all_adts,
+    pathfind_navmesh_tile_polygons, pathfind_navmesh_tile_vertices, pathfind_navmesh_tiles,
+    pathfind_new_map, pathfind_raycast, pathfind_set_off_mesh_connections,
+    pathfind_transport_find_path, pathfind_transport_free, pathfind_transport_line_of_sight,
+    pathfind_transport_new, pathfind_transport_new_from_model, pathfind_unload_adt,
+    pathfind_verify_model, Map, NavmeshPolygon, NavmeshTileInfo, OffMeshConnection, PathPoint,
+    Vertex, COULD_NOT_DESERIALIZE_DOODAD, FAILED_TO_OPEN_DBC, POLY_FLAG_WALKABLE, RECAST_FAILURE,
+    STRAIGHT_PATH_OFFMESH_CONNECTION, SUCCESS,
 };
-use core::ffi::{c_float, c_uchar, c_uint};
+use core::ffi::{c_float, c_uchar, c_uint, c_void};
 use std::ffi::CString;
 use std::path::Path;
 
@@ -60,9 +68,44 @@ fn test_both() {
     }
 
     test_build(output_path, data_directory);
+    test_models(output_path);
     test_pathfind(output_path);
 }
 
+fn test_models(output_path: &str) {
+    let mut walkable = 0;
+    let models = std::fs::read_dir(Path::new(output_path).join("BVH"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |e| e == "bvh"))
+        .collect::<Vec<_>>();
+    assert!(!models.is_empty());
+
+    for model in models {
+        let model_path = CString::new(model.to_str().unwrap()).unwrap();
+
+        let result = unsafe { pathfind_verify_model(model_path.as_ptr()) };
+        assert_eq!(result, SUCCESS, "{}", model.display());
+
+        let mut result: u8 = 0;
+        let transport = unsafe {
+            pathfind_transport_new_from_model(model_path.as_ptr(), &mut result as *mut u8)
+        };
+        match result {
+            SUCCESS => {
+                assert!(!transport.is_null());
+                walkable += 1;
+                unsafe { pathfind_transport_free(transport) }
+            }
+            // Models without anything to walk on
+            RECAST_FAILURE => assert!(transport.is_null()),
+            result => panic!("{}: {}", model.display(), result),
+        }
+    }
+
+    assert!(walkable > 0);
+}
+
 fn test_pathfind(output_path: &str) {
     let data_path = CString::new(output_path).unwrap();
     let map_name = CString::new(MAP_NAME).unwrap();
@@ -133,6 +176,15 @@ fn test_pathfind(output_path: &str) {
     assert_eq!(buf[1], 35.611702);
     assert_eq!(amount_of_vertices, 2);
 
+    // Everything except the first position is far outside of the map.
+    let mut heights = [0.0_f32; 4];
+    let result = unsafe {
+        pathfind_find_highest_heights(map, X, Y, 1.0e6, 1.0e6, 2, 2, heights.as_mut_ptr())
+    };
+    assert_eq!(result, SUCCESS);
+    assert_eq!(heights[0], 46.30131);
+    assert!(heights[1..].iter().all(|h| h.is_nan()));
+
     let mut amount_of_adts_loaded: u32 = 0;
     let _a = unsafe { pathfind_load_all_adts(map, &mut amount_of_adts_loaded as *mut u32) };
 
@@ -187,6 +239,207 @@ fn test_pathfind(output_path: &str) {
 
     assert!(amount_of_vertices >= 5);
 
+    let mut points = [PathPoint::default(); BUFFER_LENGTH];
+    let mut amount_of_points: u32 = 0;
+    let result = unsafe {
+        pathfind_find_path_points(
+            map,
+            START_X,
+            START_Y,
+            START_Z,
+            END_X,
+            END_Y,
+            END_Z,
+            points.as_mut_ptr(),
+            BUFFER_LENGTH as u32,
+            &mut amount_of_points as *mut u32,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert!(amount_of_points >= amount_of_vertices);
+
+    for point in &points[..amount_of_points as usize - 1] {
+        assert_ne!(point.polygon, 0);
+        assert_ne!(point.flags & POLY_FLAG_WALKABLE, 0);
+    }
+
+    const CONNECTION_ID: u32 = 7;
+    let connection = OffMeshConnection {
+        id: CONNECTION_ID,
+        from_x: START_X,
+        from_y: START_Y,
+        from_z: START_Z,
+        to_x: END_X,
+        to_y: END_Y,
+        to_z: END_Z,
+        radius: 1.0,
+        bidirectional: 0,
+    };
+    // The second call finds the connection already in the tile and leaves it alone.
+    for _ in 0..2 {
+        let result = unsafe { pathfind_set_off_mesh_connections(map, &connection, 1) };
+        assert_eq!(result, SUCCESS);
+    }
+
+    let result = unsafe {
+        pathfind_find_path_points(
+            map,
+            START_X,
+            START_Y,
+            START_Z,
+            END_X,
+            END_Y,
+            END_Z,
+            points.as_mut_ptr(),
+            BUFFER_LENGTH as u32,
+            &mut amount_of_points as *mut u32,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert!(points[..amount_of_points as usize].iter().any(|p| {
+        p.path_flags & STRAIGHT_PATH_OFFMESH_CONNECTION != 0 && p.connection == CONNECTION_ID
+    }));
+
+    let result = unsafe { pathfind_set_off_mesh_connections(map, core::ptr::null(), 0) };
+    assert_eq!(result, SUCCESS);
+
+    let result = unsafe {
+        pathfind_find_path_points(
+            map,
+            START_X,
+            START_Y,
+            START_Z,
+            END_X,
+            END_Y,
+            END_Z,
+            points.as_mut_ptr(),
+            BUFFER_LENGTH as u32,
+            &mut amount_of_points as *mut u32,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert!(points[..amount_of_points as usize]
+        .iter()
+        .all(|p| p.path_flags & STRAIGHT_PATH_OFFMESH_CONNECTION == 0));
+
+    // Far outside of the map, so the ray always stops at the edge of the navmesh.
+    const FAR_AWAY: c_float = 10.0;
+    let mut hit_x: c_float = 0.0;
+    let mut hit_y: c_float = 0.0;
+    let mut hit_z: c_float = 0.0;
+    let mut hit: u8 = 0xFF;
+    let result = unsafe {
+        pathfind_raycast(
+            map,
+            START_X,
+            START_Y,
+            START_Z,
+            START_X + (END_X - START_X) * FAR_AWAY,
+            START_Y + (END_Y - START_Y) * FAR_AWAY,
+            START_Z,
+            &mut hit_x as *mut c_float,
+            &mut hit_y as *mut c_float,
+            &mut hit_z as *mut c_float,
+            &mut hit as *mut u8,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert_eq!(hit, 1);
+
+    // Halfway to where the first ray stopped there is nothing in the way.
+    let (middle_x, middle_y) = ((START_X + hit_x) / 2.0, (START_Y + hit_y) / 2.0);
+    let result = unsafe {
+        pathfind_raycast(
+            map,
+            START_X,
+            START_Y,
+            START_Z,
+            middle_x,
+            middle_y,
+            START_Z,
+            &mut hit_x as *mut c_float,
+            &mut hit_y as *mut c_float,
+            &mut hit_z as *mut c_float,
+            &mut hit as *mut u8,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert_eq!(hit, 0);
+    assert!((hit_x - middle_x).abs() < 0.1);
+    assert!((hit_y - middle_y).abs() < 0.1);
+
+    const MAX_WALL_DISTANCE: f32 = 20.0;
+    let mut wall_distance: f32 = 0.0;
+    let mut normal_x: c_float = 0.0;
+    let mut normal_y: c_float = 0.0;
+    let mut normal_z: c_float = 0.0;
+    let result = unsafe {
+        pathfind_distance_to_wall(
+            map,
+            START_X,
+            START_Y,
+            START_Z,
+            MAX_WALL_DISTANCE,
+            &mut wall_distance as *mut c_float,
+            &mut hit_x as *mut c_float,
+            &mut hit_y as *mut c_float,
+            &mut hit_z as *mut c_float,
+            &mut normal_x as *mut c_float,
+            &mut normal_y as *mut c_float,
+            &mut normal_z as *mut c_float,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert!((0.0..=MAX_WALL_DISTANCE).contains(&wall_distance));
+
+    let mut tiles = [NavmeshTileInfo::default(); 64];
+    let mut amount_of_tiles: c_uint = 0;
+    let result = unsafe {
+        pathfind_navmesh_tiles(
+            map,
+            tiles.as_mut_ptr(),
+            tiles.len() as c_uint,
+            &mut amount_of_tiles as *mut c_uint,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert!(amount_of_tiles > 0);
+
+    let tile = tiles[0];
+    let mut vertices = vec![0.0 as c_float; tile.vertex_count as usize * 3];
+    let mut amount_of_vertices: c_uint = 0;
+    let result = unsafe {
+        pathfind_navmesh_tile_vertices(
+            map,
+            tile.index,
+            vertices.as_mut_ptr(),
+            tile.vertex_count,
+            &mut amount_of_vertices as *mut c_uint,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert_eq!(amount_of_vertices, tile.vertex_count);
+
+    let mut polygons = vec![NavmeshPolygon::default(); tile.polygon_count as usize];
+    let mut amount_of_polygons: c_uint = 0;
+    let result = unsafe {
+        pathfind_navmesh_tile_polygons(
+            map,
+            tile.index,
+            polygons.as_mut_ptr(),
+            tile.polygon_count,
+            &mut amount_of_polygons as *mut c_uint,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert_eq!(amount_of_polygons, tile.polygon_count);
+    for polygon in &polygons {
+        assert!(polygon.vertex_count >= 3);
+        assert!(polygon.vertices[..polygon.vertex_count as usize]
+            .iter()
+            .all(|&v| u32::from(v) < tile.vertex_count));
+    }
+
     let mut zone: u32 = 0;
     let mut area: u32 = 0;
 
@@ -299,9 +552,147 @@ fn test_pathfind(output_path: &str) {
 
     assert!(distance < POINT_DISTANCE);
 
+    extern "C" fn fixed_random(_state: *mut c_void) -> c_float {
+        0.5
+    }
+
+    let result = unsafe {
+        pathfind_find_random_point_around_circle_with_random(
+            map,
+            POINT_X,
+            POINT_Y,
+            POINT_Z,
+            POINT_DISTANCE,
+            fixed_random,
+            core::ptr::null_mut(),
+            &mut out_x as *mut c_float,
+            &mut out_y as *mut c_float,
+            &mut out_z as *mut c_float,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+
+    let (first_x, first_y, first_z) = (out_x, out_y, out_z);
+
+    let result = unsafe {
+        pathfind_find_random_point_around_circle_with_random(
+            map,
+            POINT_X,
+            POINT_Y,
+            POINT_Z,
+            POINT_DISTANCE,
+            fixed_random,
+            core::ptr::null_mut(),
+            &mut out_x as *mut c_float,
+            &mut out_y as *mut c_float,
+            &mut out_z as *mut c_float,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert_eq!((first_x, first_y, first_z), (out_x, out_y, out_z));
+
     unsafe { pathfind_free_map(map) }
 }
 
+#[test]
+fn test_transport() {
+    // A flat 20 by 20 deck made of two triangles.
+    let vertices: [c_float; 12] = [
+        0.0, 0.0, 0.0, 20.0, 0.0, 0.0, 20.0, 20.0, 0.0, 0.0, 20.0, 0.0,
+    ];
+    let indices: [c_uint; 6] = [0, 1, 2, 0, 2, 3];
+
+    let mut result: u8 = 0;
+    let transport = unsafe {
+        pathfind_transport_new(
+            vertices.as_ptr(),
+            4,
+            indices.as_ptr(),
+            indices.len() as c_uint,
+            &mut result as *mut u8,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert!(!transport.is_null());
+
+    let mut buffer = [Vertex::default(); 16];
+    let mut amount_of_vertices: c_uint = 0;
+    let result = unsafe {
+        pathfind_transport_find_path(
+            transport,
+            2.0,
+            2.0,
+            0.0,
+            18.0,
+            18.0,
+            0.0,
+            buffer.as_mut_ptr(),
+            buffer.len() as c_uint,
+            &mut amount_of_vertices as *mut c_uint,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert!(amount_of_vertices >= 2);
+
+    let mut los: u8 = 0xFF;
+    let result = unsafe {
+        pathfind_transport_line_of_sight(
+            transport,
+            2.0,
+            2.0,
+            1.0,
+            18.0,
+            18.0,
+            1.0,
+            &mut los as *mut u8,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert_eq!(los, 1);
+
+    let result = unsafe {
+        pathfind_transport_line_of_sight(
+            transport,
+            2.0,
+            2.0,
+            1.0,
+            2.0,
+            2.0,
+            -1.0,
+            &mut los as *mut u8,
+        )
+    };
+    assert_eq!(result, SUCCESS);
+    assert_eq!(los, 0);
+
+    unsafe { pathfind_transport_free(transport) }
+
+    let mut result: u8 = 0;
+    for indices in [&[0, 1, 2, 0, 2][..], &[0, 1, 4]] {
+        let transport = unsafe {
+            pathfind_transport_new(
+                vertices.as_ptr(),
+                4,
+                indices.as_ptr(),
+                indices.len() as c_uint,
+                &mut result as *mut u8,
+            )
+        };
+        assert_eq!(result, RECAST_FAILURE);
+        assert!(transport.is_null());
+    }
+
+    let missing = CString::new(concat!(env!("OUT_DIR"), "/missing.bvh")).unwrap();
+    let transport =
+        unsafe { pathfind_transport_new_from_model(missing.as_ptr(), &mut result as *mut u8) };
+    assert_eq!(result, COULD_NOT_DESERIALIZE_DOODAD);
+    assert!(transport.is_null());
+    assert_eq!(
+        unsafe { pathfind_verify_model(missing.as_ptr()) },
+        COULD_NOT_DESERIALIZE_DOODAD
+    );
+}
+
 fn call_line_of_sight(map: *const Map, start: Vertex, end: Vertex) -> bool {
     let mut los: u8 = 0;
     let doodads: u8 = 0;
@@ -0,0 +1,160 @@
+use crate::error::NamigatorError;
+use std::fmt::{Display, Formatter};
+
+/// Maps of the `Map.dbc` of the 0.5.3 alpha client.
+///
+/// Use [`list_client_maps`](crate::mpq::list_client_maps) for the maps of other alpha clients.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Map {
+    Azeroth,
+    Kalimdor,
+    Test,
+    Kalidar,
+    ScottTest,
+    CashTest,
+    PvpZone01,
+    Shadowfang,
+    StormwindJail,
+    StormwindPrison,
+    DeadminesInstance,
+    PvpZone02,
+    Collin,
+    WailingCaverns,
+    Monastery,
+    RazorfenKraulInstance,
+    Blackfathom,
+    Uldaman,
+    GnomeragonInstance,
+    SunkenTemple,
+}
+
+impl Map {
+    pub const fn as_int(&self) -> u32 {
+        match self {
+            Map::Azeroth => 0,
+            Map::Kalimdor => 1,
+            Map::Test => 13,
+            Map::Kalidar => 17,
+            Map::ScottTest => 25,
+            Map::CashTest => 29,
+            Map::PvpZone01 => 30,
+            Map::Shadowfang => 33,
+            Map::StormwindJail => 34,
+            Map::StormwindPrison => 35,
+            Map::DeadminesInstance => 36,
+            Map::PvpZone02 => 37,
+            Map::Collin => 42,
+            Map::WailingCaverns => 43,
+            Map::Monastery => 44,
+            Map::RazorfenKraulInstance => 47,
+            Map::Blackfathom => 48,
+            Map::Uldaman => 70,
+            Map::GnomeragonInstance => 90,
+            Map::SunkenTemple => 109,
+        }
+    }
+
+    /// Every map, ordered by id.
+    pub const fn variants() -> [Self; 20] {
+        [
+            Map::Azeroth,
+            Map::Kalimdor,
+            Map::Test,
+            Map::Kalidar,
+            Map::ScottTest,
+            Map::CashTest,
+            Map::PvpZone01,
+            Map::Shadowfang,
+            Map::StormwindJail,
+            Map::StormwindPrison,
+            Map::DeadminesInstance,
+            Map::PvpZone02,
+            Map::Collin,
+            Map::WailingCaverns,
+            Map::Monastery,
+            Map::RazorfenKraulInstance,
+            Map::Blackfathom,
+            Map::Uldaman,
+            Map::GnomeragonInstance,
+            Map::SunkenTemple,
+        ]
+    }
+
+    /// Name of the directory of the map inside `World\Maps`.
+    pub const fn directory_name(&self) -> &'static str {
+        match self {
+            Map::Azeroth => "Azeroth",
+            Map::Kalimdor => "Kalimdor",
+            Map::Test => "Test",
+            Map::Kalidar => "Kalidar",
+            Map::ScottTest => "ScottTest",
+            Map::CashTest => "CashTest",
+            Map::PvpZone01 => "PVPZone01",
+            Map::Shadowfang => "Shadowfang",
+            Map::StormwindJail => "StormwindJail",
+            Map::StormwindPrison => "StormwindPrison",
+            Map::DeadminesInstance => "DeadminesInstance",
+            Map::PvpZone02 => "PVPZone02",
+            Map::Collin => "Collin",
+            Map::WailingCaverns => "WailingCaverns",
+            Map::Monastery => "Monastery",
+            Map::RazorfenKraulInstance => "RazorfenKraulInstance",
+            Map::Blackfathom => "Blackfathom",
+            Map::Uldaman => "Uldaman",
+            Map::GnomeragonInstance => "GnomeragonInstance",
+            Map::SunkenTemple => "SunkenTemple",
+        }
+    }
+}
+
+impl TryFrom<u32> for Map {
+    type Error = NamigatorError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Map::Azeroth,
+            1 => Map::Kalimdor,
+            13 => Map::Test,
+            17 => Map::Kalidar,
+            25 => Map::ScottTest,
+            29 => Map::CashTest,
+            30 => Map::PvpZone01,
+            33 => Map::Shadowfang,
+            34 => Map::StormwindJail,
+            35 => Map::StormwindPrison,
+            36 => Map::DeadminesInstance,
+            37 => Map::PvpZone02,
+            42 => Map::Collin,
+            43 => Map::WailingCaverns,
+            44 => Map::Monastery,
+            47 => Map::RazorfenKraulInstance,
+            48 => Map::Blackfathom,
+            70 => Map::Uldaman,
+            90 => Map::GnomeragonInstance,
+            109 => Map::SunkenTemple,
+            _ => return Err(NamigatorError::MapIdNotFound),
+        })
+    }
+}
+
+impl Display for Map {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.directory_name())
+    }
+}
+
+/// Id from the `AreaTable.dbc` of the alpha client, which is used for both zones and areas.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Area(pub u32);
+
+impl From<u32> for Area {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl Display for Area {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,495 @@
+use namigator::{Vector2d, Vector3d};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+#[cfg(feature = "server")]
+use std::time::Duration;
+
+pub(crate) const USAGE: &str = "\
+Usage: namigator [--json] <command> [options]
+
+Commands:
+    build-bvh --data <dir> --output <dir>
+        Build the gameobject models.
+    build-map (<map>... | --all --expansion <expansion>) --data <dir> --output <dir>
+        Build the navmesh of maps, building the gameobject models first if needed.
+    maps --data <dir>
+        List the maps of the client, with the directory names used as <map>.
+    status --output <dir> [<map>... | --all --expansion <expansion>]
+        Show which maps are built, by default every map in the output directory.
+    verify --output <dir> [<map>... | --all --expansion <expansion>]
+        Load every file of built maps and fail if any of them can not be used.
+    query <query> --output <dir> --map <map> [query options]
+        Query a built map, only loading the ADTs needed for the query.
+    serve --output <dir> [--listen <address>] [--timeout <seconds>] [--threads <amount>]
+        Serve queries over HTTP, only with the server feature.
+
+Queries:
+    path --from <x,y,z> --to <x,y,z>        Path between two positions.
+    heights --at <x,y>                      Every height at a position.
+    height --from <x,y,z> --to <x,y>        Height at the end of walking from a position.
+    los --from <x,y,z> --to <x,y,z>         Whether there is line of sight.
+    zone --at <x,y,z>                       Zone and area of a position.
+    random-point --from <x,y,z> --radius <yards>
+                                            Random point on the navmesh around a position.
+
+Options:
+    --data <dir>             Data directory of the client containing the MPQ files.
+    --output <dir>           Directory for the built files.
+    --threads <amount>       Threads used for building or for answering connections,
+                             defaults to the amount of CPUs.
+    --gameobject-csv <file>  Gameobject spawns to include in the navmesh.
+    --force                  Build even if the files are up to date.
+    --all                    Every map of the expansion.
+    --expansion <expansion>  vanilla, tbc, wrath or alpha.
+    --map <map>              Directory name of the map to query.
+    --listen <address>       Address for the server, defaults to 127.0.0.1:8080.
+    --timeout <seconds>      Time before a server request fails, defaults to 30.
+    --json                   Print the result as JSON.
+    -h, --help               Print this message.";
+
+/// Options that do not take a value.
+const SWITCHES: &[&str] = &["--json", "--force", "--all", "--help"];
+/// Options that take a value, either as the next argument or after `=`.
+const VALUES: &[&str] = &[
+    "--data",
+    "--output",
+    "--threads",
+    "--gameobject-csv",
+    "--expansion",
+    "--map",
+    "--from",
+    "--to",
+    "--at",
+    "--radius",
+    "--listen",
+    "--timeout",
+];
+/// Options that are allowed for every command.
+const GLOBAL: &[&str] = &["--json", "--help"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Args {
+    pub json: bool,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Command {
+    BuildBvh(BuildOptions),
+    BuildMap {
+        build: BuildOptions,
+        maps: Maps,
+        gameobject_csv: Option<PathBuf>,
+    },
+    ClientMaps {
+        data: PathBuf,
+    },
+    Status {
+        output: PathBuf,
+        maps: Maps,
+    },
+    Verify {
+        output: PathBuf,
+        maps: Maps,
+    },
+    Query {
+        output: PathBuf,
+        map: String,
+        query: Query,
+    },
+    #[cfg(feature = "server")]
+    Serve(ServeOptions),
+    Help,
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ServeOptions {
+    pub output: PathBuf,
+    pub listen: String,
+    pub timeout: Duration,
+    /// Threads answering connections.
+    pub threads: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BuildOptions {
+    pub data: PathBuf,
+    pub output: PathBuf,
+    pub threads: u32,
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Maps {
+    /// Map directory names, empty for every map in the output directory.
+    Names(Vec<String>),
+    All(Expansion),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Query {
+    Path { from: Vector3d, to: Vector3d },
+    Heights { at: Vector2d },
+    Height { from: Vector3d, to: Vector2d },
+    LineOfSight { from: Vector3d, to: Vector3d },
+    Zone { at: Vector3d },
+    RandomPoint { from: Vector3d, radius: f32 },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Expansion {
+    Vanilla,
+    Tbc,
+    Wrath,
+    Alpha,
+}
+
+impl Expansion {
+    /// Directory names of every map in the expansion.
+    pub(crate) fn map_names(&self) -> Vec<&'static str> {
+        match self {
+            Expansion::Vanilla => namigator::vanilla::Map::variants()
+                .iter()
+                .map(|map| map.directory_name())
+                .collect(),
+            Expansion::Tbc => namigator::tbc::Map::variants()
+                .iter()
+                .map(|map| map.directory_name())
+                .collect(),
+            Expansion::Wrath => namigator::wrath::Map::variants()
+                .iter()
+                .map(|map| map.directory_name())
+                .collect(),
+            Expansion::Alpha => namigator::alpha::Map::variants()
+                .iter()
+                .map(|map| map.directory_name())
+                .collect(),
+        }
+    }
+}
+
+/// Arguments split into options and positional arguments.
+#[derive(Debug, Default)]
+pub(crate) struct Parsed {
+    switches: HashSet<&'static str>,
+    values: HashMap<&'static str, String>,
+    positional: Vec<String>,
+}
+
+impl Parsed {
+    pub(crate) fn new(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') || arg == "-" {
+                parsed.positional.push(arg);
+                continue;
+            }
+
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            let name = if name == "-h" { "--help" } else { name };
+
+            if let Some(&name) = SWITCHES.iter().find(|&&s| s == name) {
+                if inline.is_some() {
+                    return Err(format!("'{}' does not take a value", name));
+                }
+                parsed.switches.insert(name);
+            } else if let Some(&name) = VALUES.iter().find(|&&s| s == name) {
+                let value = match inline.or_else(|| args.next()) {
+                    Some(value) => value,
+                    None => return Err(format!("'{}' requires a value", name)),
+                };
+                parsed.values.insert(name, value);
+            } else {
+                return Err(format!("unknown option '{}'", name));
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub(crate) fn switch(&self, name: &str) -> bool {
+        self.switches.contains(name)
+    }
+
+    pub(crate) fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub(crate) fn required(&self, name: &str) -> Result<&str, String> {
+        self.value(name)
+            .ok_or_else(|| format!("missing required option '{}'", name))
+    }
+
+    /// Fail if any option other than `allowed` and the global options was used.
+    pub(crate) fn only(&self, command: &str, allowed: &[&str]) -> Result<(), String> {
+        let used = self.switches.iter().chain(self.values.keys());
+
+        for name in used {
+            if !allowed.contains(name) && !GLOBAL.contains(name) {
+                return Err(format!("'{}' can not be used with '{}'", name, command));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn threads(&self) -> Result<u32, String> {
+        match self.value("--threads") {
+            Some(threads) => match threads.parse::<u32>() {
+                Ok(threads) if threads > 0 => Ok(threads),
+                _ => Err(format!("invalid amount of threads '{}'", threads)),
+            },
+            None => Ok(std::thread::available_parallelism()
+                .map(|threads| threads.get() as u32)
+                .unwrap_or(1)),
+        }
+    }
+
+    /// Comma separated numbers of `name`, such as `1.5,-2,3`.
+    fn numbers<const N: usize>(&self, name: &str) -> Result<[f32; N], String> {
+        let value = self.required(name)?;
+        let invalid = || {
+            format!(
+                "'{}' requires {} comma separated numbers, got '{}'",
+                name, N, value
+            )
+        };
+
+        let numbers = value
+            .split(',')
+            .map(|n| n.trim().parse::<f32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        numbers.try_into().map_err(|_| invalid())
+    }
+
+    fn vector2d(&self, name: &str) -> Result<Vector2d, String> {
+        let [x, y] = self.numbers(name)?;
+        Ok(Vector2d { x, y })
+    }
+
+    fn vector3d(&self, name: &str) -> Result<Vector3d, String> {
+        let [x, y, z] = self.numbers(name)?;
+        Ok(Vector3d { x, y, z })
+    }
+
+    /// Query named by the positional argument after the command.
+    fn query(&self) -> Result<Query, String> {
+        let name = match self.positional.get(1) {
+            Some(name) => name.as_str(),
+            None => return Err("missing query".to_string()),
+        };
+        if let Some(argument) = self.positional.get(2) {
+            return Err(format!("unexpected argument '{}'", argument));
+        }
+
+        let allowed: &[&str] = match name {
+            "path" | "height" | "los" => &["--from", "--to"],
+            "heights" | "zone" => &["--at"],
+            "random-point" => &["--from", "--radius"],
+            query => return Err(format!("unknown query '{}'", query)),
+        };
+        self.only(name, &[&["--output", "--map"], allowed].concat())?;
+
+        Ok(match name {
+            "path" => Query::Path {
+                from: self.vector3d("--from")?,
+                to: self.vector3d("--to")?,
+            },
+            "heights" => Query::Heights {
+                at: self.vector2d("--at")?,
+            },
+            "height" => Query::Height {
+                from: self.vector3d("--from")?,
+                to: self.vector2d("--to")?,
+            },
+            "los" => Query::LineOfSight {
+                from: self.vector3d("--from")?,
+                to: self.vector3d("--to")?,
+            },
+            "zone" => Query::Zone {
+                at: self.vector3d("--at")?,
+            },
+            _ => {
+                let [radius] = self.numbers("--radius")?;
+                if radius <= 0.0 {
+                    return Err(format!("invalid radius '{}'", radius));
+                }
+
+                Query::RandomPoint {
+                    from: self.vector3d("--from")?,
+                    radius,
+                }
+            }
+        })
+    }
+
+    fn build_options(&self) -> Result<BuildOptions, String> {
+        Ok(BuildOptions {
+            data: self.required("--data")?.into(),
+            output: self.required("--output")?.into(),
+            threads: self.threads()?,
+            force: self.switch("--force"),
+        })
+    }
+
+    /// Map names from the positional arguments after the command, or `--all`.
+    fn maps(&self) -> Result<Maps, String> {
+        let names = self.positional[1..].to_vec();
+
+        if !self.switch("--all") {
+            if self.value("--expansion").is_some() {
+                return Err("'--expansion' can only be used with '--all'".to_string());
+            }
+
+            return Ok(Maps::Names(names));
+        }
+
+        if !names.is_empty() {
+            return Err("map names can not be used with '--all'".to_string());
+        }
+
+        let expansion = match self.required("--expansion")? {
+            "vanilla" => Expansion::Vanilla,
+            "tbc" => Expansion::Tbc,
+            "wrath" => Expansion::Wrath,
+            "alpha" => Expansion::Alpha,
+            expansion => return Err(format!("unknown expansion '{}'", expansion)),
+        };
+
+        Ok(Maps::All(expansion))
+    }
+}
+
+pub(crate) fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let parsed = Parsed::new(args)?;
+    let json = parsed.switch("--json");
+
+    if parsed.switch("--help") {
+        return Ok(Args {
+            json,
+            command: Command::Help,
+        });
+    }
+
+    let name = match parsed.positional.first() {
+        Some(name) => name.as_str(),
+        None => return Err("missing command".to_string()),
+    };
+
+    let command = match name {
+        "build-bvh" => {
+            parsed.only(name, &["--data", "--output", "--threads", "--force"])?;
+            if parsed.positional.len() > 1 {
+                return Err(format!("unexpected argument '{}'", parsed.positional[1]));
+            }
+
+            Command::BuildBvh(parsed.build_options()?)
+        }
+        "build-map" => {
+            parsed.only(
+                name,
+                &[
+                    "--data",
+                    "--output",
+                    "--threads",
+                    "--force",
+                    "--gameobject-csv",
+                    "--all",
+                    "--expansion",
+                ],
+            )?;
+
+            let maps = parsed.maps()?;
+            if maps == Maps::Names(Vec::new()) {
+                return Err("missing map names or '--all'".to_string());
+            }
+
+            Command::BuildMap {
+                build: parsed.build_options()?,
+                maps,
+                gameobject_csv: parsed.value("--gameobject-csv").map(PathBuf::from),
+            }
+        }
+        "maps" => {
+            parsed.only(name, &["--data"])?;
+            if parsed.positional.len() > 1 {
+                return Err(format!("unexpected argument '{}'", parsed.positional[1]));
+            }
+
+            Command::ClientMaps {
+                data: parsed.required("--data")?.into(),
+            }
+        }
+        "status" => {
+            parsed.only(name, &["--output", "--all", "--expansion"])?;
+
+            Command::Status {
+                output: parsed.required("--output")?.into(),
+                maps: parsed.maps()?,
+            }
+        }
+        "verify" => {
+            parsed.only(name, &["--output", "--all", "--expansion"])?;
+
+            Command::Verify {
+                output: parsed.required("--output")?.into(),
+                maps: parsed.maps()?,
+            }
+        }
+        "query" => Command::Query {
+            query: parsed.query()?,
+            output: parsed.required("--output")?.into(),
+            map: parsed.required("--map")?.to_string(),
+        },
+        #[cfg(feature = "server")]
+        "serve" => {
+            parsed.only(name, &["--output", "--listen", "--timeout", "--threads"])?;
+            if parsed.positional.len() > 1 {
+                return Err(format!("unexpected argument '{}'", parsed.positional[1]));
+            }
+
+            let timeout = parsed.value("--timeout").unwrap_or("30");
+            let timeout = match timeout.parse::<u64>() {
+                Ok(timeout) if timeout > 0 => Duration::from_secs(timeout),
+                _ => return Err(format!("invalid timeout '{}'", timeout)),
+            };
+
+            Command::Serve(ServeOptions {
+                output: parsed.required("--output")?.into(),
+                listen: parsed
+                    .value("--listen")
+                    .unwrap_or("127.0.0.1:8080")
+                    .to_string(),
+                timeout,
+                threads: parsed.threads()?,
+            })
+        }
+        command => return Err(format!("unknown command '{}'", command)),
+    };
+
+    Ok(Args { json, command })
+}
+
+/// Parse the query `name` with `options` given as option names without `--` and their values.
+#[cfg(feature = "server")]
+pub(crate) fn parse_query<'a>(
+    name: &str,
+    options: impl IntoIterator<Item = (&'a str, String)>,
+) -> Result<Query, String> {
+    let mut args = vec!["query".to_string(), name.to_string()];
+    // Values are joined with `=` so that negative numbers are not mistaken for options.
+    args.extend(
+        options
+            .into_iter()
+            .map(|(name, value)| format!("--{}={}", name, value)),
+    );
+
+    Parsed::new(args)?.query()
+}
@@ -0,0 +1,335 @@
+use crate::args::{BuildOptions, Maps};
+use crate::json::Json;
+use crate::Output;
+use namigator::mpq::list_client_maps;
+use namigator::raw::{
+    build_bvh, build_map, build_status, bvh_files_exist, map_files_exist, verify_map_output,
+    BuildStatus, StaleReason,
+};
+use namigator::NamigatorError;
+use std::path::Path;
+
+pub(crate) fn run_build_bvh(options: &BuildOptions) -> Output {
+    let (json, text, success) = bvh_step(options, true);
+
+    Output {
+        json: Json::object([("bvh", json)]),
+        text,
+        success,
+    }
+}
+
+pub(crate) fn run_build_map(
+    options: &BuildOptions,
+    maps: &Maps,
+    gameobject_csv: Option<&Path>,
+) -> Output {
+    // Catch typos before spending time on the gameobject models,
+    // clients that can not be read are left for the build to report.
+    if let (Maps::Names(names), Ok(client_maps)) = (maps, list_client_maps(&options.data)) {
+        let unknown = names
+            .iter()
+            .filter(|name| {
+                !client_maps
+                    .iter()
+                    .any(|m| m.directory.eq_ignore_ascii_case(name))
+            })
+            .collect::<Vec<_>>();
+
+        if !unknown.is_empty() {
+            let error = "not a map of the client, see 'namigator maps'";
+
+            return Output {
+                json: Json::object([(
+                    "maps",
+                    Json::Array(
+                        unknown
+                            .iter()
+                            .map(|name| {
+                                Json::object([
+                                    ("name", name.as_str().into()),
+                                    ("status", "failed".into()),
+                                    ("error", error.into()),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                )]),
+                text: unknown
+                    .iter()
+                    .map(|name| line(name, "failed", Some(error)))
+                    .collect(),
+                success: false,
+            };
+        }
+    }
+
+    // Maps can not be built without the gameobject models, so they are only built when missing.
+    let (bvh, mut text, mut success) = bvh_step(
+        &BuildOptions {
+            force: false,
+            ..options.clone()
+        },
+        false,
+    );
+
+    let mut reports = Vec::new();
+    if success {
+        for name in map_names(&options.output, maps) {
+            // Maps without a manifest are reported instead of rebuilt, unless forced.
+            let build_status = build_status(&options.output, &name);
+            let no_manifest = matches!(
+                build_status,
+                Ok(BuildStatus::Stale(StaleReason::NoManifest))
+            );
+            let needs_build = build_status.map_or(true, |status| status.needs_build());
+            let result = if !options.force && !needs_build {
+                Ok(false)
+            } else {
+                build_map(
+                    &options.data,
+                    &options.output,
+                    &name,
+                    gameobject_csv.unwrap_or_else(|| Path::new("")),
+                    options.threads,
+                )
+                .map(|_| true)
+            };
+
+            let (status, error) = status(&result);
+            let reason = (no_manifest && matches!(result, Ok(false)))
+                .then_some("no build manifest, use --force to build again");
+            text.push_str(&line(&name, status, error.as_deref().or(reason)));
+            success &= result.is_ok();
+
+            reports.push(Json::object([
+                ("name", name.as_str().into()),
+                ("status", status.into()),
+                ("reason", reason.into()),
+                ("error", error.into()),
+            ]));
+        }
+    }
+
+    Output {
+        json: Json::object([("bvh", bvh), ("maps", Json::Array(reports))]),
+        text,
+        success,
+    }
+}
+
+pub(crate) fn run_client_maps(data: &Path) -> Output {
+    match list_client_maps(data) {
+        Ok(maps) => Output {
+            json: Json::object([(
+                "maps",
+                Json::Array(
+                    maps.iter()
+                        .map(|m| {
+                            Json::object([
+                                ("id", m.id.into()),
+                                ("directory", m.directory.as_str().into()),
+                                ("name", m.name.as_str().into()),
+                                ("has_adts", m.has_adts.into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            )]),
+            text: maps
+                .iter()
+                .map(|m| {
+                    format!(
+                        "{}\t{}\t{}\t{}\n",
+                        m.id,
+                        m.directory,
+                        m.name,
+                        if m.has_adts { "adts" } else { "no adts" }
+                    )
+                })
+                .collect(),
+            success: true,
+        },
+        Err(e) => Output {
+            json: Json::object([("error", e.to_string().into())]),
+            text: format!("error: {}\n", e),
+            success: false,
+        },
+    }
+}
+
+pub(crate) fn run_status(output: &Path, maps: &Maps) -> Output {
+    let bvh = bvh_files_exist(output);
+    let mut text = line(
+        "gameobjects",
+        if matches!(bvh, Ok(true)) {
+            "built"
+        } else {
+            "missing"
+        },
+        bvh.as_ref().err().map(ToString::to_string).as_deref(),
+    );
+    let mut success = bvh.is_ok();
+
+    let mut reports = Vec::new();
+    for name in map_names(output, maps) {
+        let status = build_status(output, &name);
+
+        let (built, state, reason) = match &status {
+            Ok(BuildStatus::Missing) => (false, "missing", None),
+            Ok(BuildStatus::UpToDate) => (true, "up to date", None),
+            Ok(BuildStatus::Stale(reason)) => (true, "stale", Some(reason.to_string())),
+            Err(_) => (false, "missing", None),
+        };
+        let error = status.as_ref().err().map(ToString::to_string);
+        text.push_str(&line(&name, state, reason.as_deref().or(error.as_deref())));
+        success &= status.is_ok();
+
+        reports.push(Json::object([
+            ("name", name.as_str().into()),
+            ("built", status.is_ok().then_some(built).into()),
+            ("status", status.is_ok().then_some(state).into()),
+            ("reason", reason.into()),
+            ("error", error.into()),
+        ]));
+    }
+
+    Output {
+        json: Json::object([("bvh", bvh.ok().into()), ("maps", Json::Array(reports))]),
+        text,
+        success,
+    }
+}
+
+pub(crate) fn run_verify(output: &Path, maps: &Maps) -> Output {
+    let mut text = String::new();
+    let mut success = true;
+
+    let mut reports = Vec::new();
+    for name in map_names(output, maps) {
+        // Unbuilt maps are expected when verifying every map of an expansion.
+        let built = map_files_exist(output, &name);
+        if matches!(maps, Maps::All(_)) && matches!(built, Ok(false)) {
+            continue;
+        }
+
+        let report = built.and_then(|built| {
+            if built {
+                verify_map_output(output, &name).map(Some)
+            } else {
+                Ok(None)
+            }
+        });
+
+        let (status, problems) = match &report {
+            Ok(Some(report)) if report.is_ok() => (
+                format!(
+                    "ok ({} ADTs, {} models)",
+                    report.adts_checked, report.models_checked
+                ),
+                Vec::new(),
+            ),
+            Ok(Some(report)) => (
+                format!("{} problems", report.problems.len()),
+                report.problems.iter().map(ToString::to_string).collect(),
+            ),
+            Ok(None) => ("missing".to_string(), Vec::new()),
+            Err(_) => ("failed".to_string(), Vec::new()),
+        };
+        let ok = matches!(&report, Ok(Some(report)) if report.is_ok());
+        let error = report.as_ref().err().map(ToString::to_string);
+
+        text.push_str(&line(&name, &status, error.as_deref()));
+        for problem in &problems {
+            text.push_str(&format!("    {}\n", problem));
+        }
+        success &= ok;
+
+        reports.push(Json::object([
+            ("name", name.as_str().into()),
+            ("ok", ok.into()),
+            (
+                "problems",
+                Json::Array(problems.into_iter().map(Json::from).collect()),
+            ),
+            ("error", error.into()),
+        ]));
+    }
+
+    Output {
+        json: Json::object([("maps", Json::Array(reports))]),
+        text,
+        success,
+    }
+}
+
+/// Build the gameobject models unless they exist and `options.force` is not set.
+///
+/// `report_skipped` decides whether the text output mentions skipped builds.
+fn bvh_step(options: &BuildOptions, report_skipped: bool) -> (Json, String, bool) {
+    let result = if !options.force && matches!(bvh_files_exist(&options.output), Ok(true)) {
+        Ok(None)
+    } else {
+        build_bvh(&options.data, &options.output, options.threads).map(Some)
+    };
+
+    let (status, error) = match &result {
+        Ok(Some(_)) => ("built", None),
+        Ok(None) => ("skipped", None),
+        Err(e) => ("failed", Some(e.to_string())),
+    };
+
+    let text = match result {
+        Ok(None) if !report_skipped => String::new(),
+        Ok(Some(objects)) => line("gameobjects", &format!("built {} objects", objects), None),
+        _ => line("gameobjects", status, error.as_deref()),
+    };
+
+    let json = Json::object([
+        ("status", status.into()),
+        ("objects", result.as_ref().ok().copied().flatten().into()),
+        ("error", error.into()),
+    ]);
+
+    (json, text, result.is_ok())
+}
+
+/// Status name and error message of a build that returns whether anything was built.
+fn status(result: &Result<bool, NamigatorError>) -> (&'static str, Option<String>) {
+    match result {
+        Ok(true) => ("built", None),
+        Ok(false) => ("skipped", None),
+        Err(e) => ("failed", Some(e.to_string())),
+    }
+}
+
+fn line(name: &str, status: &str, error: Option<&str>) -> String {
+    match error {
+        Some(error) => format!("{}: {} ({})\n", name, status, error),
+        None => format!("{}: {}\n", name, status),
+    }
+}
+
+/// Names of the selected maps, or of every map with a directory in `output` if none are selected.
+pub(crate) fn map_names(output: &Path, maps: &Maps) -> Vec<String> {
+    match maps {
+        Maps::Names(names) if names.is_empty() => {
+            let mut names = std::fs::read_dir(output.join("Nav"))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect::<Vec<_>>();
+            names.sort();
+
+            names
+        }
+        Maps::Names(names) => names.clone(),
+        Maps::All(expansion) => expansion
+            .map_names()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    }
+}
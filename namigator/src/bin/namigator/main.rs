@@ -0,0 +1,64 @@
+//! Command line tool for building and querying namigator maps.
+//!
+//! Run `namigator --help` for the available commands.
+mod args;
+mod build;
+mod json;
+mod query;
+#[cfg(feature = "server")]
+mod server;
+
+#[cfg(test)]
+mod test;
+
+use crate::args::{Command, USAGE};
+use crate::json::Json;
+use std::process::ExitCode;
+
+/// Result of a command, printed as either text or JSON.
+pub(crate) struct Output {
+    json: Json,
+    text: String,
+    success: bool,
+}
+
+fn main() -> ExitCode {
+    let args = match args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let output = match &args.command {
+        Command::BuildBvh(options) => build::run_build_bvh(options),
+        Command::BuildMap {
+            build,
+            maps,
+            gameobject_csv,
+        } => build::run_build_map(build, maps, gameobject_csv.as_deref()),
+        Command::ClientMaps { data } => build::run_client_maps(data),
+        Command::Status { output, maps } => build::run_status(output, maps),
+        Command::Verify { output, maps } => build::run_verify(output, maps),
+        Command::Query { output, map, query } => query::run_query(output, map, query),
+        #[cfg(feature = "server")]
+        Command::Serve(options) => server::serve(options),
+        Command::Help => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+    };
+
+    if args.json {
+        println!("{}", output.json);
+    } else {
+        print!("{}", output.text);
+    }
+
+    if output.success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
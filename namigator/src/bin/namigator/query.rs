@@ -0,0 +1,224 @@
+use crate::args::Query;
+use crate::json::Json;
+use crate::Output;
+use namigator::raw::{adt_coordinates, adts_on_line, PathfindMap};
+use namigator::{NamigatorError, Vector3d};
+use std::path::Path;
+
+/// ADTs around the ADTs that a query passes through that are also loaded, so that paths can go around obstacles.
+const PATH_MARGIN: i32 = 1;
+/// Paths that need ADTs outside of the loaded ones fail, so the margin is grown up to this before giving up.
+const MAX_PATH_MARGIN: i32 = 4;
+/// Amount of ADTs along each axis of a map.
+const ADTS_PER_SIDE: i32 = 64;
+
+pub(crate) fn run_query(output: &Path, map: &str, query: &Query) -> Output {
+    let result = open_map(output, map).and_then(|mut map| query_map(&mut map, query));
+
+    match result {
+        Ok((json, text)) => Output {
+            json,
+            text,
+            success: true,
+        },
+        Err(e) => Output {
+            json: Json::object([("error", e.to_string().into())]),
+            text: format!("error: {}\n", e),
+            success: false,
+        },
+    }
+}
+
+/// Load the map `name` for queries.
+///
+/// Maps without ADTs, such as instances that only consist of a single WMO, are loaded completely
+/// since queries only load ADTs.
+pub(crate) fn open_map(output: &Path, name: &str) -> Result<PathfindMap, NamigatorError> {
+    let mut map = PathfindMap::new(output, name)?;
+
+    if !map.has_adts()? {
+        map.load_all_adts()?;
+    }
+
+    Ok(map)
+}
+
+/// Run `query` on `map` after loading the ADTs it needs, returning the JSON and text output.
+pub(crate) fn query_map(
+    map: &mut PathfindMap,
+    query: &Query,
+) -> Result<(Json, String), NamigatorError> {
+    Ok(match *query {
+        Query::Path { from, to } => {
+            let path = find_path(map, from, to)?;
+
+            (
+                Json::object([("path", Json::Array(path.iter().map(vector).collect()))]),
+                path.iter().map(|&v| format!("{}\n", text(v))).collect(),
+            )
+        }
+        Query::Heights { at } => {
+            load(map, &[(at.x, at.y)])?;
+            let heights = map.find_heights(at.x, at.y)?;
+
+            (
+                Json::object([(
+                    "heights",
+                    Json::Array(heights.iter().map(|&h| h.into()).collect()),
+                )]),
+                heights.iter().map(|h| format!("{}\n", h)).collect(),
+            )
+        }
+        Query::Height { from, to } => {
+            load_line(
+                map,
+                from,
+                Vector3d {
+                    x: to.x,
+                    y: to.y,
+                    z: from.z,
+                },
+                PATH_MARGIN,
+            )?;
+            let height = map.find_height(from, to)?;
+
+            (
+                Json::object([("height", height.into())]),
+                format!("{}\n", height),
+            )
+        }
+        Query::LineOfSight { from, to } => {
+            load_line(map, from, to, 0)?;
+            let line_of_sight = map.line_of_sight(from, to)?;
+
+            (
+                Json::object([("line_of_sight", line_of_sight.into())]),
+                format!("{}\n", line_of_sight),
+            )
+        }
+        Query::Zone { at } => {
+            load(map, &[(at.x, at.y)])?;
+            let (zone, area) = map.get_zone_and_area(at.x, at.y, at.z)?;
+
+            (
+                Json::object([("zone", zone.into()), ("area", area.into())]),
+                format!("zone: {}\narea: {}\n", zone, area),
+            )
+        }
+        Query::RandomPoint { from, radius } => {
+            load(
+                map,
+                &[
+                    (from.x - radius, from.y - radius),
+                    (from.x - radius, from.y + radius),
+                    (from.x + radius, from.y - radius),
+                    (from.x + radius, from.y + radius),
+                ],
+            )?;
+            let point = map.find_random_point_around_circle(from, radius)?;
+
+            (
+                Json::object([("point", vector(&point))]),
+                format!("{}\n", text(point)),
+            )
+        }
+    })
+}
+
+/// Find a path after loading the ADTs along the straight line between `from` and `to`, and then along the path,
+/// with a margin of ADTs around them.
+///
+/// The path can only go through loaded ADTs, so the margin is grown when no path is found
+/// and the path is found again when ADTs were loaded around it, since a shorter path might go through them.
+fn find_path(
+    map: &mut PathfindMap,
+    from: Vector3d,
+    to: Vector3d,
+) -> Result<Vec<Vector3d>, NamigatorError> {
+    let mut margin = PATH_MARGIN;
+    load_line(map, from, to, margin)?;
+
+    loop {
+        let path = match map.find_path(from, to).map(<[Vector3d]>::to_vec) {
+            Err(NamigatorError::UnknownPath) if margin < MAX_PATH_MARGIN => {
+                margin += 1;
+                load_line(map, from, to, margin)?;
+                continue;
+            }
+            path => path?,
+        };
+
+        let mut loaded = false;
+        for segment in path.windows(2) {
+            loaded |= load_line(map, segment[0], segment[1], PATH_MARGIN)?;
+        }
+
+        if !loaded {
+            return Ok(path);
+        }
+    }
+}
+
+/// Load the ADTs of the straight line between `from` and `to` and the ADTs up to `margin` ADTs around them,
+/// returning whether any ADT was loaded.
+///
+/// ADTs in the margin that the map does not have are skipped.
+fn load_line(
+    map: &mut PathfindMap,
+    from: Vector3d,
+    to: Vector3d,
+    margin: i32,
+) -> Result<bool, NamigatorError> {
+    if !map.has_adts()? {
+        return Ok(false);
+    }
+
+    let mut loaded = false;
+    for (x, y) in adts_on_line(from, to) {
+        let around = (-margin..=margin).flat_map(|dx| (-margin..=margin).map(move |dy| (dx, dy)));
+
+        for (dx, dy) in around {
+            let (adt_x, adt_y) = (x + dx, y + dy);
+            if !(0..ADTS_PER_SIDE).contains(&adt_x)
+                || !(0..ADTS_PER_SIDE).contains(&adt_y)
+                || map.adt_loaded(adt_x, adt_y)?
+            {
+                continue;
+            }
+
+            match map.load_adt(adt_x, adt_y) {
+                Ok(_) => loaded = true,
+                Err(NamigatorError::MapDoesNotHaveAdt | NamigatorError::FailedToLoadAdt)
+                    if (dx, dy) != (0, 0) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    Ok(loaded)
+}
+
+/// Load the ADT of every position that is not already loaded.
+fn load(map: &mut PathfindMap, positions: &[(f32, f32)]) -> Result<(), NamigatorError> {
+    if !map.has_adts()? {
+        return Ok(());
+    }
+
+    for &(x, y) in positions {
+        let (adt_x, adt_y) = adt_coordinates(x, y);
+
+        if !map.adt_loaded(adt_x, adt_y)? {
+            map.load_adt_at(x, y)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn vector(v: &Vector3d) -> Json {
+    Json::object([("x", v.x.into()), ("y", v.y.into()), ("z", v.z.into())])
+}
+
+fn text(v: Vector3d) -> String {
+    format!("{} {} {}", v.x, v.y, v.z)
+}
@@ -0,0 +1,635 @@
+//! HTTP server for querying maps from other languages.
+//!
+//! Every map is loaded once, on first use, by a worker thread that answers all queries for that map.
+//! Requests for a map that is still loading wait for it like for any other query.
+//! Connections are handled by a fixed amount of threads and give up on the worker after the request timeout.
+//! Connections that arrive while too many are already waiting are answered with `503` right away.
+//!
+//! Endpoints, all `GET`:
+//!
+//! * `/maps`: every map in the output directory and whether it is loaded.
+//! * `/maps/<map>/<query>?<options>`: the same queries and options as `namigator query`,
+//!   such as `/maps/Azeroth/path?from=-8949.95,-132.49,83.53&to=-8934.76,-117.82,82.88`.
+//! * `/maps/<map>/tiles/<x>/<y>.png`: top down image of an ADT, see `PathfindMap::tile_png`.
+//! * `/metrics`: request counts and durations in the Prometheus text format.
+//!
+//! Failed requests are answered with `400` for invalid requests, `404` for unknown maps and ADTs,
+//! `422` for queries without an answer such as positions without a path between them,
+//! `500` for files that can not be read and `504` for timeouts.
+use crate::args::{parse_query, Maps, Query, ServeOptions};
+use crate::build::map_names;
+use crate::json::Json;
+use crate::query::{open_map, query_map};
+use crate::Output;
+use namigator::raw::{NavmeshArea, PathfindMap};
+use namigator::NamigatorError;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Largest request line and headers that are accepted.
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Connections that are accepted while every thread is busy before answering with `503`.
+const MAX_QUEUED_CONNECTIONS: usize = 64;
+
+/// Time spent writing the `503` response, which is done on the thread accepting connections.
+const BUSY_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// ADTs of a map that stay loaded after only being loaded for tile images.
+/// The least recently drawn is unloaded when there are more.
+pub(crate) const MAX_TILE_ADTS: usize = 16;
+
+const TILE_AREAS: &[NavmeshArea] = &[
+    NavmeshArea::Terrain,
+    NavmeshArea::Wmo,
+    NavmeshArea::Doodads,
+    NavmeshArea::Liquid,
+];
+
+pub(crate) fn serve(options: &ServeOptions) -> Output {
+    let listener = match TcpListener::bind(&options.listen) {
+        Ok(listener) => listener,
+        Err(e) => {
+            return Output {
+                json: Json::object([("error", e.to_string().into())]),
+                text: format!("error: unable to listen on '{}': {}\n", options.listen, e),
+                success: false,
+            }
+        }
+    };
+
+    let server = Arc::new(Server {
+        output: options.output.clone(),
+        timeout: options.timeout,
+        maps: Arc::new(Mutex::new(HashMap::new())),
+        next_worker: AtomicU64::new(0),
+        metrics: Metrics::default(),
+    });
+
+    let (connections, receiver) = mpsc::sync_channel::<TcpStream>(MAX_QUEUED_CONNECTIONS);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..options.threads {
+        let server = Arc::clone(&server);
+        let receiver = Arc::clone(&receiver);
+
+        std::thread::spawn(move || loop {
+            // The lock is only held while waiting for a connection, not while handling it.
+            let stream = receiver.lock().unwrap().recv();
+            match stream {
+                Ok(stream) => server.handle(stream),
+                Err(_) => return,
+            }
+        });
+    }
+
+    eprintln!("listening on http://{}", options.listen);
+
+    loop {
+        if let Ok((stream, _)) = listener.accept() {
+            if let Err(TrySendError::Full(mut stream)) = connections.try_send(stream) {
+                let _ = stream.set_write_timeout(Some(BUSY_WRITE_TIMEOUT));
+                let response = Response::error(503, "too many connections");
+                let _ = stream.write_all(&response.to_bytes());
+
+                server
+                    .metrics
+                    .record("unknown", response.status, Duration::ZERO);
+            }
+        }
+    }
+}
+
+type Workers = Arc<Mutex<HashMap<String, Worker>>>;
+
+struct Server {
+    output: PathBuf,
+    timeout: Duration,
+    /// Workers of the maps that are loaded or loading.
+    maps: Workers,
+    next_worker: AtomicU64,
+    metrics: Metrics,
+}
+
+struct Worker {
+    /// Identifies the worker, so that a worker that failed to load only removes itself.
+    id: u64,
+    jobs: Sender<Job>,
+    loaded: Arc<AtomicBool>,
+}
+
+impl Server {
+    fn handle(&self, mut stream: TcpStream) {
+        let start = Instant::now();
+
+        // Slow clients should not keep a thread forever.
+        let _ = stream.set_read_timeout(Some(self.timeout));
+        let _ = stream.set_write_timeout(Some(self.timeout));
+
+        let (endpoint, response) = match read_request_target(&mut stream) {
+            Ok(target) => match route(&target) {
+                Ok(route) => (route.endpoint(), self.respond(route, start)),
+                Err(response) => ("unknown", response),
+            },
+            Err(response) => ("unknown", response),
+        };
+
+        let _ = stream.write_all(&response.to_bytes());
+
+        self.metrics
+            .record(endpoint, response.status, start.elapsed());
+    }
+
+    fn respond(&self, route: Route, start: Instant) -> Response {
+        match route {
+            Route::Maps => self.list_maps(),
+            Route::Metrics => Response {
+                status: 200,
+                content_type: "text/plain; version=0.0.4",
+                body: self.metrics.render(self.maps_loaded()).into_bytes(),
+            },
+            Route::Map { name, request } => {
+                let (id, worker) = match self.worker(&name) {
+                    Ok(worker) => worker,
+                    Err(response) => return response,
+                };
+
+                let deadline = start + self.timeout;
+                let (reply, receiver) = mpsc::channel();
+                if worker
+                    .send(Job {
+                        request,
+                        deadline,
+                        reply,
+                    })
+                    .is_err()
+                {
+                    // The worker panicked, so the map is loaded again on the next request.
+                    remove_worker(&self.maps, &name, id);
+                    return Response::error(500, "map worker stopped");
+                }
+
+                match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(response) => response,
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.metrics.timeout();
+                        Response::error(504, "request timed out")
+                    }
+                    Err(RecvTimeoutError::Disconnected) => {
+                        Response::error(500, "map worker stopped")
+                    }
+                }
+            }
+        }
+    }
+
+    fn list_maps(&self) -> Response {
+        let workers = self.maps.lock().unwrap();
+
+        let maps = map_names(&self.output, &Maps::Names(Vec::new()))
+            .into_iter()
+            .map(|name| {
+                let worker = workers.get(&name);
+                let loaded = worker.map_or(false, |w| w.loaded.load(Ordering::Relaxed));
+                let loading = worker.is_some() && !loaded;
+
+                Json::object([
+                    ("name", name.into()),
+                    ("loaded", loaded.into()),
+                    ("loading", loading.into()),
+                ])
+            })
+            .collect();
+
+        Response::json(200, &Json::object([("maps", Json::Array(maps))]))
+    }
+
+    fn maps_loaded(&self) -> usize {
+        self.maps
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|w| w.loaded.load(Ordering::Relaxed))
+            .count()
+    }
+
+    /// Worker of the map `name`, which starts loading the map if it is not already.
+    fn worker(&self, name: &str) -> Result<(u64, Sender<Job>), Response> {
+        if let Some(worker) = self.maps.lock().unwrap().get(name) {
+            return Ok((worker.id, worker.jobs.clone()));
+        }
+
+        // Only names from the output directory are used, so that requests can not choose other paths.
+        if !map_names(&self.output, &Maps::Names(Vec::new()))
+            .iter()
+            .any(|n| n == name)
+        {
+            return Err(Response::error(404, &format!("unknown map '{}'", name)));
+        }
+
+        let mut maps = self.maps.lock().unwrap();
+        let worker = maps.entry(name.to_string()).or_insert_with(|| {
+            let id = self.next_worker.fetch_add(1, Ordering::Relaxed);
+            spawn_worker(&self.output, name, id, Arc::clone(&self.maps))
+        });
+
+        Ok((worker.id, worker.jobs.clone()))
+    }
+}
+
+pub(crate) enum Request {
+    Query(Query),
+    Tile { x: i32, y: i32 },
+}
+
+struct Job {
+    request: Request,
+    /// Jobs that are still queued after this have already been answered with a timeout.
+    deadline: Instant,
+    reply: Sender<Response>,
+}
+
+/// Start a thread that loads the map `name` and answers the jobs sent to it.
+fn spawn_worker(output: &Path, name: &str, id: u64, workers: Workers) -> Worker {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let loaded = Arc::new(AtomicBool::new(false));
+
+    let output = output.to_path_buf();
+    let name = name.to_string();
+    let is_loaded = Arc::clone(&loaded);
+    std::thread::spawn(move || {
+        // Jobs queue up while the map is loading and time out as usual if it takes too long.
+        let mut map = match open_map(&output, &name) {
+            Ok(map) => map,
+            Err(e) => {
+                // The map is loaded again on the next request.
+                remove_worker(&workers, &name, id);
+
+                let message = format!("unable to load map '{}': {}", name, e);
+                for job in receiver {
+                    let _ = job.reply.send(Response::error(500, &message));
+                }
+                return;
+            }
+        };
+        is_loaded.store(true, Ordering::Relaxed);
+
+        let mut tile_adts = TileAdts::default();
+        for job in receiver {
+            if Instant::now() >= job.deadline {
+                continue;
+            }
+
+            let response = match job.request {
+                Request::Query(query) => {
+                    query_map(&mut map, &query).map(|(json, _)| Response::json(200, &json))
+                }
+                Request::Tile { x, y } => tile(&mut map, &mut tile_adts, x, y),
+            };
+
+            // The connection has given up if the request timed out.
+            let _ = job
+                .reply
+                .send(response.unwrap_or_else(|e| error_response(&e)));
+        }
+    });
+
+    Worker {
+        id,
+        jobs: sender,
+        loaded,
+    }
+}
+
+/// Remove the worker of `name` if it is still the worker `id`, and not one started after it.
+fn remove_worker(workers: &Workers, name: &str, id: u64) {
+    let mut workers = workers.lock().unwrap();
+    if workers.get(name).map_or(false, |w| w.id == id) {
+        workers.remove(name);
+    }
+}
+
+/// Image of the ADT at `x`, `y`, which is only kept loaded if it was recently drawn or already loaded.
+fn tile(
+    map: &mut PathfindMap,
+    tile_adts: &mut TileAdts,
+    x: i32,
+    y: i32,
+) -> Result<Response, NamigatorError> {
+    let loaded_for_tile = !map.adt_loaded(x, y)?;
+    if loaded_for_tile {
+        map.load_adt(x, y)?;
+    }
+
+    let png = map.tile_png(x, y, TILE_AREAS);
+
+    if let Some((x, y)) = tile_adts.drawn((x, y), loaded_for_tile) {
+        map.unload_adt(x, y)?;
+    }
+
+    Ok(Response {
+        status: 200,
+        content_type: "image/png",
+        body: png?,
+    })
+}
+
+/// ADTs that were loaded only for drawing their images, least recently drawn first.
+#[derive(Debug, Default)]
+pub(crate) struct TileAdts {
+    adts: VecDeque<(i32, i32)>,
+}
+
+impl TileAdts {
+    /// Record that the image of `adt` was drawn, where `loaded_for_tile` is whether it was loaded for it.
+    ///
+    /// Returns the ADT to unload when there are more than [`MAX_TILE_ADTS`].
+    pub(crate) fn drawn(&mut self, adt: (i32, i32), loaded_for_tile: bool) -> Option<(i32, i32)> {
+        if let Some(i) = self.adts.iter().position(|&a| a == adt) {
+            self.adts.remove(i);
+            self.adts.push_back(adt);
+        } else if loaded_for_tile {
+            self.adts.push_back(adt);
+        }
+
+        if self.adts.len() > MAX_TILE_ADTS {
+            self.adts.pop_front()
+        } else {
+            None
+        }
+    }
+}
+
+/// Response for a query or image that failed.
+pub(crate) fn error_response(error: &NamigatorError) -> Response {
+    let status = match error {
+        NamigatorError::MapDoesNotHaveAdt | NamigatorError::IncorrectAdtCoordinates => 404,
+        NamigatorError::UnknownPath
+        | NamigatorError::UnknownHeight
+        | NamigatorError::UnknownZoneAndArea
+        | NamigatorError::UnableToFindRandomPointInCircle
+        | NamigatorError::FailedToFindPointBetweenVectors
+        | NamigatorError::NoPolygonNearPoint
+        | NamigatorError::NoReachablePointFound => 422,
+        // Files that can not be read, or namigator failing.
+        _ => 500,
+    };
+
+    Response::error(status, &error.to_string())
+}
+
+pub(crate) enum Route {
+    Maps,
+    Metrics,
+    Map { name: String, request: Request },
+}
+
+impl Route {
+    /// Name used for the endpoint in metrics.
+    pub(crate) fn endpoint(&self) -> &'static str {
+        match self {
+            Route::Maps => "maps",
+            Route::Metrics => "metrics",
+            Route::Map { request, .. } => match request {
+                Request::Tile { .. } => "tile",
+                Request::Query(Query::Path { .. }) => "path",
+                Request::Query(Query::Heights { .. }) => "heights",
+                Request::Query(Query::Height { .. }) => "height",
+                Request::Query(Query::LineOfSight { .. }) => "los",
+                Request::Query(Query::Zone { .. }) => "zone",
+                Request::Query(Query::RandomPoint { .. }) => "random-point",
+            },
+        }
+    }
+}
+
+/// Find the route of a request target such as `/maps/Azeroth/zone?at=1,2,3`.
+pub(crate) fn route(target: &str) -> Result<Route, Response> {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let segments = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| Response::error(400, "invalid percent encoding"))?;
+    let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let not_found = || Response::error(404, &format!("unknown path '{}'", path));
+
+    match segments.as_slice() {
+        ["maps"] => Ok(Route::Maps),
+        ["metrics"] => Ok(Route::Metrics),
+        ["maps", name, "tiles", x, y] => {
+            let tile = y
+                .strip_suffix(".png")
+                .and_then(|y| Some((x.parse().ok()?, y.parse().ok()?)));
+
+            match tile {
+                Some((x, y)) => Ok(Route::Map {
+                    name: name.to_string(),
+                    request: Request::Tile { x, y },
+                }),
+                None => Err(not_found()),
+            }
+        }
+        ["maps", name, query_name] => {
+            let mut options = Vec::new();
+            for pair in query.split('&').filter(|p| !p.is_empty()) {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+                match (percent_decode(key), percent_decode(value)) {
+                    (Some(key), Some(value)) => options.push((key, value)),
+                    _ => return Err(Response::error(400, "invalid percent encoding")),
+                }
+            }
+
+            let query = parse_query(
+                query_name,
+                options
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.clone())),
+            )
+            .map_err(|e| Response::error(400, &e))?;
+
+            Ok(Route::Map {
+                name: name.to_string(),
+                request: Request::Query(query),
+            })
+        }
+        _ => Err(not_found()),
+    }
+}
+
+/// Read the request head and return the target of a `GET` request.
+pub(crate) fn read_request_target(stream: &mut impl Read) -> Result<String, Response> {
+    let mut head = Vec::new();
+    let mut buffer = [0_u8; 1024];
+
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_HEAD {
+            return Err(Response::error(431, "request head too large"));
+        }
+
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => return Err(Response::error(400, "incomplete request")),
+            Ok(n) => head.extend_from_slice(&buffer[..n]),
+        }
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+
+    match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(target)) => Ok(target.to_string()),
+        (Some(_), Some(_)) => Err(Response::error(405, "only GET is supported")),
+        _ => Err(Response::error(400, "invalid request line")),
+    }
+}
+
+/// Decode `%XX` escapes and `+` as space.
+pub(crate) fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'%' => {
+                let hex = [iter.next()?, iter.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b'+' => bytes.push(b' '),
+            b => bytes.push(b),
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+pub(crate) struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, json: &Json) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: json.to_string().into_bytes(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &Json::object([("error", message.into())]))
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            422 => "Unprocessable Entity",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            504 => "Gateway Timeout",
+            _ => "Internal Server Error",
+        };
+
+        // Allow web pages on other ports of localhost to use the server.
+        let mut out = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason,
+            self.content_type,
+            self.body.len()
+        )
+        .into_bytes();
+        out.extend_from_slice(&self.body);
+
+        out
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    inner: Mutex<MetricsInner>,
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    /// Amount of requests by endpoint and status.
+    requests: BTreeMap<(&'static str, u16), u64>,
+    /// Total seconds and amount of requests by endpoint.
+    durations: BTreeMap<&'static str, (f64, u64)>,
+    timeouts: u64,
+}
+
+impl Metrics {
+    pub(crate) fn record(&self, endpoint: &'static str, status: u16, duration: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+
+        *inner.requests.entry((endpoint, status)).or_default() += 1;
+
+        let (seconds, count) = inner.durations.entry(endpoint).or_default();
+        *seconds += duration.as_secs_f64();
+        *count += 1;
+    }
+
+    pub(crate) fn timeout(&self) {
+        self.inner.lock().unwrap().timeouts += 1;
+    }
+
+    pub(crate) fn render(&self, maps_loaded: usize) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut s = String::new();
+
+        s.push_str("# HELP namigator_requests_total Requests by endpoint and status.\n");
+        s.push_str("# TYPE namigator_requests_total counter\n");
+        for ((endpoint, status), count) in &inner.requests {
+            writeln!(
+                s,
+                "namigator_requests_total{{endpoint=\"{}\",status=\"{}\"}} {}",
+                endpoint, status, count
+            )
+            .unwrap();
+        }
+
+        s.push_str("# HELP namigator_request_duration_seconds Time spent on requests.\n");
+        s.push_str("# TYPE namigator_request_duration_seconds summary\n");
+        for (endpoint, (seconds, count)) in &inner.durations {
+            writeln!(
+                s,
+                "namigator_request_duration_seconds_sum{{endpoint=\"{}\"}} {}",
+                endpoint, seconds
+            )
+            .unwrap();
+            writeln!(
+                s,
+                "namigator_request_duration_seconds_count{{endpoint=\"{}\"}} {}",
+                endpoint, count
+            )
+            .unwrap();
+        }
+
+        s.push_str(
+            "# HELP namigator_request_timeouts_total Requests that timed out waiting for a map.\n",
+        );
+        s.push_str("# TYPE namigator_request_timeouts_total counter\n");
+        writeln!(s, "namigator_request_timeouts_total {}", inner.timeouts).unwrap();
+
+        s.push_str("# HELP namigator_maps_loaded Maps that are loaded.\n");
+        s.push_str("# TYPE namigator_maps_loaded gauge\n");
+        writeln!(s, "namigator_maps_loaded {}", maps_loaded).unwrap();
+
+        s
+    }
+}
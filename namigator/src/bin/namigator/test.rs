@@ -0,0 +1,279 @@
+use crate::args::{parse, BuildOptions, Command, Expansion, Maps, Query};
+use crate::json::Json;
+use namigator::{Vector2d, Vector3d};
+use std::path::PathBuf;
+
+fn args(s: &str) -> Vec<String> {
+    s.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn test_parse() {
+    let parsed = parse(args(
+        "build-map Azeroth Kalimdor --data /data --output=/out --threads 4 --force --json",
+    ))
+    .unwrap();
+    assert!(parsed.json);
+    assert_eq!(
+        parsed.command,
+        Command::BuildMap {
+            build: BuildOptions {
+                data: PathBuf::from("/data"),
+                output: PathBuf::from("/out"),
+                threads: 4,
+                force: true,
+            },
+            maps: Maps::Names(vec!["Azeroth".to_string(), "Kalimdor".to_string()]),
+            gameobject_csv: None,
+        }
+    );
+
+    let parsed = parse(args("status --output /out --all --expansion tbc")).unwrap();
+    assert!(!parsed.json);
+    assert_eq!(
+        parsed.command,
+        Command::Status {
+            output: PathBuf::from("/out"),
+            maps: Maps::All(Expansion::Tbc),
+        }
+    );
+
+    assert_eq!(
+        parse(args("verify Azeroth --output /out")).unwrap().command,
+        Command::Verify {
+            output: PathBuf::from("/out"),
+            maps: Maps::Names(vec!["Azeroth".to_string()]),
+        }
+    );
+
+    assert_eq!(
+        parse(args("maps --data /data")).unwrap().command,
+        Command::ClientMaps {
+            data: PathBuf::from("/data"),
+        }
+    );
+
+    assert_eq!(parse(args("status -h")).unwrap().command, Command::Help);
+
+    for invalid in [
+        "",
+        "build",
+        "build-bvh --data /data",
+        "build-bvh --data /data --output /out --threads 0",
+        "build-bvh --data /data --output /out --gameobject-csv go.csv",
+        "build-bvh --data /data --output /out Azeroth",
+        "build-map --data /data --output /out",
+        "build-map Azeroth --all --expansion wrath --data /data --output /out",
+        "build-map --all --expansion cata --data /data --output /out",
+        "status --output /out --expansion wrath",
+        "status --output",
+        "status --output /out --json=true",
+        "status --output /out --verbose",
+    ] {
+        assert!(parse(args(invalid)).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_parse_query() {
+    let query = |s: &str| match parse(args(&format!("query {} --output /out --map Azeroth", s))) {
+        Ok(a) => match a.command {
+            Command::Query { query, .. } => Ok(query),
+            command => panic!("{:?}", command),
+        },
+        Err(e) => Err(e),
+    };
+
+    assert_eq!(
+        query("path --from -8949.95,-132.493,83.5312 --to=1,2,3"),
+        Ok(Query::Path {
+            from: Vector3d {
+                x: -8949.95,
+                y: -132.493,
+                z: 83.5312
+            },
+            to: Vector3d {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0
+            },
+        })
+    );
+    assert_eq!(
+        query("height --from 1,2,3 --to 4,5"),
+        Ok(Query::Height {
+            from: Vector3d {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0
+            },
+            to: Vector2d { x: 4.0, y: 5.0 },
+        })
+    );
+    assert_eq!(
+        query("random-point --from 1,2,3 --radius 10"),
+        Ok(Query::RandomPoint {
+            from: Vector3d {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0
+            },
+            radius: 10.0,
+        })
+    );
+
+    for invalid in [
+        "",
+        "teleport --at 1,2,3",
+        "heights --at 1,2,3",
+        "zone --at 1,2",
+        "los --from 1,2,3 --to a,b,c",
+        "path --from 1,2,3",
+        "path --from 1,2,3 --to 1,2,3 --at 1,2",
+        "random-point --from 1,2,3 --radius -1",
+        "zone extra --at 1,2,3",
+    ] {
+        assert!(query(invalid).is_err(), "{}", invalid);
+    }
+
+    assert!(parse(args("query zone --at 1,2,3 --output /out")).is_err());
+}
+
+#[test]
+fn test_json() {
+    let json = Json::object([
+        ("name", "a \"quoted\"\n\\ name\u{1}".into()),
+        ("built", Some(true).into()),
+        ("error", None::<String>.into()),
+        ("objects", 12_u32.into()),
+        ("height", 1.5_f32.into()),
+        ("nan", f32::NAN.into()),
+        ("list", Json::Array(vec![Json::Null, false.into()])),
+    ]);
+
+    assert_eq!(
+        json.to_string(),
+        r#"{"name":"a \"quoted\"\n\\ name\u0001","built":true,"error":null,"objects":12,"height":1.5,"nan":null,"list":[null,false]}"#
+    );
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_server_route() {
+    use crate::server::{percent_decode, read_request_target, route, Request, Route};
+
+    assert_eq!(percent_decode("a%2Cb+c").as_deref(), Some("a,b c"));
+    assert_eq!(percent_decode("%2"), None);
+    assert_eq!(percent_decode("%zz"), None);
+
+    let request = |target: &str| match route(target) {
+        Ok(Route::Map { name, request }) => Ok((name, request)),
+        Ok(route) => Ok((route.endpoint().to_string(), Request::Tile { x: -1, y: -1 })),
+        Err(response) => Err(response.status),
+    };
+
+    assert!(matches!(
+        request("/maps/Azeroth/zone?at=-8949.95%2C-132.49,83.5"),
+        Ok((name, Request::Query(Query::Zone { at }))) if name == "Azeroth" && at.x == -8949.95 && at.y == -132.49
+    ));
+    assert!(matches!(
+        request("/maps/Azeroth/tiles/32/48.png"),
+        Ok((_, Request::Tile { x: 32, y: 48 }))
+    ));
+    assert!(matches!(request("/maps"), Ok((endpoint, _)) if endpoint == "maps"));
+    assert!(matches!(request("/metrics"), Ok((endpoint, _)) if endpoint == "metrics"));
+
+    assert_eq!(request("/maps/Azeroth/zone").err(), Some(400));
+    assert_eq!(request("/maps/Azeroth/zone?at=1,2").err(), Some(400));
+    assert_eq!(request("/maps/Azeroth/teleport?at=1,2,3").err(), Some(400));
+    assert_eq!(request("/maps/Azeroth/tiles/32/48").err(), Some(404));
+    assert_eq!(request("/").err(), Some(404));
+
+    let target = |s: &str| read_request_target(&mut s.as_bytes()).map_err(|r| r.status);
+    assert_eq!(
+        target("GET /maps HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+        Ok("/maps".to_string())
+    );
+    assert_eq!(target("POST /maps HTTP/1.1\r\n\r\n"), Err(405));
+    assert_eq!(target("GET /maps HTTP/1.1\r\n"), Err(400));
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_server_metrics() {
+    use crate::server::Metrics;
+    use std::time::Duration;
+
+    let metrics = Metrics::default();
+    metrics.record("path", 200, Duration::from_millis(500));
+    metrics.record("path", 200, Duration::from_millis(250));
+    metrics.record("path", 504, Duration::from_secs(1));
+    metrics.timeout();
+
+    let text = metrics.render(2);
+    assert!(text.contains("namigator_requests_total{endpoint=\"path\",status=\"200\"} 2\n"));
+    assert!(text.contains("namigator_requests_total{endpoint=\"path\",status=\"504\"} 1\n"));
+    assert!(text.contains("namigator_request_duration_seconds_sum{endpoint=\"path\"} 1.75\n"));
+    assert!(text.contains("namigator_request_duration_seconds_count{endpoint=\"path\"} 3\n"));
+    assert!(text.contains("namigator_request_timeouts_total 1\n"));
+    assert!(text.contains("namigator_maps_loaded 2\n"));
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_server_errors() {
+    use crate::server::error_response;
+    use namigator::NamigatorError;
+
+    let status = |e: NamigatorError| error_response(&e).status;
+    assert_eq!(status(NamigatorError::MapDoesNotHaveAdt), 404);
+    assert_eq!(status(NamigatorError::IncorrectAdtCoordinates), 404);
+    assert_eq!(status(NamigatorError::UnknownPath), 422);
+    assert_eq!(status(NamigatorError::NoPolygonNearPoint), 422);
+    assert_eq!(status(NamigatorError::FailedToOpenAdt), 500);
+    assert_eq!(
+        status(NamigatorError::Io(std::io::ErrorKind::NotFound.into())),
+        500
+    );
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_server_tile_adts() {
+    use crate::server::{TileAdts, MAX_TILE_ADTS};
+
+    let mut adts = TileAdts::default();
+
+    // Already loaded by queries, so never unloaded for tiles.
+    assert_eq!(adts.drawn((-1, -1), false), None);
+
+    for i in 0..MAX_TILE_ADTS as i32 {
+        assert_eq!(adts.drawn((i, 0), true), None);
+    }
+
+    // Drawing the oldest again makes the second oldest the least recently drawn.
+    assert_eq!(adts.drawn((0, 0), false), None);
+    assert_eq!(adts.drawn((100, 0), true), Some((1, 0)));
+    assert_eq!(adts.drawn((101, 0), true), Some((2, 0)));
+    assert_eq!(adts.drawn((-1, -1), false), None);
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_parse_serve() {
+    use crate::args::ServeOptions;
+    use std::time::Duration;
+
+    assert_eq!(
+        parse(args("serve --output /out --timeout 5 --threads 2"))
+            .unwrap()
+            .command,
+        Command::Serve(ServeOptions {
+            output: PathBuf::from("/out"),
+            listen: "127.0.0.1:8080".to_string(),
+            timeout: Duration::from_secs(5),
+            threads: 2,
+        })
+    );
+    assert!(parse(args("serve --output /out --threads 0")).is_err());
+}
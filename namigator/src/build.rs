@@ -1,4 +1,9 @@
 use crate::error::{error_code_to_error, NamigatorError};
+use crate::manifest::write_manifest;
+use crate::offmesh::{
+    map_off_mesh_connections_path, write_off_mesh_connections, OffMeshConnection,
+};
+use crate::staging::{BuildLock, Staging};
 use crate::util::path_to_cstr;
 use namigator_sys::{
     mapbuild_build_bvh, mapbuild_build_map, mapbuild_bvh_files_exist, mapbuild_map_files_exist,
@@ -56,6 +61,18 @@ pub fn build_bvh(
 /// `gameobject_csv` TODO
 ///
 /// `threads` is the amount of threads that will be used.
+///
+/// After a successful build a manifest of the inputs is written next to the map files,
+/// which [`build_status`](crate::raw::build_status) uses to tell whether the map has to be built again.
+///
+/// The map is built in a staging directory inside `output_path` and only moved into place when the build succeeds,
+/// so a build that fails or is killed never leaves partial map files behind.
+/// Off-mesh connections stored for the map by
+/// [`build_map_with_off_mesh_connections`](crate::raw::build_map_with_off_mesh_connections) are kept.
+/// Building the same map into the same `output_path` from another process at the same time returns
+/// [`NamigatorError::MapBuildLocked`].
+/// Different maps should not be built into the same `output_path` at the same time,
+/// since they share the index of the gameobject models.
 pub fn build_map(
     data_path: impl AsRef<Path>,
     output_path: impl AsRef<Path>,
@@ -63,44 +80,73 @@ pub fn build_map(
     gameobject_csv: impl AsRef<Path>,
     threads: u32,
 ) -> Result<(), NamigatorError> {
-    fn inner(
-        data_path: &Path,
-        output_path: &Path,
-        map_name: &str,
-        gameobject_csv: &Path,
-        threads: u32,
-    ) -> Result<(), NamigatorError> {
-        let data_path = path_to_cstr(data_path)?;
-        let output_path = path_to_cstr(output_path)?;
-        let gameobject_csv = path_to_cstr(gameobject_csv)?;
-        let map_name = CString::new(map_name)?;
-
-        let result = unsafe {
-            mapbuild_build_map(
-                data_path.as_ptr(),
-                output_path.as_ptr(),
-                map_name.as_ptr(),
-                gameobject_csv.as_ptr(),
-                threads,
-            )
-        };
-
-        if result == SUCCESS {
-            return Ok(());
-        }
-
-        Err(error_code_to_error(result))
-    }
-
-    inner(
+    build_map_staged(
         data_path.as_ref(),
         output_path.as_ref(),
         map_name,
         gameobject_csv.as_ref(),
         threads,
+        None,
     )
 }
 
+/// Build a map while holding its [`BuildLock`], writing `connections` next to the map files when given
+/// and keeping the connections of the already built map otherwise.
+pub(crate) fn build_map_staged(
+    data_path: &Path,
+    output_path: &Path,
+    map_name: &str,
+    gameobject_csv: &Path,
+    threads: u32,
+    connections: Option<&[OffMeshConnection]>,
+) -> Result<(), NamigatorError> {
+    let _lock = BuildLock::acquire(output_path, map_name)?;
+    let staging = Staging::new(output_path, map_name)?;
+
+    let data = path_to_cstr(data_path)?;
+    let output = path_to_cstr(staging.path())?;
+    let csv = path_to_cstr(gameobject_csv)?;
+    let name = CString::new(map_name)?;
+
+    let result = unsafe {
+        mapbuild_build_map(
+            data.as_ptr(),
+            output.as_ptr(),
+            name.as_ptr(),
+            csv.as_ptr(),
+            threads,
+        )
+    };
+
+    if result != SUCCESS {
+        return Err(error_code_to_error(result));
+    }
+
+    if let Some(connections) = connections {
+        write_off_mesh_connections(
+            map_off_mesh_connections_path(staging.path(), map_name),
+            connections,
+        )?;
+    }
+    staging.keep_sidecar_files()?;
+
+    write_manifest(data_path, staging.path(), map_name, gameobject_csv)?;
+    staging.finish()
+}
+
+/// Result of building a single map when building many maps at once.
+#[derive(Debug)]
+pub enum MapBuildStatus {
+    Built,
+    /// The map files were already built from the current inputs so nothing was built.
+    AlreadyBuilt,
+    /// The map files exist without a manifest so nothing was built,
+    /// see [`BuildStatus::needs_build`](crate::raw::BuildStatus::needs_build).
+    NoManifest,
+    /// Building failed, for example because the map has no ADTs.
+    Failed(NamigatorError),
+}
+
 pub fn map_files_exist(
     output_path: impl AsRef<Path>,
     map_name: &str,
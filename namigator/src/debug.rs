@@ -0,0 +1,229 @@
+//! Images for debugging pathfinding queries.
+//!
+//! ```rust,no_run
+//! # use namigator::raw::PathfindMap;
+//! # use namigator::Vector3d;
+//! # fn t() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut map = PathfindMap::new("/output", "Azeroth")?;
+//! let start = Vector3d { x: -8949.95, y: -132.493, z: 83.5312 };
+//! let stop = Vector3d { x: -8934.76, y: -117.82, z: 82.88 };
+//!
+//! let svg = namigator::debug::render_query_svg(&mut map, start, stop)?;
+//! std::fs::write("query.svg", svg)?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::error::NamigatorError;
+use crate::export::NavmeshArea;
+use crate::navmesh::{NavmeshPolygon, NavmeshView};
+use crate::pathfind::PathfindMap;
+use crate::util::adts_on_line;
+use crate::Vector3d;
+use std::fmt::Write as _;
+
+/// Yards around the query that are included in the image.
+const MARGIN: f32 = 15.0;
+
+/// Line of sight probes are raised by this much so that they do not hit the ground they start on.
+const PROBE_HEIGHT: f32 = 2.0;
+
+const PATH_COLOR: &str = "#ff00ff";
+const CLEAR_PROBE_COLOR: &str = "#00c000";
+const BLOCKED_PROBE_COLOR: &str = "#ff0000";
+const UNWALKABLE_COLOR: &str = "#c82828";
+
+/// Line of sight check between two positions, drawn by [`render_query_svg`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Probe {
+    pub from: Vector3d,
+    pub to: Vector3d,
+    pub line_of_sight: bool,
+}
+
+/// Draw a top down SVG image of a [`PathfindMap::find_path`] query from `start` to `stop`.
+///
+/// The image contains the navmesh polygons around the query colored by what they were built from,
+/// the path returned by [`PathfindMap::find_path`], and [`PathfindMap::line_of_sight`] probes
+/// between every pair of path points and between `start` and `stop`.
+/// Probes are green when there is line of sight and red when it is blocked.
+///
+/// North is up, and one SVG unit is one yard.
+/// The ADTs along the straight line between `start` and `stop` are loaded if they are not already.
+/// If no path is found the error is written in the image instead.
+pub fn render_query_svg(
+    map: &mut PathfindMap,
+    start: Vector3d,
+    stop: Vector3d,
+) -> Result<String, NamigatorError> {
+    let view = map.export_view(&adts_on_line(start, stop))?;
+
+    let path = map.find_path(start, stop).map(|p| p.to_vec());
+
+    let mut probes = Vec::new();
+    let mut probe = |from: Vector3d, to: Vector3d| -> Result<(), NamigatorError> {
+        let raise = |v: Vector3d| Vector3d {
+            z: v.z + PROBE_HEIGHT,
+            ..v
+        };
+
+        probes.push(Probe {
+            from,
+            to,
+            line_of_sight: map.line_of_sight(raise(from), raise(to))?,
+        });
+
+        Ok(())
+    };
+
+    probe(start, stop)?;
+    if let Ok(path) = &path {
+        for pair in path.windows(2) {
+            probe(pair[0], pair[1])?;
+        }
+    }
+
+    Ok(write_svg(&view, start, stop, path.as_deref(), &probes))
+}
+
+pub(crate) fn write_svg(
+    view: &NavmeshView,
+    start: Vector3d,
+    stop: Vector3d,
+    path: Result<&[Vector3d], &NamigatorError>,
+    probes: &[Probe],
+) -> String {
+    let points = path.unwrap_or(&[]);
+
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (start.x, start.x, start.y, start.y);
+    for v in points.iter().chain(Some(&stop)) {
+        min_x = min_x.min(v.x);
+        max_x = max_x.max(v.x);
+        min_y = min_y.min(v.y);
+        max_y = max_y.max(v.y);
+    }
+    min_x -= MARGIN;
+    max_x += MARGIN;
+    min_y -= MARGIN;
+    max_y += MARGIN;
+
+    let mut s = String::new();
+
+    // x points north and y points west, so both are negated to get north up and east right.
+    writeln!(
+        s,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        -max_y,
+        -max_x,
+        max_y - min_y,
+        max_x - min_x
+    )
+    .unwrap();
+
+    writeln!(s, r#"<g stroke="black" stroke-width="0.1">"#).unwrap();
+    for (tile, polygon) in view.polygons() {
+        if !NavmeshArea::All.contains(polygon) {
+            continue;
+        }
+
+        let vertices = tile.polygon_vertices(polygon).collect::<Vec<_>>();
+        let inside = vertices
+            .iter()
+            .any(|v| v.x >= min_x && v.x <= max_x && v.y >= min_y && v.y <= max_y);
+        if !inside {
+            continue;
+        }
+
+        writeln!(
+            s,
+            r#"<polygon points="{}" fill="{}"><title>{}</title></polygon>"#,
+            svg_points(&vertices),
+            polygon_color(polygon),
+            polygon.reference
+        )
+        .unwrap();
+    }
+    writeln!(s, "</g>").unwrap();
+
+    for probe in probes {
+        let color = if probe.line_of_sight {
+            CLEAR_PROBE_COLOR
+        } else {
+            BLOCKED_PROBE_COLOR
+        };
+
+        writeln!(
+            s,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="0.15" stroke-dasharray="0.5 0.5"/>"#,
+            -probe.from.y, -probe.from.x, -probe.to.y, -probe.to.x, color
+        )
+        .unwrap();
+    }
+
+    match path {
+        Ok(path) => {
+            writeln!(
+                s,
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="0.3"/>"#,
+                svg_points(path),
+                PATH_COLOR
+            )
+            .unwrap();
+        }
+        Err(e) => {
+            writeln!(
+                s,
+                r#"<text x="{}" y="{}" font-size="2">{}</text>"#,
+                -max_y + 1.0,
+                -max_x + 3.0,
+                escape(&e.to_string())
+            )
+            .unwrap();
+        }
+    }
+
+    for (v, color, name) in [(start, "#0000ff", "start"), (stop, "#ffa500", "stop")] {
+        writeln!(
+            s,
+            r#"<circle cx="{}" cy="{}" r="0.6" fill="{}"><title>{} {} {} {}</title></circle>"#,
+            -v.y, -v.x, color, name, v.x, v.y, v.z
+        )
+        .unwrap();
+    }
+
+    s.push_str("</svg>\n");
+
+    s
+}
+
+fn polygon_color(polygon: &NavmeshPolygon) -> String {
+    if !polygon.flags.is_walkable() || polygon.flags.is_steep() {
+        return UNWALKABLE_COLOR.to_string();
+    }
+
+    let area = [
+        NavmeshArea::Liquid,
+        NavmeshArea::Wmo,
+        NavmeshArea::Doodads,
+        NavmeshArea::Terrain,
+    ]
+    .iter()
+    .find(|area| area.contains(polygon))
+    .unwrap_or(&NavmeshArea::All);
+
+    let [r, g, b, _] = area.color().map(|c| (c * 255.0) as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn svg_points(vertices: &[Vector3d]) -> String {
+    vertices
+        .iter()
+        .map(|v| format!("{},{}", -v.y, -v.x))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -92,11 +92,26 @@ pub enum NamigatorError {
     UnknownZoneAndArea,
     FailedToLoadAdt,
     MapDoesNotHaveAdt,
+    UnableToFindRandomPointInCircle,
     FailedToFindPointBetweenVectors,
+    NoPolygonNearPoint,
+    NavmeshQueryFailed,
+    FailedToAddOffMeshConnections,
+    CouldNotOpenLockFile,
+    CouldNotCreateMpq,
 
     UnknownException,
 
     MapIsNullPointer,
+    NoReachablePointFound,
+    Io(std::io::Error),
+    /// Line number, starting at 1, of the first invalid line.
+    InvalidOffMeshConnectionFile(usize),
+    /// Another build of the same map into the same output is running.
+    /// Contains the path of the lock file, which is empty when it is not known.
+    MapBuildLocked(std::path::PathBuf),
+    /// No archive of an [`MpqArchiveSet`](crate::mpq::MpqArchiveSet) contains the file.
+    FileNotFoundInMpq(String),
 }
 
 pub(crate) fn error_code_to_error(v: u8) -> NamigatorError {
@@ -218,10 +233,21 @@ pub(crate) fn error_code_to_error(v: u8) -> NamigatorError {
         namigator_sys::UNKNOWN_ZONE_AND_AREA => NamigatorError::UnknownZoneAndArea,
         namigator_sys::FAILED_TO_LOAD_ADT => NamigatorError::FailedToLoadAdt,
         namigator_sys::MAP_DOES_NOT_HAVE_ADT => NamigatorError::MapDoesNotHaveAdt,
+        namigator_sys::UNABLE_TO_FIND_RANDOM_POINT_IN_CIRCLE => {
+            NamigatorError::UnableToFindRandomPointInCircle
+        }
         namigator_sys::UNKNOWN_EXCEPTION => NamigatorError::UnknownException,
         namigator_sys::FAILED_TO_FIND_POINT_BETWEEN_VECTORS => {
             NamigatorError::FailedToFindPointBetweenVectors
         }
+        namigator_sys::NO_POLYGON_NEAR_POINT => NamigatorError::NoPolygonNearPoint,
+        namigator_sys::NAVMESH_QUERY_FAILED => NamigatorError::NavmeshQueryFailed,
+        namigator_sys::FAILED_TO_ADD_OFF_MESH_CONNECTIONS => {
+            NamigatorError::FailedToAddOffMeshConnections
+        }
+        namigator_sys::FILE_IS_LOCKED => NamigatorError::MapBuildLocked(std::path::PathBuf::new()),
+        namigator_sys::COULD_NOT_OPEN_LOCK_FILE => NamigatorError::CouldNotOpenLockFile,
+        namigator_sys::COULD_NOT_CREATE_MPQ => NamigatorError::CouldNotCreateMpq,
         _ => NamigatorError::UnknownException,
     }
 }
@@ -368,9 +394,32 @@ impl Display for NamigatorError {
             NamigatorError::FailedToLoadAdt => f.write_str("Failed to load ADT"),
             NamigatorError::MapDoesNotHaveAdt => f.write_str("Map does not have ADT"),
             NamigatorError::MapIsNullPointer => f.write_str("Map is null pointer"),
+            NamigatorError::NoReachablePointFound => f.write_str("No reachable point found"),
+            NamigatorError::Io(e) => e.fmt(f),
+            NamigatorError::InvalidOffMeshConnectionFile(line) => {
+                write!(f, "Invalid off-mesh connection file at line {}", line)
+            }
+            NamigatorError::MapBuildLocked(path) => write!(
+                f,
+                "Map is already being built, '{}' is locked",
+                path.display()
+            ),
+            NamigatorError::FileNotFoundInMpq(name) => {
+                write!(f, "File '{}' not found in any MPQ", name)
+            }
+            NamigatorError::UnableToFindRandomPointInCircle => {
+                f.write_str("Unable to find random point in circle")
+            }
             NamigatorError::FailedToFindPointBetweenVectors => {
                 f.write_str("Failed to find point between vectors")
             }
+            NamigatorError::NoPolygonNearPoint => f.write_str("No polygon near point"),
+            NamigatorError::NavmeshQueryFailed => f.write_str("Navmesh query failed"),
+            NamigatorError::FailedToAddOffMeshConnections => {
+                f.write_str("Failed to add off-mesh connections")
+            }
+            NamigatorError::CouldNotOpenLockFile => f.write_str("Could not open lock file"),
+            NamigatorError::CouldNotCreateMpq => f.write_str("Could not create MPQ"),
         }
     }
 }
@@ -382,3 +431,9 @@ impl From<NulError> for NamigatorError {
         Self::CStringConversion(e)
     }
 }
+
+impl From<std::io::Error> for NamigatorError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
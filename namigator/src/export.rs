@@ -0,0 +1,257 @@
+use crate::error::NamigatorError;
+use crate::geometry::{collision_geometry, CollisionGeometry, Mesh};
+use crate::navmesh::{NavmeshPolygon, NavmeshTile, NavmeshView};
+use crate::pathfind::PathfindMap;
+use crate::util::adt_coordinates;
+use crate::Vector3d;
+use std::io::Write;
+use std::path::Path;
+
+/// Maps are at most this many ADTs wide and tall.
+const ADTS_PER_SIDE: i32 = 64;
+
+/// Layer of an export, each layer is written as its own object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ExportLayer {
+    /// Polygons of the navmesh.
+    Navmesh,
+    /// Triangles of the ADT terrain.
+    Terrain,
+    /// Triangles of the WMOs.
+    Wmo,
+    /// Triangles of the doodads, including the doodads placed inside WMOs.
+    Doodads,
+    /// Surfaces of the ADT and WMO liquids.
+    Liquid,
+}
+
+impl ExportLayer {
+    pub(crate) const fn name(&self) -> &'static str {
+        match self {
+            ExportLayer::Navmesh => "navmesh",
+            ExportLayer::Terrain => "terrain",
+            ExportLayer::Wmo => "wmo",
+            ExportLayer::Doodads => "doodads",
+            ExportLayer::Liquid => "liquid",
+        }
+    }
+}
+
+/// Navmesh polygons selected by the kind of ground they were built on, used when rendering the navmesh.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NavmeshArea {
+    /// The whole navmesh.
+    All,
+    /// Polygons built on top of ADT terrain.
+    Terrain,
+    /// Polygons built on top of WMOs.
+    Wmo,
+    /// Polygons built on top of doodads.
+    Doodads,
+    /// Polygons built on top of liquid.
+    Liquid,
+}
+
+impl NavmeshArea {
+    pub(crate) fn contains(&self, polygon: &NavmeshPolygon) -> bool {
+        if polygon.off_mesh_connection || polygon.indices.len() < 3 {
+            return false;
+        }
+
+        match self {
+            NavmeshArea::All => true,
+            NavmeshArea::Terrain => polygon.area.is_adt(),
+            NavmeshArea::Wmo => polygon.area.is_wmo(),
+            NavmeshArea::Doodads => polygon.area.is_doodad(),
+            NavmeshArea::Liquid => polygon.area.is_liquid(),
+        }
+    }
+
+    /// RGBA color of the area.
+    pub(crate) const fn color(&self) -> [f32; 4] {
+        match self {
+            NavmeshArea::All => [0.4, 0.6, 0.8, 1.0],
+            NavmeshArea::Terrain => [0.3, 0.6, 0.2, 1.0],
+            NavmeshArea::Wmo => [0.6, 0.4, 0.3, 1.0],
+            NavmeshArea::Doodads => [0.9, 0.6, 0.1, 1.0],
+            NavmeshArea::Liquid => [0.1, 0.3, 0.9, 0.6],
+        }
+    }
+}
+
+impl PathfindMap {
+    /// Write the ADTs at `tiles` to a Wavefront OBJ file with an object for every layer in `include`.
+    ///
+    /// The terrain, WMO, doodad and liquid layers are the collision geometry that the navmesh was built from.
+    /// They are read from the client data in `data_path`, the directory that was passed to
+    /// [`build_map`](crate::raw::build_map), which is not touched when only [`ExportLayer::Navmesh`] is included.
+    ///
+    /// ADTs that are not loaded are loaded first, and an empty `tiles` exports every ADT that is already loaded.
+    /// Positions are rotated so that the y axis points up, which is what Blender and MeshLab expect.
+    pub fn export_obj(
+        &mut self,
+        data_path: impl AsRef<Path>,
+        tiles: &[(i32, i32)],
+        include: &[ExportLayer],
+        path: impl AsRef<Path>,
+    ) -> Result<(), NamigatorError> {
+        let layers = self.export_layers(data_path.as_ref(), tiles, include)?;
+
+        let mut out = Vec::new();
+        write_obj(&layers, &mut out)?;
+        std::fs::write(path, out)?;
+
+        Ok(())
+    }
+
+    /// Load the ADTs at `tiles` and return the mesh of every layer in `include`, in order and without duplicates.
+    pub(crate) fn export_layers(
+        &mut self,
+        data_path: &Path,
+        tiles: &[(i32, i32)],
+        include: &[ExportLayer],
+    ) -> Result<Vec<(ExportLayer, Mesh)>, NamigatorError> {
+        let view = self.export_view(tiles)?;
+
+        let mut geometry = if include.iter().any(|&l| l != ExportLayer::Navmesh) {
+            let adts = if tiles.is_empty() {
+                self.loaded_adts()?
+            } else {
+                tiles.to_vec()
+            };
+
+            collision_geometry(data_path, &self.map_name, &adts)?
+        } else {
+            CollisionGeometry::default()
+        };
+
+        let mut layers: Vec<(ExportLayer, Mesh)> = Vec::with_capacity(include.len());
+        for &layer in include {
+            if layers.iter().any(|(l, _)| *l == layer) {
+                continue;
+            }
+
+            let mesh = match layer {
+                ExportLayer::Navmesh => navmesh_mesh(&view, tiles),
+                ExportLayer::Terrain => std::mem::take(&mut geometry.terrain),
+                ExportLayer::Wmo => std::mem::take(&mut geometry.wmo),
+                ExportLayer::Doodads => std::mem::take(&mut geometry.doodads),
+                ExportLayer::Liquid => std::mem::take(&mut geometry.liquid),
+            };
+            layers.push((layer, mesh));
+        }
+
+        Ok(layers)
+    }
+
+    /// Load the ADTs at `tiles` and return a view of the navmesh.
+    pub(crate) fn export_view(
+        &mut self,
+        tiles: &[(i32, i32)],
+    ) -> Result<NavmeshView, NamigatorError> {
+        for &(x, y) in tiles {
+            if !self.adt_loaded(x, y)? {
+                self.load_adt(x, y)?;
+            }
+        }
+
+        self.navmesh_view()
+    }
+
+    /// Coordinates of every loaded ADT, empty for maps without ADTs.
+    fn loaded_adts(&self) -> Result<Vec<(i32, i32)>, NamigatorError> {
+        let mut adts = Vec::new();
+
+        if self.has_adts()? {
+            for x in 0..ADTS_PER_SIDE {
+                for y in 0..ADTS_PER_SIDE {
+                    if self.adt_loaded(x, y)? {
+                        adts.push((x, y));
+                    }
+                }
+            }
+        }
+
+        Ok(adts)
+    }
+}
+
+/// Whether `polygon` is in one of the ADTs at `tiles`, or `tiles` is empty.
+pub(crate) fn in_tiles(tile: &NavmeshTile, polygon: &NavmeshPolygon, tiles: &[(i32, i32)]) -> bool {
+    if tiles.is_empty() {
+        return true;
+    }
+
+    let count = polygon.indices.len() as f32;
+    let (x, y) = tile
+        .polygon_vertices(polygon)
+        .fold((0.0, 0.0), |(x, y), v| (x + v.x, y + v.y));
+
+    tiles.contains(&adt_coordinates(x / count, y / count))
+}
+
+/// Navmesh polygons in the ADTs at `tiles` as triangles.
+pub(crate) fn navmesh_mesh(view: &NavmeshView, tiles: &[(i32, i32)]) -> Mesh {
+    let mut mesh = Mesh::default();
+
+    for tile in view.tiles() {
+        let mut remap = vec![None; tile.vertices.len()];
+
+        for polygon in &tile.polygons {
+            if !NavmeshArea::All.contains(polygon) || !in_tiles(tile, polygon, tiles) {
+                continue;
+            }
+
+            let indices = polygon
+                .indices
+                .iter()
+                .map(|&i| {
+                    let i = usize::from(i);
+                    *remap[i].get_or_insert_with(|| {
+                        mesh.vertices.push(tile.vertices[i]);
+                        (mesh.vertices.len() - 1) as u32
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            mesh.push_polygon(&indices);
+        }
+    }
+
+    mesh
+}
+
+/// Rotate a world position so that the y axis points up, as expected by most 3D tools.
+pub(crate) fn y_up(v: Vector3d) -> [f32; 3] {
+    [-v.y, v.z, -v.x]
+}
+
+pub(crate) fn write_obj(layers: &[(ExportLayer, Mesh)], w: &mut impl Write) -> std::io::Result<()> {
+    writeln!(w, "# namigator export")?;
+
+    // OBJ indices start at 1 and are shared between all objects in the file.
+    let mut first_index = 1_usize;
+
+    for (layer, mesh) in layers {
+        writeln!(w, "o {}", layer.name())?;
+
+        for &v in &mesh.vertices {
+            let [x, y, z] = y_up(v);
+            writeln!(w, "v {} {} {}", x, y, z)?;
+        }
+
+        for triangle in mesh.indices.chunks_exact(3) {
+            writeln!(
+                w,
+                "f {} {} {}",
+                first_index + triangle[0] as usize,
+                first_index + triangle[1] as usize,
+                first_index + triangle[2] as usize
+            )?;
+        }
+
+        first_index += mesh.vertices.len();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::error::{error_code_to_error, NamigatorError};
+use crate::util::path_to_cstr;
+use crate::Vector3d;
+use namigator_sys::{
+    mapbuild_collision_geometry, GEOMETRY_DOODAD, GEOMETRY_LIQUID, GEOMETRY_TERRAIN, GEOMETRY_WMO,
+    SUCCESS,
+};
+use std::ffi::{c_float, c_int, c_uchar, c_uint, c_void, CString};
+use std::path::Path;
+
+/// Triangles in world coordinates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Mesh {
+    pub vertices: Vec<Vector3d>,
+    /// Three indices into `vertices` per triangle.
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Add a polygon as a fan of triangles around its first vertex.
+    pub(crate) fn push_polygon(&mut self, indices: &[u32]) {
+        for k in 1..indices.len().saturating_sub(1) {
+            self.indices
+                .extend_from_slice(&[indices[0], indices[k], indices[k + 1]]);
+        }
+    }
+}
+
+/// Geometry that the navmesh of a map is built from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CollisionGeometry {
+    pub terrain: Mesh,
+    pub liquid: Mesh,
+    pub wmo: Mesh,
+    pub doodads: Mesh,
+}
+
+/// Read the collision geometry of the ADTs at `adts` of `map_name` from the client data in `data_path`.
+///
+/// Maps without ADTs are a single WMO, which is read regardless of `adts`.
+pub(crate) fn collision_geometry(
+    data_path: &Path,
+    map_name: &str,
+    adts: &[(i32, i32)],
+) -> Result<CollisionGeometry, NamigatorError> {
+    extern "C" fn collect(
+        state: *mut c_void,
+        layer: c_uchar,
+        vertices: *const c_float,
+        amount_of_vertices: c_uint,
+        indices: *const c_int,
+        amount_of_indices: c_uint,
+    ) {
+        // SAFETY: state is always the `&mut CollisionGeometry` passed to `mapbuild_collision_geometry`.
+        let geometry = unsafe { &mut *(state as *mut CollisionGeometry) };
+        let mesh = match layer {
+            GEOMETRY_TERRAIN => &mut geometry.terrain,
+            GEOMETRY_LIQUID => &mut geometry.liquid,
+            GEOMETRY_WMO => &mut geometry.wmo,
+            GEOMETRY_DOODAD => &mut geometry.doodads,
+            _ => return,
+        };
+
+        // SAFETY: namigator passes the lengths of the buffers, which are valid for the duration of the call.
+        let (vertices, indices) = unsafe {
+            (
+                std::slice::from_raw_parts(vertices, amount_of_vertices as usize * 3),
+                std::slice::from_raw_parts(indices, amount_of_indices as usize),
+            )
+        };
+
+        let offset = mesh.vertices.len() as u32;
+        mesh.vertices
+            .extend(vertices.chunks_exact(3).map(|v| Vector3d {
+                x: v[0],
+                y: v[1],
+                z: v[2],
+            }));
+        mesh.indices
+            .extend(indices.iter().map(|&i| offset + i as u32));
+    }
+
+    let data_path = path_to_cstr(data_path)?;
+    let map_name = CString::new(map_name)?;
+    let adts = adts.iter().flat_map(|&(x, y)| [x, y]).collect::<Vec<_>>();
+
+    let mut geometry = CollisionGeometry::default();
+    // SAFETY: CStrings are valid pointers and geometry outlives the call.
+    let result = unsafe {
+        mapbuild_collision_geometry(
+            data_path.as_ptr(),
+            map_name.as_ptr(),
+            adts.as_ptr(),
+            (adts.len() / 2) as c_uint,
+            collect,
+            &mut geometry as *mut CollisionGeometry as *mut c_void,
+        )
+    };
+
+    if result != SUCCESS {
+        return Err(error_code_to_error(result));
+    }
+
+    Ok(geometry)
+}
@@ -0,0 +1,284 @@
+use crate::error::NamigatorError;
+use crate::export::{y_up, ExportLayer, NavmeshArea};
+use crate::geometry::Mesh;
+use crate::json::Json;
+use crate::pathfind::PathfindMap;
+use crate::Vector3d;
+use std::path::Path;
+
+/// glTF `ARRAY_BUFFER` and `ELEMENT_ARRAY_BUFFER` buffer view targets.
+const TARGET_VERTICES: u32 = 34962;
+const TARGET_INDICES: u32 = 34963;
+
+/// glTF `FLOAT` and `UNSIGNED_INT` accessor component types.
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+
+/// Binary glTF header and chunk types.
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+
+/// glTF primitive modes.
+const MODE_LINE_STRIP: u32 = 3;
+const MODE_TRIANGLES: u32 = 4;
+
+/// Paths are raised by this much so that they are not hidden inside the navmesh.
+const PATH_HEIGHT_OFFSET: f32 = 0.5;
+
+impl PathfindMap {
+    /// Write the ADTs at `tiles` to a binary glTF 2.0 (`.glb`) file with a mesh and material for every layer in `include`.
+    ///
+    /// The layers are read the same way as for [`Self::export_obj`], so `data_path` is the client data directory.
+    /// `path` is drawn as a red line on top of the navmesh, for example the result of [`Self::find_path`].
+    ///
+    /// ADTs that are not loaded are loaded first, and an empty `tiles` exports every ADT that is already loaded.
+    /// The file is self contained and can be opened in any glTF viewer.
+    pub fn export_gltf(
+        &mut self,
+        data_path: impl AsRef<Path>,
+        tiles: &[(i32, i32)],
+        include: &[ExportLayer],
+        path: Option<&[Vector3d]>,
+        out: impl AsRef<Path>,
+    ) -> Result<(), NamigatorError> {
+        let layers = self.export_layers(data_path.as_ref(), tiles, include)?;
+
+        std::fs::write(out, write_gltf(&layers, path))?;
+
+        Ok(())
+    }
+}
+
+impl ExportLayer {
+    /// RGBA base color of the layer material.
+    const fn color(&self) -> [f32; 4] {
+        match self {
+            ExportLayer::Navmesh => NavmeshArea::All.color(),
+            ExportLayer::Terrain => NavmeshArea::Terrain.color(),
+            ExportLayer::Wmo => NavmeshArea::Wmo.color(),
+            ExportLayer::Doodads => NavmeshArea::Doodads.color(),
+            ExportLayer::Liquid => NavmeshArea::Liquid.color(),
+        }
+    }
+}
+
+const PATH_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+struct Primitive<'a> {
+    name: &'static str,
+    color: [f32; 4],
+    mode: u32,
+    positions: Vec<[f32; 3]>,
+    /// Not used for line strips.
+    indices: &'a [u32],
+}
+
+pub(crate) fn write_gltf(layers: &[(ExportLayer, Mesh)], path: Option<&[Vector3d]>) -> Vec<u8> {
+    let mut primitives = layers
+        .iter()
+        .map(|(layer, mesh)| Primitive {
+            name: layer.name(),
+            color: layer.color(),
+            mode: MODE_TRIANGLES,
+            positions: mesh.vertices.iter().map(|&v| y_up(v)).collect(),
+            indices: &mesh.indices,
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(path) = path {
+        primitives.push(Primitive {
+            name: "path",
+            color: PATH_COLOR,
+            mode: MODE_LINE_STRIP,
+            positions: path
+                .iter()
+                .map(|&p| {
+                    y_up(Vector3d {
+                        z: p.z + PATH_HEIGHT_OFFSET,
+                        ..p
+                    })
+                })
+                .collect(),
+            indices: &[],
+        });
+    }
+
+    // Accessors must have at least one element.
+    primitives
+        .retain(|p| p.positions.len() >= 2 && (p.mode != MODE_TRIANGLES || !p.indices.is_empty()));
+
+    let mut buffer = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut materials = Vec::new();
+    let mut meshes = Vec::new();
+    let mut nodes = Vec::new();
+
+    for (i, primitive) in primitives.iter().enumerate() {
+        let (min, max) = bounds(&primitive.positions);
+
+        buffer_views.push(buffer_view(
+            buffer.len(),
+            primitive.positions.len() * 12,
+            TARGET_VERTICES,
+        ));
+        for c in primitive.positions.iter().flatten() {
+            buffer.extend_from_slice(&c.to_le_bytes());
+        }
+        accessors.push(Json::object([
+            ("bufferView", (buffer_views.len() - 1).into()),
+            ("componentType", COMPONENT_FLOAT.into()),
+            ("count", primitive.positions.len().into()),
+            ("type", "VEC3".into()),
+            ("min", floats(&min)),
+            ("max", floats(&max)),
+        ]));
+
+        let mut mesh_primitive = vec![(
+            "attributes",
+            Json::object([("POSITION", (accessors.len() - 1).into())]),
+        )];
+
+        if !primitive.indices.is_empty() {
+            buffer_views.push(buffer_view(
+                buffer.len(),
+                primitive.indices.len() * 4,
+                TARGET_INDICES,
+            ));
+            for index in primitive.indices {
+                buffer.extend_from_slice(&index.to_le_bytes());
+            }
+            accessors.push(Json::object([
+                ("bufferView", (buffer_views.len() - 1).into()),
+                ("componentType", COMPONENT_UNSIGNED_INT.into()),
+                ("count", primitive.indices.len().into()),
+                ("type", "SCALAR".into()),
+            ]));
+
+            mesh_primitive.push(("indices", (accessors.len() - 1).into()));
+        }
+
+        mesh_primitive.push(("material", i.into()));
+        mesh_primitive.push(("mode", primitive.mode.into()));
+
+        let alpha_mode = if primitive.color[3] < 1.0 {
+            "BLEND"
+        } else {
+            "OPAQUE"
+        };
+        materials.push(Json::object([
+            ("name", primitive.name.into()),
+            ("doubleSided", true.into()),
+            ("alphaMode", alpha_mode.into()),
+            (
+                "pbrMetallicRoughness",
+                Json::object([
+                    ("baseColorFactor", floats(&primitive.color)),
+                    ("metallicFactor", 0_u32.into()),
+                    ("roughnessFactor", 1_u32.into()),
+                ]),
+            ),
+        ]));
+
+        meshes.push(Json::object([
+            ("name", primitive.name.into()),
+            (
+                "primitives",
+                Json::Array(vec![Json::Object(mesh_primitive)]),
+            ),
+        ]));
+        nodes.push(Json::object([
+            ("name", primitive.name.into()),
+            ("mesh", i.into()),
+        ]));
+    }
+
+    let mut gltf = vec![
+        (
+            "asset",
+            Json::object([("version", "2.0".into()), ("generator", "namigator".into())]),
+        ),
+        ("scene", 0_u32.into()),
+        (
+            "scenes",
+            Json::Array(vec![Json::object([(
+                "nodes",
+                Json::Array((0..nodes.len()).map(Json::from).collect()),
+            )])]),
+        ),
+    ];
+
+    if !nodes.is_empty() {
+        gltf.extend([
+            ("nodes", Json::Array(nodes)),
+            ("meshes", Json::Array(meshes)),
+            ("materials", Json::Array(materials)),
+            ("accessors", Json::Array(accessors)),
+            ("bufferViews", Json::Array(buffer_views)),
+            (
+                "buffers",
+                Json::Array(vec![Json::object([("byteLength", buffer.len().into())])]),
+            ),
+        ]);
+    }
+
+    glb(&Json::Object(gltf).to_string(), &buffer)
+}
+
+fn buffer_view(offset: usize, length: usize, target: u32) -> Json {
+    Json::object([
+        ("buffer", 0_u32.into()),
+        ("byteOffset", offset.into()),
+        ("byteLength", length.into()),
+        ("target", target.into()),
+    ])
+}
+
+fn floats(values: &[f32]) -> Json {
+    Json::Array(values.iter().map(|&v| v.into()).collect())
+}
+
+fn bounds(positions: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+
+    for p in positions {
+        for ((min, max), &c) in min.iter_mut().zip(&mut max).zip(p) {
+            *min = min.min(c);
+            *max = max.max(c);
+        }
+    }
+
+    (min, max)
+}
+
+/// Binary glTF container with a JSON chunk and a binary chunk for `buffer`, if it is not empty.
+fn glb(json: &str, buffer: &[u8]) -> Vec<u8> {
+    fn chunk(out: &mut Vec<u8>, kind: u32, data: &[u8], padding: u8) {
+        // Chunks must start and end on four byte boundaries.
+        let length = (data.len() + 3) / 4 * 4;
+
+        out.extend_from_slice(&(length as u32).to_le_bytes());
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(data);
+        out.resize(out.len() + length - data.len(), padding);
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+    out.extend_from_slice(&GLB_VERSION.to_le_bytes());
+    // Total length, written once known.
+    out.extend_from_slice(&0_u32.to_le_bytes());
+
+    chunk(&mut out, GLB_CHUNK_JSON, json.as_bytes(), b' ');
+    if !buffer.is_empty() {
+        chunk(&mut out, GLB_CHUNK_BIN, buffer, 0);
+    }
+
+    let length = out.len() as u32;
+    out[8..12].copy_from_slice(&length.to_le_bytes());
+
+    out
+}
@@ -0,0 +1,98 @@
+use std::fmt::{Display, Formatter};
+
+/// JSON value for the glTF exports.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    pub(crate) fn object(fields: impl IntoIterator<Item = (&'static str, Json)>) -> Self {
+        Self::Object(fields.into_iter().collect())
+    }
+}
+
+impl From<bool> for Json {
+    fn from(v: bool) -> Self {
+        Self::Bool(v)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(v: u32) -> Self {
+        Self::Number(f64::from(v))
+    }
+}
+
+impl From<usize> for Json {
+    fn from(v: usize) -> Self {
+        Self::Number(v as f64)
+    }
+}
+
+impl From<f32> for Json {
+    fn from(v: f32) -> Self {
+        Self::Number(f64::from(v))
+    }
+}
+
+impl From<&str> for Json {
+    fn from(v: &str) -> Self {
+        Self::String(v.to_string())
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Bool(v) => write!(f, "{}", v),
+            // JSON has no infinity or NaN.
+            Json::Number(v) if !v.is_finite() => f.write_str("null"),
+            Json::Number(v) => write!(f, "{}", v),
+            Json::String(v) => write_string(f, v),
+            Json::Array(v) => {
+                f.write_str("[")?;
+                for (i, v) in v.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(v) => {
+                f.write_str("{")?;
+                for (i, (key, v)) in v.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", v)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_str("\"")?;
+
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if u32::from(c) < 0x20 => write!(f, "\\u{:04x}", u32::from(c))?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+
+    f.write_str("\"")
+}
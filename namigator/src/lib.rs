@@ -7,8 +7,41 @@ extern crate namigator_sys;
 
 mod build;
 
+mod manifest;
+
+mod staging;
+
+mod verify;
+
 mod pathfind;
 
+mod movement;
+
+mod navmesh;
+
+mod offmesh;
+
+mod path;
+
+mod polygon;
+
+mod transport;
+
+mod export;
+
+mod geometry;
+
+mod gltf;
+
+mod json;
+
+mod render;
+
+#[cfg(feature = "debug-render")]
+pub mod debug;
+
+pub mod mpq;
+
 mod error;
 pub use error::*;
 
@@ -41,7 +74,17 @@ pub mod raw {
     //! ```
     //!
     pub use crate::build::*;
+    pub use crate::export::{ExportLayer, NavmeshArea};
+    pub use crate::manifest::{build_status, BuildStatus, StaleReason};
+    pub use crate::movement::*;
+    pub use crate::navmesh::*;
+    pub use crate::offmesh::*;
+    pub use crate::path::{PathOptions, PathSegment, PathSmoothing, MIN_PATH_STEP};
     pub use crate::pathfind::*;
+    pub use crate::polygon::*;
+    pub use crate::transport::*;
+    pub use crate::util::{adt_coordinates, adts_on_line};
+    pub use crate::verify::{verify_map_output, VerificationProblem, VerificationReport};
 }
 
 #[cfg(feature = "vanilla")]
@@ -75,5 +118,19 @@ pub mod wrath {
     );
 }
 
+#[cfg(feature = "alpha")]
+mod alpha_map;
+
+#[cfg(feature = "alpha")]
+pub mod alpha {
+    //! The 0.5.3 alpha client, which keeps every file in its own `MPQ` archive.
+    crate::util::specific_pathfind!(
+        crate::alpha_map::Map,
+        AlphaMap,
+        crate::alpha_map::Area,
+        crate::alpha_map::Area
+    );
+}
+
 #[cfg(test)]
 mod test;
@@ -0,0 +1,420 @@
+use crate::build::map_files_exist;
+use crate::error::NamigatorError;
+use crate::offmesh::map_off_mesh_connections_path;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+const MANIFEST_FILE_NAME: &str = "BuildManifest.txt";
+
+const KEY_FORMAT_VERSION: &str = "format_version";
+const KEY_NAMIGATOR_VERSION: &str = "namigator_sys_version";
+const KEY_MAP: &str = "map";
+const KEY_DATA_PATH: &str = "data_path";
+const KEY_GAMEOBJECT_CSV: &str = "gameobject_csv";
+const KEY_OFF_MESH_CONNECTIONS_HASH: &str = "off_mesh_connections_hash";
+/// Prefix of the keys for MPQ files, followed by the path relative to the data directory.
+const KEY_MPQ_PREFIX: &str = "mpq:";
+
+/// Hashes of files that have already been read, by path.
+///
+/// Reading every MPQ again for every map when building all maps would take a long time.
+static HASH_CACHE: Mutex<Option<HashMap<PathBuf, FileHash>>> = Mutex::new(None);
+
+/// Whether the files of a map are built from the current inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildStatus {
+    /// The map has not been built.
+    Missing,
+    UpToDate,
+    /// The map files exist but should be built again.
+    Stale(StaleReason),
+}
+
+impl BuildStatus {
+    /// Whether the map has to be built before it can be used with the current inputs.
+    ///
+    /// Maps without a manifest, such as maps built before manifests were written, are not
+    /// considered to need a build since there is nothing to compare them to.
+    /// They are only built again when [`build_map`](crate::raw::build_map) is called for them.
+    pub fn needs_build(&self) -> bool {
+        match self {
+            BuildStatus::Missing => true,
+            BuildStatus::UpToDate | BuildStatus::Stale(StaleReason::NoManifest) => false,
+            BuildStatus::Stale(_) => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaleReason {
+    /// The map was built before manifests were written, or the manifest was removed.
+    NoManifest,
+    /// The map was built with another version of the nav and BVH file format.
+    ///
+    /// The version in the manifest is included, or `None` if the manifest has no version.
+    FormatVersionChanged(Option<u32>),
+    /// The map was built with another version of the bundled namigator, which can build different navmeshes
+    /// from the same inputs even when the file format is the same.
+    ///
+    /// The `namigator-sys` version in the manifest is included, or `None` if the manifest has no version.
+    NamigatorVersionChanged(Option<String>),
+    /// The manifest was written for another map.
+    ConfigChanged,
+    GameobjectCsvChanged,
+    /// The off-mesh connections stored next to the map files were changed, added or removed.
+    OffMeshConnectionsChanged,
+    /// MPQ path relative to the data directory.
+    MpqAdded(String),
+    MpqRemoved(String),
+    MpqChanged(String),
+}
+
+impl Display for StaleReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StaleReason::NoManifest => f.write_str("no build manifest"),
+            StaleReason::FormatVersionChanged(Some(v)) => {
+                write!(f, "built with file format version {}", v)
+            }
+            StaleReason::FormatVersionChanged(None) => {
+                f.write_str("built with an unknown file format version")
+            }
+            StaleReason::NamigatorVersionChanged(Some(v)) => {
+                write!(f, "built with namigator-sys {}", v)
+            }
+            StaleReason::NamigatorVersionChanged(None) => {
+                f.write_str("built with an unknown version of namigator-sys")
+            }
+            StaleReason::ConfigChanged => f.write_str("build configuration changed"),
+            StaleReason::GameobjectCsvChanged => f.write_str("gameobject CSV changed"),
+            StaleReason::OffMeshConnectionsChanged => f.write_str("off-mesh connections changed"),
+            StaleReason::MpqAdded(p) => write!(f, "MPQ '{}' added", p),
+            StaleReason::MpqRemoved(p) => write!(f, "MPQ '{}' removed", p),
+            StaleReason::MpqChanged(p) => write!(f, "MPQ '{}' changed", p),
+        }
+    }
+}
+
+/// Check whether `map_name` in `output_path` was built from the current MPQ files, gameobject CSV,
+/// off-mesh connections, file format and version of the bundled namigator.
+///
+/// The inputs are read from the paths used for the build, which are stored in a manifest next to the map files by
+/// [`build_map`](crate::raw::build_map).
+/// MPQ files are only compared when the data directory still exists, so that built maps can be checked without the client.
+///
+/// Files are only read again when their size or modification time has changed.
+pub fn build_status(
+    output_path: impl AsRef<Path>,
+    map_name: &str,
+) -> Result<BuildStatus, NamigatorError> {
+    fn inner(output_path: &Path, map_name: &str) -> Result<BuildStatus, NamigatorError> {
+        if !map_files_exist(output_path, map_name)? {
+            return Ok(BuildStatus::Missing);
+        }
+
+        manifest_status(output_path, map_name)
+    }
+
+    inner(output_path.as_ref(), map_name)
+}
+
+/// Compare the manifest of `map_name` to the current inputs, assuming that the map files exist.
+pub(crate) fn manifest_status(
+    output_path: &Path,
+    map_name: &str,
+) -> Result<BuildStatus, NamigatorError> {
+    let old = match std::fs::read_to_string(manifest_path(output_path, map_name)) {
+        Ok(s) => parse_manifest(&s),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(BuildStatus::Stale(StaleReason::NoManifest))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let get = |key: &str| old.get(key).map(String::as_str).unwrap_or("");
+
+    let version = get(KEY_FORMAT_VERSION).parse().ok();
+    if version != Some(namigator_sys::FILE_FORMAT_VERSION) {
+        return Ok(BuildStatus::Stale(StaleReason::FormatVersionChanged(
+            version,
+        )));
+    }
+
+    let namigator_version = old.get(KEY_NAMIGATOR_VERSION);
+    if namigator_version.map(String::as_str) != Some(namigator_sys::VERSION) {
+        return Ok(BuildStatus::Stale(StaleReason::NamigatorVersionChanged(
+            namigator_version.cloned(),
+        )));
+    }
+
+    if get(KEY_MAP) != map_name {
+        return Ok(BuildStatus::Stale(StaleReason::ConfigChanged));
+    }
+
+    let data_path = PathBuf::from(get(KEY_DATA_PATH));
+    let gameobject_csv = PathBuf::from(get(KEY_GAMEOBJECT_CSV));
+
+    let new = manifest(&data_path, output_path, map_name, &gameobject_csv, &old)?;
+
+    let hash = |m: &BTreeMap<String, String>, key: &str| {
+        m.get(key).and_then(|h| FileHash::parse(h)).map(|h| h.hash)
+    };
+    if hash(&old, &gameobject_csv_hash_key()) != hash(&new, &gameobject_csv_hash_key()) {
+        return Ok(BuildStatus::Stale(StaleReason::GameobjectCsvChanged));
+    }
+    if hash(&old, KEY_OFF_MESH_CONNECTIONS_HASH) != hash(&new, KEY_OFF_MESH_CONNECTIONS_HASH) {
+        return Ok(BuildStatus::Stale(StaleReason::OffMeshConnectionsChanged));
+    }
+
+    if data_path.is_dir() {
+        let mpqs = |m: &BTreeMap<String, String>| {
+            m.iter()
+                .filter_map(|(k, v)| Some((k.strip_prefix(KEY_MPQ_PREFIX)?.to_string(), v.clone())))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let (old_mpqs, new_mpqs) = (mpqs(&old), mpqs(&new));
+
+        for (path, entry) in &new_mpqs {
+            match old_mpqs.get(path) {
+                None => return Ok(BuildStatus::Stale(StaleReason::MpqAdded(path.clone()))),
+                Some(old_entry)
+                    if FileHash::parse(old_entry).map(|h| h.hash)
+                        != FileHash::parse(entry).map(|h| h.hash) =>
+                {
+                    return Ok(BuildStatus::Stale(StaleReason::MpqChanged(path.clone())))
+                }
+                Some(_) => {}
+            }
+        }
+
+        if let Some(path) = old_mpqs.keys().find(|p| !new_mpqs.contains_key(*p)) {
+            return Ok(BuildStatus::Stale(StaleReason::MpqRemoved(path.clone())));
+        }
+    }
+
+    Ok(BuildStatus::UpToDate)
+}
+
+/// Write the manifest of a map that was just built.
+pub(crate) fn write_manifest(
+    data_path: &Path,
+    output_path: &Path,
+    map_name: &str,
+    gameobject_csv: &Path,
+) -> Result<(), NamigatorError> {
+    let manifest = manifest(
+        data_path,
+        output_path,
+        map_name,
+        gameobject_csv,
+        &BTreeMap::new(),
+    )?;
+
+    std::fs::write(
+        manifest_path(output_path, map_name),
+        format_manifest(&manifest),
+    )?;
+
+    Ok(())
+}
+
+fn manifest_path(output_path: &Path, map_name: &str) -> PathBuf {
+    output_path
+        .join("Nav")
+        .join(map_name)
+        .join(MANIFEST_FILE_NAME)
+}
+
+fn gameobject_csv_hash_key() -> String {
+    format!("{}_hash", KEY_GAMEOBJECT_CSV)
+}
+
+/// Manifest of the current inputs, reusing hashes from `previous` for files with the same size and modification time.
+///
+/// The off-mesh connections are read from the map files in `output_path`.
+fn manifest(
+    data_path: &Path,
+    output_path: &Path,
+    map_name: &str,
+    gameobject_csv: &Path,
+    previous: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, NamigatorError> {
+    let mut manifest = BTreeMap::new();
+
+    manifest.insert(
+        KEY_FORMAT_VERSION.to_string(),
+        namigator_sys::FILE_FORMAT_VERSION.to_string(),
+    );
+    manifest.insert(
+        KEY_NAMIGATOR_VERSION.to_string(),
+        namigator_sys::VERSION.to_string(),
+    );
+    manifest.insert(KEY_MAP.to_string(), map_name.to_string());
+    manifest.insert(KEY_DATA_PATH.to_string(), path_string(data_path));
+    manifest.insert(KEY_GAMEOBJECT_CSV.to_string(), path_string(gameobject_csv));
+
+    if gameobject_csv.is_file() {
+        let key = gameobject_csv_hash_key();
+        let hash = FileHash::of(gameobject_csv, previous.get(&key))?;
+        manifest.insert(key, hash.to_string());
+    }
+
+    let connections = map_off_mesh_connections_path(output_path, map_name);
+    if connections.is_file() {
+        let key = KEY_OFF_MESH_CONNECTIONS_HASH;
+        let hash = FileHash::of(&connections, previous.get(key))?;
+        manifest.insert(key.to_string(), hash.to_string());
+    }
+
+    for path in mpq_files(data_path)? {
+        let relative = path
+            .strip_prefix(data_path)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let key = format!("{}{}", KEY_MPQ_PREFIX, relative);
+        let hash = FileHash::of(&path, previous.get(&key))?;
+        manifest.insert(key, hash.to_string());
+    }
+
+    Ok(manifest)
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Every file with the `MPQ` extension, in any case, in `data_path` and its subdirectories.
+fn mpq_files(data_path: &Path) -> Result<Vec<PathBuf>, NamigatorError> {
+    let mut files = Vec::new();
+    if !data_path.is_dir() {
+        return Ok(files);
+    }
+
+    let mut directories = vec![data_path.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                directories.push(path);
+            } else if path
+                .extension()
+                .map_or(false, |e| e.eq_ignore_ascii_case("mpq"))
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+pub(crate) fn parse_manifest(s: &str) -> BTreeMap<String, String> {
+    s.lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+pub(crate) fn format_manifest(manifest: &BTreeMap<String, String>) -> String {
+    manifest
+        .iter()
+        .map(|(key, value)| format!("{}\t{}\n", key, value))
+        .collect()
+}
+
+/// Hash of a file along with the size and modification time it had when hashed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct FileHash {
+    size: u64,
+    /// Nanoseconds since the Unix epoch.
+    modified: u128,
+    hash: u64,
+}
+
+impl FileHash {
+    /// Hash `path`, unless `previous` or an earlier call hashed it with the same size and modification time.
+    fn of(path: &Path, previous: Option<&String>) -> Result<Self, NamigatorError> {
+        let metadata = std::fs::metadata(path)?;
+        let size = metadata.len();
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+
+        let same_file = |h: &FileHash| h.size == size && h.modified == modified;
+
+        if let Some(previous) = previous.and_then(|p| Self::parse(p)).filter(same_file) {
+            return Ok(previous);
+        }
+
+        let mut cache = HASH_CACHE.lock().unwrap();
+        let cache = cache.get_or_insert_with(HashMap::new);
+
+        if let Some(&cached) = cache.get(path).filter(|h| same_file(h)) {
+            return Ok(cached);
+        }
+
+        let hash = Self {
+            size,
+            modified,
+            hash: fnv1a(BufReader::new(File::open(path)?))?,
+        };
+        cache.insert(path.to_path_buf(), hash);
+
+        Ok(hash)
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.split(' ');
+
+        let hash = Self {
+            size: parts.next()?.parse().ok()?,
+            modified: parts.next()?.parse().ok()?,
+            hash: u64::from_str_radix(parts.next()?, 16).ok()?,
+        };
+
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(hash)
+    }
+}
+
+impl Display for FileHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {:016x}", self.size, self.modified, self.hash)
+    }
+}
+
+/// 64 bit FNV-1a hash, which is only used for noticing changed files.
+pub(crate) fn fnv1a(mut reader: impl Read) -> std::io::Result<u64> {
+    const OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01B3;
+
+    let mut hash = OFFSET_BASIS;
+    let mut buffer = [0_u8; 64 * 1024];
+
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(hash);
+        }
+
+        for &b in &buffer[..n] {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+}
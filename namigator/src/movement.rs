@@ -0,0 +1,281 @@
+use crate::error::NamigatorError;
+use crate::pathfind::PathfindMap;
+use crate::util::{distance, path_length};
+use crate::{Vector2d, Vector3d};
+use rand_core::RngCore;
+
+/// Points are raised by this much before checking line of sight so that the ground itself doesn't block it.
+const LINE_OF_SIGHT_HEIGHT: f32 = 2.0;
+
+/// Paths longer than this multiple of the straight line distance are considered detours.
+const MAX_PATH_RATIO: f32 = 1.5;
+
+/// Offsets in degrees from the direction directly away from the threat, in the order they are tried.
+const FLEE_ANGLES: [f32; 7] = [0.0, 30.0, -30.0, 60.0, -60.0, 90.0, -90.0];
+
+/// Fractions of the requested distance that are tried for every angle, in the order they are tried.
+const FLEE_DISTANCE_FRACTIONS: [f32; 3] = [1.0, 0.75, 0.5];
+
+const WANDER_ATTEMPTS: usize = 10;
+
+/// Distance kept between a landing point and the wall that stopped it.
+const LANDING_WALL_OFFSET: f32 = 0.5;
+
+/// Landing points that are too far away are searched for until the distance is known to within this many yards.
+const LANDING_PRECISION: f32 = 0.5;
+
+/// Which way [`PathfindMap::find_landing_point`] travels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LandingDirection {
+    /// Orientation in radians, with `0.0` pointing along the positive x axis.
+    Orientation(f32),
+    /// Towards a position, stopping at the position if it is closer than the max distance.
+    Target(Vector3d),
+}
+
+impl PathfindMap {
+    /// Find a point up to `distance` yards away from `threat`, as seen from `from`, and the path to it.
+    ///
+    /// Directions straight away from `threat` are preferred, turning up to 90 degrees to either side
+    /// and shortening the distance if the way is blocked.
+    /// The returned point is always in line of sight of `from` and connected to it on the navmesh.
+    pub fn flee_point(
+        &mut self,
+        from: Vector3d,
+        threat: Vector3d,
+        distance: f32,
+    ) -> Result<(Vector3d, &[Vector3d]), NamigatorError> {
+        let (x, y) = {
+            let x = from.x - threat.x;
+            let y = from.y - threat.y;
+            let length = (x * x + y * y).sqrt();
+
+            if length > f32::EPSILON {
+                (x / length, y / length)
+            } else {
+                (1.0, 0.0)
+            }
+        };
+
+        let mut target = None;
+        'search: for fraction in FLEE_DISTANCE_FRACTIONS {
+            for angle in FLEE_ANGLES {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let length = distance * fraction;
+
+                let candidate = Vector2d {
+                    x: from.x + (x * cos - y * sin) * length,
+                    y: from.y + (x * sin + y * cos) * length,
+                };
+
+                if let Some(candidate) = self.reachable_candidate(from, candidate, length)? {
+                    target = Some(candidate);
+                    break 'search;
+                }
+            }
+        }
+
+        let target = target.ok_or(NamigatorError::NoReachablePointFound)?;
+        let path = self.find_path(from, target)?;
+
+        Ok((target, path))
+    }
+
+    /// Find a random point within `radius` of `home` and the path from `home` to it.
+    ///
+    /// The returned point is always in line of sight of `home` and connected to it on the navmesh.
+    /// All randomness is drawn from `rng`.
+    pub fn wander_point(
+        &mut self,
+        home: Vector3d,
+        radius: f32,
+        rng: &mut impl RngCore,
+    ) -> Result<(Vector3d, &[Vector3d]), NamigatorError> {
+        let mut target = None;
+        for _ in 0..WANDER_ATTEMPTS {
+            let candidate = match self.find_random_point_around_circle_with_rng(home, radius, rng) {
+                Ok(candidate) => candidate,
+                Err(NamigatorError::UnableToFindRandomPointInCircle) => continue,
+                Err(e) => return Err(e),
+            };
+
+            if self.is_direct_route(home, candidate, distance(home, candidate))? {
+                target = Some(candidate);
+                break;
+            }
+        }
+
+        let target = target.ok_or(NamigatorError::NoReachablePointFound)?;
+        let path = self.find_path(home, target)?;
+
+        Ok((target, path))
+    }
+
+    /// Find the furthest point that can be landed on when moving from `from` in `direction`,
+    /// such as for charges, leaps and knockbacks.
+    ///
+    /// The point is found by casting a ray along the navmesh, so it is never on the other side of a wall
+    /// or off the edge of a cliff.
+    /// If the path to the point is more than `max_path_ratio` times longer than the straight line,
+    /// the furthest point along the ray with a short enough path is used instead,
+    /// which prevents landing on top of ledges that can only be walked to by going around.
+    pub fn find_landing_point(
+        &mut self,
+        from: Vector3d,
+        direction: LandingDirection,
+        max_distance: f32,
+        max_path_ratio: f32,
+    ) -> Result<Vector3d, NamigatorError> {
+        let (x, y, length) = match direction {
+            LandingDirection::Orientation(orientation) => {
+                let (sin, cos) = orientation.sin_cos();
+                (cos, sin, max_distance)
+            }
+            LandingDirection::Target(target) => {
+                let x = target.x - from.x;
+                let y = target.y - from.y;
+                let length = (x * x + y * y).sqrt();
+
+                if length <= f32::EPSILON {
+                    return Ok(from);
+                }
+
+                (x / length, y / length, length.min(max_distance))
+            }
+        };
+
+        let stop = Vector3d {
+            x: from.x + x * length,
+            y: from.y + y * length,
+            z: from.z,
+        };
+
+        let (hit_position, hit) = self.raycast(from, stop)?;
+
+        let length = {
+            let x = hit_position.x - from.x;
+            let y = hit_position.y - from.y;
+            let length = (x * x + y * y).sqrt();
+
+            if hit {
+                (length - LANDING_WALL_OFFSET).max(0.0)
+            } else {
+                length
+            }
+        };
+
+        // Points without a height on the navmesh can not be landed on.
+        let candidate = |map: &mut Self, length: f32| -> Result<Option<Vector3d>, NamigatorError> {
+            let position = Vector2d {
+                x: from.x + x * length,
+                y: from.y + y * length,
+            };
+
+            match map.find_height(from, position) {
+                Ok(z) => Ok(Some(Vector3d {
+                    x: position.x,
+                    y: position.y,
+                    z,
+                })),
+                Err(NamigatorError::UnknownHeight | NamigatorError::NoPolygonNearPoint) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+
+        if let Some(furthest) = candidate(self, length)? {
+            if self.is_landable(from, furthest, length, max_path_ratio)? {
+                return Ok(furthest);
+            }
+        }
+
+        // Points further along the ray are assumed to only get harder to reach,
+        // so the furthest point with a short enough path is found by halving the distance.
+        let mut landing = from;
+        let (mut low, mut high) = (0.0, length);
+        while high - low > LANDING_PRECISION {
+            let middle = (low + high) / 2.0;
+
+            match candidate(self, middle)? {
+                Some(position) if self.is_landable(from, position, middle, max_path_ratio)? => {
+                    landing = position;
+                    low = middle;
+                }
+                _ => high = middle,
+            }
+        }
+
+        Ok(landing)
+    }
+
+    /// Whether the path from `from` to `to` is at most `max_path_ratio` times longer than `length`.
+    fn is_landable(
+        &mut self,
+        from: Vector3d,
+        to: Vector3d,
+        length: f32,
+        max_path_ratio: f32,
+    ) -> Result<bool, NamigatorError> {
+        if length <= f32::EPSILON {
+            return Ok(true);
+        }
+
+        match self.find_path(from, to) {
+            Ok(path) => Ok(path_length(path) <= length * max_path_ratio),
+            Err(NamigatorError::UnknownPath) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// `candidate` with its height if it can be reached directly from `from`.
+    ///
+    /// Candidates without navmesh or a path are rejected, any other error is returned.
+    fn reachable_candidate(
+        &mut self,
+        from: Vector3d,
+        candidate: Vector2d,
+        length: f32,
+    ) -> Result<Option<Vector3d>, NamigatorError> {
+        let z = match self.find_height(from, candidate) {
+            Ok(z) => z,
+            Err(NamigatorError::UnknownHeight | NamigatorError::NoPolygonNearPoint) => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        };
+        let candidate = Vector3d {
+            x: candidate.x,
+            y: candidate.y,
+            z,
+        };
+
+        Ok(self
+            .is_direct_route(from, candidate, length)?
+            .then_some(candidate))
+    }
+
+    /// Whether `to` is visible from `from` and can be walked to without a large detour.
+    fn is_direct_route(
+        &mut self,
+        from: Vector3d,
+        to: Vector3d,
+        length: f32,
+    ) -> Result<bool, NamigatorError> {
+        let raised = |v: Vector3d| Vector3d {
+            x: v.x,
+            y: v.y,
+            z: v.z + LINE_OF_SIGHT_HEIGHT,
+        };
+
+        if !self.line_of_sight(raised(from), raised(to))? {
+            return Ok(false);
+        }
+
+        let path = match self.find_path(from, to) {
+            Ok(path) => path,
+            Err(NamigatorError::UnknownPath) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        Ok(path_length(path) <= length.max(1.0) * MAX_PATH_RATIO)
+    }
+}
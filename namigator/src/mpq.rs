@@ -0,0 +1,534 @@
+//! Reading files from the `MPQ` archives of a client.
+//!
+//! ```rust,no_run
+//! # use namigator::mpq::MpqArchiveSet;
+//! # fn t() -> Result<(), Box<dyn std::error::Error>> {
+//! let archives = MpqArchiveSet::open("/WoW3.3.5/Data")?;
+//!
+//! let maps = archives.read_dbc("DBFilesClient\\Map.dbc")?;
+//! for record in 0..maps.record_count() {
+//!     println!("{:?} {:?}", maps.u32(record, 0), maps.string(record, 1));
+//! }
+//!
+//! let wdts = archives.list_files("World\\Maps\\*\\*.wdt")?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::error::{error_code_to_error, NamigatorError};
+use crate::util::{files_in, path_to_cstr};
+use namigator_sys::{
+    mpq_close_archive, mpq_has_file, mpq_list_files, mpq_open_archive, mpq_read_file, MpqArchive,
+    BUFFER_TOO_SMALL, SUCCESS,
+};
+use std::collections::HashSet;
+use std::ffi::{c_char, c_uint, CString};
+use std::path::{Path, PathBuf};
+
+/// Subdirectories of `Data` with the archives of a single locale.
+const LOCALES: &[&str] = &[
+    "enGB", "enUS", "deDE", "esES", "frFR", "koKR", "zhCN", "zhTW", "enCN", "enTW", "esMX", "ruRU",
+];
+
+const DBC_MAGIC: &[u8; 4] = b"WDBC";
+const DBC_HEADER_SIZE: usize = 20;
+const DBC_FIELD_SIZE: usize = 4;
+
+const MAP_DBC: &str = "DBFilesClient\\Map.dbc";
+/// Chunk magics are reversed in the files.
+const MAIN_MAGIC: &[u8] = b"NIAM";
+const CHUNK_HEADER_SIZE: usize = 8;
+const ADTS_PER_WDT: usize = 64 * 64;
+
+/// Every `MPQ` archive of a client `Data` directory, searched in the same order as the client.
+///
+/// Patch archives take precedence over the base archives,
+/// patches in a locale directory such as `enUS` over the patches directly in `Data`,
+/// and patches with a higher number, such as `patch-3.MPQ`, over lower ones like `patch-2.MPQ` and `patch.MPQ`.
+/// Patches named with a letter, such as `patch-A.MPQ`, take precedence over numbered patches.
+#[derive(Debug)]
+pub struct MpqArchiveSet {
+    /// Highest priority first.
+    archives: Vec<(PathBuf, *mut MpqArchive)>,
+}
+
+// SAFETY: The archives are only accessed through `&self` and StormLib handles are not tied to a thread.
+unsafe impl Send for MpqArchiveSet {}
+
+impl MpqArchiveSet {
+    /// Open every `MPQ` file in `data_path` and its locale subdirectories.
+    pub fn open(data_path: impl AsRef<Path>) -> Result<Self, NamigatorError> {
+        fn inner(data_path: &Path) -> Result<MpqArchiveSet, NamigatorError> {
+            let mut paths = files_in(data_path)?;
+            for locale in LOCALES {
+                paths.extend(files_in(&data_path.join(locale))?);
+            }
+            paths.retain(|p| {
+                p.extension()
+                    .map_or(false, |e| e.eq_ignore_ascii_case("mpq"))
+            });
+
+            if paths.is_empty() {
+                return Err(NamigatorError::NoDataFilesFound);
+            }
+
+            MpqArchiveSet::from_paths(archive_order(data_path, paths))
+        }
+
+        inner(data_path.as_ref())
+    }
+
+    /// Open `paths`, the archive that takes precedence first.
+    fn from_paths(paths: Vec<PathBuf>) -> Result<Self, NamigatorError> {
+        let mut set = MpqArchiveSet {
+            archives: Vec::with_capacity(paths.len()),
+        };
+        for path in paths {
+            let c_path = path_to_cstr(&path)?;
+
+            let mut result: u8 = 0;
+            // SAFETY: CStrings are guaranteed to be valid pointers
+            let archive = unsafe { mpq_open_archive(c_path.as_ptr(), &mut result as *mut u8) };
+            if result != SUCCESS {
+                return Err(error_code_to_error(result));
+            }
+            if archive.is_null() {
+                return Err(NamigatorError::MapIsNullPointer);
+            }
+
+            set.archives.push((path, archive));
+        }
+
+        Ok(set)
+    }
+
+    /// Paths of the opened archives, the archive that takes precedence first.
+    pub fn archive_paths(&self) -> impl Iterator<Item = &Path> {
+        self.archives.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Whether any archive contains `name`.
+    ///
+    /// Names are case insensitive and both `/` and `\` can be used as separators.
+    pub fn has_file(&self, name: &str) -> Result<bool, NamigatorError> {
+        let name = mpq_name(name)?;
+        Ok(self.archive_with(&name)?.is_some())
+    }
+
+    /// Read `name` from the archive that takes precedence.
+    ///
+    /// Names are case insensitive and both `/` and `\` can be used as separators.
+    pub fn read_file(&self, name: &str) -> Result<Vec<u8>, NamigatorError> {
+        let c_name = mpq_name(name)?;
+        let archive = match self.archive_with(&c_name)? {
+            Some(archive) => archive,
+            None => return Err(NamigatorError::FileNotFoundInMpq(name.to_string())),
+        };
+
+        let mut buffer = Vec::new();
+        let mut size: c_uint = 0;
+
+        // SAFETY: `archive` is open until `self` is dropped, CStrings are guaranteed to be valid pointers
+        // and the buffer length is the length of `buffer`.
+        let mut result = unsafe {
+            mpq_read_file(
+                archive,
+                c_name.as_ptr(),
+                buffer.as_mut_ptr(),
+                buffer.len() as c_uint,
+                &mut size as *mut c_uint,
+            )
+        };
+
+        if result == BUFFER_TOO_SMALL {
+            buffer.resize(usize::try_from(size).unwrap(), 0);
+
+            // SAFETY: Same as above, with `buffer` resized to the size of the file.
+            result = unsafe {
+                mpq_read_file(
+                    archive,
+                    c_name.as_ptr(),
+                    buffer.as_mut_ptr(),
+                    buffer.len() as c_uint,
+                    &mut size as *mut c_uint,
+                )
+            };
+        }
+
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
+        }
+
+        Ok(buffer)
+    }
+
+    /// Read and parse the DBC table `name`, for example `DBFilesClient\Map.dbc`.
+    pub fn read_dbc(&self, name: &str) -> Result<Dbc, NamigatorError> {
+        Dbc::parse(&self.read_file(name)?)
+    }
+
+    /// Names of the files in any archive that match `pattern`, sorted and without duplicates.
+    ///
+    /// `pattern` can contain the wildcards `*` and `?`, and `/` is treated as `\`.
+    /// Only files in the listfile of an archive can be found.
+    pub fn list_files(&self, pattern: &str) -> Result<Vec<String>, NamigatorError> {
+        let pattern = mpq_name(pattern)?;
+
+        let mut seen = HashSet::new();
+        let mut names = Vec::new();
+        let mut buffer: Vec<c_char> = Vec::new();
+
+        for &(_, archive) in &self.archives {
+            let mut length: c_uint = 0;
+
+            // SAFETY: `archive` is open until `self` is dropped, CStrings are guaranteed to be valid pointers
+            // and the buffer length is the length of `buffer`.
+            let mut result = unsafe {
+                mpq_list_files(
+                    archive,
+                    pattern.as_ptr(),
+                    buffer.as_mut_ptr(),
+                    buffer.len() as c_uint,
+                    &mut length as *mut c_uint,
+                )
+            };
+
+            if result == BUFFER_TOO_SMALL {
+                buffer.resize(usize::try_from(length).unwrap(), 0);
+
+                // SAFETY: Same as above, with `buffer` resized to the length of the names.
+                result = unsafe {
+                    mpq_list_files(
+                        archive,
+                        pattern.as_ptr(),
+                        buffer.as_mut_ptr(),
+                        buffer.len() as c_uint,
+                        &mut length as *mut c_uint,
+                    )
+                };
+            }
+
+            if result != SUCCESS {
+                return Err(error_code_to_error(result));
+            }
+
+            let bytes = buffer[..usize::try_from(length).unwrap()]
+                .iter()
+                .map(|&c| c as u8)
+                .collect::<Vec<u8>>();
+            for name in bytes.split(|&b| b == 0).filter(|n| !n.is_empty()) {
+                let name = String::from_utf8_lossy(name).into_owned();
+                if seen.insert(name.to_ascii_lowercase()) {
+                    names.push(name);
+                }
+            }
+        }
+
+        names.sort_by_key(|n| n.to_ascii_lowercase());
+
+        Ok(names)
+    }
+
+    fn archive_with(&self, name: &CString) -> Result<Option<*mut MpqArchive>, NamigatorError> {
+        for &(_, archive) in &self.archives {
+            let mut has_file: u8 = 0;
+            // SAFETY: `archive` is open until `self` is dropped and CStrings are guaranteed to be valid pointers.
+            let result = unsafe { mpq_has_file(archive, name.as_ptr(), &mut has_file as *mut u8) };
+
+            if result != SUCCESS {
+                return Err(error_code_to_error(result));
+            }
+
+            if has_file != 0 {
+                return Ok(Some(archive));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Drop for MpqArchiveSet {
+    fn drop(&mut self) {
+        for &(_, archive) in &self.archives {
+            // SAFETY: Every archive was opened by `mpq_open_archive` and is only closed here.
+            unsafe { mpq_close_archive(archive) }
+        }
+    }
+}
+
+/// Map from the `Map.dbc` table of a client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientMap {
+    pub id: u32,
+    /// Name of the map directory, used as `map_name` for [`build_map`](crate::raw::build_map)
+    /// and [`PathfindMap::new`](crate::raw::PathfindMap::new).
+    pub directory: String,
+    /// English name, or the directory name if the table has no names.
+    pub name: String,
+    /// Whether the WDT of the map has any ADTs, `false` for maps that only consist of a single WMO
+    /// or that have no WDT at all.
+    pub has_adts: bool,
+}
+
+/// Every map in the `Map.dbc` table of the client in `data_path`, sorted by id.
+///
+/// Works for any client version, including the 0.5.3 alpha where every file is in its own archive,
+/// since the columns are found from the contents of the table instead of a table definition.
+pub fn list_client_maps(data_path: impl AsRef<Path>) -> Result<Vec<ClientMap>, NamigatorError> {
+    fn inner(data_path: &Path) -> Result<Vec<ClientMap>, NamigatorError> {
+        let archives = MpqArchiveSet::open(data_path)?;
+        let dbc = archives.read_dbc(MAP_DBC)?;
+        let name_field = map_name_field(&dbc);
+
+        let mut maps = Vec::new();
+        for record in 0..dbc.record_count() {
+            let (id, directory) = match (dbc.u32(record, 0), dbc.string(record, 1)) {
+                (Some(id), Some(directory)) if !directory.is_empty() => (id, directory),
+                _ => continue,
+            };
+
+            let name = name_field
+                .and_then(|field| dbc.string(record, field))
+                .filter(|name| !name.is_empty())
+                .unwrap_or(directory);
+
+            let has_adts = match read_wdt(&archives, data_path, directory)? {
+                Some(wdt) => wdt_has_adts(&wdt)?,
+                None => false,
+            };
+
+            maps.push(ClientMap {
+                id,
+                directory: directory.to_string(),
+                name: name.to_string(),
+                has_adts,
+            });
+        }
+
+        maps.sort_by_key(|m| m.id);
+
+        Ok(maps)
+    }
+
+    inner(data_path.as_ref())
+}
+
+/// Read the WDT of `directory`, or `None` if the client does not have it.
+fn read_wdt(
+    archives: &MpqArchiveSet,
+    data_path: &Path,
+    directory: &str,
+) -> Result<Option<Vec<u8>>, NamigatorError> {
+    let name = format!("World\\Maps\\{0}\\{0}.wdt", directory);
+    if archives.has_file(&name)? {
+        return archives.read_file(&name).map(Some);
+    }
+
+    // The alpha client has an archive for every file, named after the file.
+    let mut alpha = data_path.join("World").join("Maps").join(directory);
+    alpha.push(format!("{}.wdt.MPQ", directory));
+    if !alpha.is_file() {
+        return Ok(None);
+    }
+
+    let archive = MpqArchiveSet::from_paths(vec![alpha])?;
+    if archive.has_file(&name)? {
+        return archive.read_file(&name).map(Some);
+    }
+
+    let candidates = archive
+        .list_files("*")?
+        .into_iter()
+        .filter(|n| !n.starts_with('('))
+        .collect::<Vec<_>>();
+    match candidates.as_slice() {
+        [] => Err(NamigatorError::NoMpqCandidate),
+        [candidate] => archive.read_file(candidate).map(Some),
+        _ => Err(NamigatorError::MultipleCandidatesInAlphaMpq),
+    }
+}
+
+/// Whether the `MAIN` chunk of a WDT has any ADTs.
+///
+/// Entries are 8 bytes with flag 1 for existing ADTs, or 16 bytes with the offset of the ADT first in the alpha client.
+pub(crate) fn wdt_has_adts(wdt: &[u8]) -> Result<bool, NamigatorError> {
+    let mut rest = wdt;
+    while rest.len() >= CHUNK_HEADER_SIZE {
+        let magic = &rest[..4];
+        let size = u32::from_le_bytes(rest[4..8].try_into().unwrap()) as usize;
+        let data = rest
+            .get(CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE + size)
+            .ok_or(NamigatorError::MainNotFound)?;
+
+        if magic == MAIN_MAGIC {
+            let entry_size = if size == ADTS_PER_WDT * 8 {
+                8
+            } else if size == ADTS_PER_WDT * 16 {
+                16
+            } else {
+                return Err(NamigatorError::MainNotFound);
+            };
+
+            return Ok(data.chunks_exact(entry_size).any(|entry| {
+                let value = u32::from_le_bytes(entry[..4].try_into().unwrap());
+                if entry_size == 8 {
+                    value & 1 != 0
+                } else {
+                    value != 0
+                }
+            }));
+        }
+
+        rest = &rest[CHUNK_HEADER_SIZE + size..];
+    }
+
+    Err(NamigatorError::MainNotFound)
+}
+
+/// Field with the English map name, which is the first string column after the directory.
+///
+/// Columns are only strings if every value starts a string in the string block,
+/// and at least one value is past the empty string at the start of the block
+/// so that columns of only zeroes and ones are not mistaken for strings.
+pub(crate) fn map_name_field(dbc: &Dbc) -> Option<u32> {
+    (2..dbc.field_count()).find(|&field| {
+        let mut past_empty = false;
+
+        let all_strings = (0..dbc.record_count()).all(|record| {
+            let offset = dbc.u32(record, field).unwrap_or(u32::MAX) as usize;
+            past_empty |= offset > 1;
+
+            offset == 0 || dbc.strings.get(offset - 1) == Some(&0) && offset < dbc.strings.len()
+        });
+
+        all_strings && past_empty
+    })
+}
+
+fn mpq_name(name: &str) -> Result<CString, NamigatorError> {
+    Ok(CString::new(name.replace('/', "\\"))?)
+}
+
+/// Sort `paths` so that the archive that takes precedence comes first.
+///
+/// Must match the order that namigator's `MpqManager` reads files in when building maps,
+/// which `test_archive_order_matches_namigator` checks.
+pub(crate) fn archive_order(data_path: &Path, mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths.sort_by_cached_key(|path| {
+        let name = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_ascii_lowercase();
+        let locale = path.parent() != Some(data_path);
+
+        let patch = if name.starts_with("patch") {
+            let suffix = name.rsplit('-').next().unwrap_or_default();
+
+            // `patch.MPQ` and `patch-enUS.MPQ` come before `patch-1.MPQ` and `patch-2.MPQ`.
+            Some(match suffix.parse::<u32>() {
+                Ok(number) => (0, number.saturating_add(1)),
+                Err(_) if suffix.len() == 1 => (1, 0),
+                Err(_) => (0, 0),
+            })
+        } else {
+            None
+        };
+
+        std::cmp::Reverse((patch.is_some(), locale, patch, name))
+    });
+
+    paths
+}
+
+/// DBC table from the `DBFilesClient` directory of the archives.
+///
+/// Every field is 4 bytes, so fields are addressed by index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dbc {
+    record_count: u32,
+    field_count: u32,
+    record_size: usize,
+    records: Vec<u8>,
+    strings: Vec<u8>,
+}
+
+impl Dbc {
+    pub fn parse(data: &[u8]) -> Result<Self, NamigatorError> {
+        if data.len() < DBC_HEADER_SIZE || &data[..4] != DBC_MAGIC {
+            return Err(NamigatorError::UnrecognizedDbcFile);
+        }
+
+        let header = |i: usize| {
+            let offset = 4 + i * 4;
+            u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+        };
+        let record_count = header(0);
+        let field_count = header(1);
+        let record_size = header(2) as usize;
+        let string_block_size = header(3) as usize;
+
+        let records_end = (record_count as usize)
+            .checked_mul(record_size)
+            .and_then(|size| size.checked_add(DBC_HEADER_SIZE))
+            .ok_or(NamigatorError::UnrecognizedDbcFile)?;
+        let strings_end = records_end
+            .checked_add(string_block_size)
+            .ok_or(NamigatorError::UnrecognizedDbcFile)?;
+
+        if data.len() < strings_end || record_size < field_count as usize * DBC_FIELD_SIZE {
+            return Err(NamigatorError::UnrecognizedDbcFile);
+        }
+
+        Ok(Self {
+            record_count,
+            field_count,
+            record_size,
+            records: data[DBC_HEADER_SIZE..records_end].to_vec(),
+            strings: data[records_end..strings_end].to_vec(),
+        })
+    }
+
+    pub const fn record_count(&self) -> u32 {
+        self.record_count
+    }
+
+    pub const fn field_count(&self) -> u32 {
+        self.field_count
+    }
+
+    /// Raw bytes of `field` in `record`, or `None` if either is out of range.
+    fn field(&self, record: u32, field: u32) -> Option<[u8; 4]> {
+        if record >= self.record_count || field >= self.field_count {
+            return None;
+        }
+
+        let offset = record as usize * self.record_size + field as usize * DBC_FIELD_SIZE;
+        self.records[offset..offset + DBC_FIELD_SIZE]
+            .try_into()
+            .ok()
+    }
+
+    pub fn u32(&self, record: u32, field: u32) -> Option<u32> {
+        self.field(record, field).map(u32::from_le_bytes)
+    }
+
+    pub fn i32(&self, record: u32, field: u32) -> Option<i32> {
+        self.field(record, field).map(i32::from_le_bytes)
+    }
+
+    pub fn f32(&self, record: u32, field: u32) -> Option<f32> {
+        self.field(record, field).map(f32::from_le_bytes)
+    }
+
+    /// String that `field` of `record` points to in the string block.
+    ///
+    /// Returns `None` if the offset is outside of the string block or the string is not UTF-8.
+    pub fn string(&self, record: u32, field: u32) -> Option<&str> {
+        let offset = self.u32(record, field)? as usize;
+        let rest = self.strings.get(offset..)?;
+        let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+
+        std::str::from_utf8(&rest[..end]).ok()
+    }
+}
@@ -0,0 +1,259 @@
+use crate::error::{error_code_to_error, NamigatorError};
+use crate::pathfind::PathfindMap;
+use crate::polygon::{PolygonArea, PolygonFlags, PolygonRef};
+use crate::Vector3d;
+use namigator_sys::{
+    pathfind_navmesh_tile_polygons, pathfind_navmesh_tile_vertices, pathfind_navmesh_tiles,
+    NavmeshTileInfo, BUFFER_TOO_SMALL, SUCCESS,
+};
+use std::collections::{HashMap, HashSet};
+use std::ffi::c_uint;
+
+const INITIAL_TILES: usize = 64;
+
+/// Copy of the navmesh tiles that were loaded when [`PathfindMap::navmesh_view`] was called.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavmeshView {
+    tiles: Vec<NavmeshTile>,
+    /// Tile and polygon index of every polygon reference.
+    polygon_indices: HashMap<PolygonRef, (usize, usize)>,
+}
+
+impl NavmeshView {
+    pub(crate) fn from_tiles(tiles: Vec<NavmeshTile>) -> Self {
+        let mut polygon_indices = HashMap::new();
+
+        for (tile_index, tile) in tiles.iter().enumerate() {
+            for (i, polygon) in tile.polygons.iter().enumerate() {
+                polygon_indices.insert(polygon.reference, (tile_index, i));
+            }
+        }
+
+        Self {
+            tiles,
+            polygon_indices,
+        }
+    }
+
+    pub fn tiles(&self) -> &[NavmeshTile] {
+        &self.tiles
+    }
+
+    /// All polygons of all tiles along with the tile they are in.
+    pub fn polygons(&self) -> impl Iterator<Item = (&NavmeshTile, &NavmeshPolygon)> {
+        self.tiles
+            .iter()
+            .flat_map(|tile| tile.polygons.iter().map(move |polygon| (tile, polygon)))
+    }
+
+    pub fn polygon(&self, polygon: PolygonRef) -> Option<(&NavmeshTile, &NavmeshPolygon)> {
+        let &(tile, index) = self.polygon_indices.get(&polygon)?;
+        let tile = &self.tiles[tile];
+
+        Some((tile, &tile.polygons[index]))
+    }
+
+    /// All polygons that can be reached from `start` by crossing polygon edges, including `start` itself.
+    ///
+    /// Polygons in tiles that are not part of the view are never reached.
+    pub fn connected_polygons(&self, start: PolygonRef) -> HashSet<PolygonRef> {
+        let mut connected = HashSet::new();
+        if self.polygon(start).is_none() {
+            return connected;
+        }
+
+        let mut stack = vec![start];
+        connected.insert(start);
+
+        while let Some(polygon) = stack.pop() {
+            let (_, polygon) = match self.polygon(polygon) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            for &neighbour in polygon.neighbours.iter().flatten() {
+                if self.polygon_indices.contains_key(&neighbour) && connected.insert(neighbour) {
+                    stack.push(neighbour);
+                }
+            }
+        }
+
+        connected
+    }
+}
+
+/// Loaded navmesh tile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavmeshTile {
+    /// Detour tile coordinates.
+    pub x: i32,
+    pub y: i32,
+    pub layer: i32,
+    pub vertices: Vec<Vector3d>,
+    pub polygons: Vec<NavmeshPolygon>,
+}
+
+impl NavmeshTile {
+    /// Vertices of `polygon` in order, `polygon` must be from this tile.
+    pub fn polygon_vertices<'a>(
+        &'a self,
+        polygon: &'a NavmeshPolygon,
+    ) -> impl Iterator<Item = Vector3d> + 'a {
+        polygon
+            .indices
+            .iter()
+            .map(move |&i| self.vertices[usize::from(i)])
+    }
+}
+
+/// Polygon of a [`NavmeshTile`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavmeshPolygon {
+    pub reference: PolygonRef,
+    /// Indices into [`NavmeshTile::vertices`].
+    pub indices: Vec<u16>,
+    /// Polygon on the other side of every edge, edge `i` goes from vertex `i` to vertex `i + 1`.
+    pub neighbours: Vec<Option<PolygonRef>>,
+    pub area: PolygonArea,
+    pub flags: PolygonFlags,
+    pub off_mesh_connection: bool,
+}
+
+impl PathfindMap {
+    /// Copy the currently loaded navmesh tiles for inspection.
+    ///
+    /// The view does not change when ADTs are loaded or unloaded afterwards.
+    pub fn navmesh_view(&self) -> Result<NavmeshView, NamigatorError> {
+        let mut tiles = Vec::new();
+
+        for info in self.navmesh_tiles()? {
+            let vertices = self.navmesh_tile_vertices(&info)?;
+            let polygons = self.navmesh_tile_polygons(&info)?;
+
+            tiles.push(NavmeshTile {
+                x: info.x,
+                y: info.y,
+                layer: info.layer,
+                vertices,
+                polygons,
+            });
+        }
+
+        Ok(NavmeshView::from_tiles(tiles))
+    }
+
+    fn navmesh_tiles(&self) -> Result<Vec<NavmeshTileInfo>, NamigatorError> {
+        let mut tiles = vec![NavmeshTileInfo::default(); INITIAL_TILES];
+        let mut amount_of_tiles: c_uint = 0;
+
+        let mut result = unsafe {
+            pathfind_navmesh_tiles(
+                self.map,
+                tiles.as_mut_ptr(),
+                tiles.len() as c_uint,
+                &mut amount_of_tiles as *mut c_uint,
+            )
+        };
+
+        if result == BUFFER_TOO_SMALL {
+            tiles.resize(
+                usize::try_from(amount_of_tiles).unwrap(),
+                NavmeshTileInfo::default(),
+            );
+
+            result = unsafe {
+                pathfind_navmesh_tiles(
+                    self.map,
+                    tiles.as_mut_ptr(),
+                    tiles.len() as c_uint,
+                    &mut amount_of_tiles as *mut c_uint,
+                )
+            };
+        }
+
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
+        }
+
+        tiles.truncate(usize::try_from(amount_of_tiles).unwrap());
+
+        Ok(tiles)
+    }
+
+    fn navmesh_tile_vertices(
+        &self,
+        info: &NavmeshTileInfo,
+    ) -> Result<Vec<Vector3d>, NamigatorError> {
+        let mut buffer = vec![0.0_f32; usize::try_from(info.vertex_count).unwrap() * 3];
+        let mut amount_of_vertices: c_uint = 0;
+
+        let result = unsafe {
+            pathfind_navmesh_tile_vertices(
+                self.map,
+                info.index,
+                buffer.as_mut_ptr(),
+                info.vertex_count,
+                &mut amount_of_vertices as *mut c_uint,
+            )
+        };
+
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
+        }
+
+        Ok(buffer
+            .chunks_exact(3)
+            .take(usize::try_from(amount_of_vertices).unwrap())
+            .map(|v| Vector3d {
+                x: v[0],
+                y: v[1],
+                z: v[2],
+            })
+            .collect())
+    }
+
+    fn navmesh_tile_polygons(
+        &self,
+        info: &NavmeshTileInfo,
+    ) -> Result<Vec<NavmeshPolygon>, NamigatorError> {
+        let mut buffer = vec![
+            namigator_sys::NavmeshPolygon::default();
+            usize::try_from(info.polygon_count).unwrap()
+        ];
+        let mut amount_of_polygons: c_uint = 0;
+
+        let result = unsafe {
+            pathfind_navmesh_tile_polygons(
+                self.map,
+                info.index,
+                buffer.as_mut_ptr(),
+                info.polygon_count,
+                &mut amount_of_polygons as *mut c_uint,
+            )
+        };
+
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
+        }
+
+        Ok(buffer
+            .iter()
+            .take(usize::try_from(amount_of_polygons).unwrap())
+            .map(|p| {
+                let vertex_count = usize::from(p.vertex_count);
+
+                NavmeshPolygon {
+                    reference: p.polygon,
+                    indices: p.vertices[..vertex_count].to_vec(),
+                    neighbours: p.neighbours[..vertex_count]
+                        .iter()
+                        .map(|&n| if n == 0 { None } else { Some(n) })
+                        .collect(),
+                    area: PolygonArea(p.area),
+                    flags: PolygonFlags(p.flags),
+                    off_mesh_connection: p.off_mesh_connection == 1,
+                }
+            })
+            .collect())
+    }
+}
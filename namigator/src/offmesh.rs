@@ -0,0 +1,231 @@
+use crate::build::build_map_staged;
+use crate::error::{error_code_to_error, NamigatorError};
+use crate::pathfind::PathfindMap;
+use crate::Vector3d;
+use namigator_sys::{pathfind_set_off_mesh_connections, SUCCESS};
+use std::ffi::c_uint;
+use std::path::{Path, PathBuf};
+
+/// Name of the file in the navmesh directory of a map that [`PathfindMap::new`] loads connections from.
+pub(crate) const OFF_MESH_CONNECTIONS_FILE: &str = "OffMeshConnections.csv";
+
+/// Link between two points that can not be walked between on the navmesh,
+/// such as elevators and lifts.
+///
+/// Connections are added to the Detour navmesh, so [`PathfindMap::find_path`] uses them
+/// when that gives the shortest path, counting a connection as the straight line between its ends.
+/// The connection is stored in the navmesh tile that contains `from`,
+/// and Detour can only attach `to` to polygons in the same or a neighbouring tile.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OffMeshConnection {
+    /// Returned in [`PathSegment::connection`](crate::raw::PathSegment::connection) when the connection is used.
+    pub id: u32,
+    pub from: Vector3d,
+    pub to: Vector3d,
+    /// The connection can also be used from `to` to `from`.
+    pub bidirectional: bool,
+    /// Distance in yards from `from` and `to` that the navmesh polygons the connection is attached to can be.
+    pub radius: f32,
+}
+
+/// Build the map files and store `connections` next to them so that [`PathfindMap::new`] loads them.
+///
+/// The connections are added to the navmesh tiles as they are loaded.
+/// They are written together with the map files, and are part of the manifest
+/// so that [`build_status`](crate::raw::build_status) notices when the stored connections change.
+///
+/// See [`build_map`](crate::raw::build_map) for the other arguments
+/// and [`read_off_mesh_connections`] for loading connections from a file.
+pub fn build_map_with_off_mesh_connections(
+    data_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    map_name: &str,
+    gameobject_csv: impl AsRef<Path>,
+    threads: u32,
+    connections: &[OffMeshConnection],
+) -> Result<(), NamigatorError> {
+    build_map_staged(
+        data_path.as_ref(),
+        output_path.as_ref(),
+        map_name,
+        gameobject_csv.as_ref(),
+        threads,
+        Some(connections),
+    )
+}
+
+/// Read connections from a file with one connection per line.
+///
+/// Lines have the format `id,from_x,from_y,from_z,to_x,to_y,to_z,bidirectional,radius`
+/// where `bidirectional` is `0` or `1`.
+/// Empty lines and lines starting with `#` are ignored.
+pub fn read_off_mesh_connections(
+    path: impl AsRef<Path>,
+) -> Result<Vec<OffMeshConnection>, NamigatorError> {
+    parse_off_mesh_connections(&std::fs::read_to_string(path)?)
+}
+
+/// Write connections in the format read by [`read_off_mesh_connections`].
+pub fn write_off_mesh_connections(
+    path: impl AsRef<Path>,
+    connections: &[OffMeshConnection],
+) -> Result<(), NamigatorError> {
+    Ok(std::fs::write(
+        path,
+        format_off_mesh_connections(connections),
+    )?)
+}
+
+pub(crate) fn map_off_mesh_connections_path(output_path: &Path, map_name: &str) -> PathBuf {
+    output_path
+        .join("Nav")
+        .join(map_name)
+        .join(OFF_MESH_CONNECTIONS_FILE)
+}
+
+/// Connections stored for the map by [`build_map_with_off_mesh_connections`], if any.
+pub(crate) fn read_map_off_mesh_connections(
+    output_path: &Path,
+    map_name: &str,
+) -> Result<Vec<OffMeshConnection>, NamigatorError> {
+    let path = map_off_mesh_connections_path(output_path, map_name);
+
+    if path.exists() {
+        read_off_mesh_connections(path)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+pub(crate) fn parse_off_mesh_connections(
+    s: &str,
+) -> Result<Vec<OffMeshConnection>, NamigatorError> {
+    let mut connections = Vec::new();
+
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let connection = parse_off_mesh_connection(line)
+            .ok_or(NamigatorError::InvalidOffMeshConnectionFile(i + 1))?;
+        connections.push(connection);
+    }
+
+    Ok(connections)
+}
+
+fn parse_off_mesh_connection(line: &str) -> Option<OffMeshConnection> {
+    let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+    if fields.len() != 9 {
+        return None;
+    }
+
+    let float = |i: usize| fields[i].parse::<f32>().ok();
+
+    Some(OffMeshConnection {
+        id: fields[0].parse().ok()?,
+        from: Vector3d {
+            x: float(1)?,
+            y: float(2)?,
+            z: float(3)?,
+        },
+        to: Vector3d {
+            x: float(4)?,
+            y: float(5)?,
+            z: float(6)?,
+        },
+        bidirectional: match fields[7] {
+            "0" => false,
+            "1" => true,
+            _ => return None,
+        },
+        radius: float(8)?,
+    })
+}
+
+pub(crate) fn format_off_mesh_connections(connections: &[OffMeshConnection]) -> String {
+    let mut s = String::new();
+
+    for c in connections {
+        s.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            c.id,
+            c.from.x,
+            c.from.y,
+            c.from.z,
+            c.to.x,
+            c.to.y,
+            c.to.z,
+            u8::from(c.bidirectional),
+            c.radius,
+        ));
+    }
+
+    s
+}
+
+impl PathfindMap {
+    /// Add a connection to the navmesh, including tiles that are loaded later.
+    ///
+    /// Connections stored with [`build_map_with_off_mesh_connections`] are added automatically.
+    pub fn add_off_mesh_connection(
+        &mut self,
+        connection: OffMeshConnection,
+    ) -> Result<(), NamigatorError> {
+        self.off_mesh_connections.push(connection);
+        self.set_off_mesh_connections()
+    }
+
+    /// Remove all connections with `id` and return whether any were removed.
+    pub fn remove_off_mesh_connection(&mut self, id: u32) -> Result<bool, NamigatorError> {
+        let before = self.off_mesh_connections.len();
+        self.off_mesh_connections.retain(|c| c.id != id);
+
+        if self.off_mesh_connections.len() == before {
+            return Ok(false);
+        }
+
+        self.set_off_mesh_connections()?;
+        Ok(true)
+    }
+
+    pub fn off_mesh_connections(&self) -> &[OffMeshConnection] {
+        &self.off_mesh_connections
+    }
+
+    /// Add the connections to every loaded tile, only tiles where they changed are rebuilt.
+    pub(crate) fn set_off_mesh_connections(&mut self) -> Result<(), NamigatorError> {
+        let connections = self
+            .off_mesh_connections
+            .iter()
+            .map(|c| namigator_sys::OffMeshConnection {
+                id: c.id,
+                from_x: c.from.x,
+                from_y: c.from.y,
+                from_z: c.from.z,
+                to_x: c.to.x,
+                to_y: c.to.y,
+                to_z: c.to.z,
+                radius: c.radius,
+                bidirectional: u8::from(c.bidirectional),
+            })
+            .collect::<Vec<_>>();
+
+        // SAFETY: The pointer and length come from the same Vec.
+        let result = unsafe {
+            pathfind_set_off_mesh_connections(
+                self.map,
+                connections.as_ptr(),
+                connections.len() as c_uint,
+            )
+        };
+
+        if result == SUCCESS {
+            Ok(())
+        } else {
+            Err(error_code_to_error(result))
+        }
+    }
+}
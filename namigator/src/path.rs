@@ -0,0 +1,294 @@
+use crate::error::{error_code_to_error, NamigatorError};
+use crate::pathfind::PathfindMap;
+use crate::polygon::{PolygonArea, PolygonFlags, PolygonRef};
+use crate::util::distance;
+use crate::{Vector2d, Vector3d};
+use namigator_sys::{
+    pathfind_find_path_points, PathPoint, BUFFER_TOO_SMALL, STRAIGHT_PATH_OFFMESH_CONNECTION,
+    SUCCESS,
+};
+use std::ffi::c_uint;
+
+const INITIAL_PATH_POINTS: usize = 10;
+
+/// Shortest distance between points inserted by [`PathSmoothing::Spline`], [`PathOptions::max_segment_length`]
+/// and [`PathfindMap::resample_heights`], smaller values are raised to this.
+pub const MIN_PATH_STEP: f32 = 0.1;
+
+/// How the corners returned by Detour are smoothed.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum PathSmoothing {
+    /// Return the corners as found by Detour.
+    #[default]
+    None,
+    /// Only keep the corners where the path changes direction.
+    ///
+    /// Detour's `findStraightPath` already string-pulls the path through the polygon corridor,
+    /// so this returns the same corners as [`Self::None`].
+    StringPull,
+    /// Replace corners with a Catmull-Rom spline that is sampled every `step` yards, at least [`MIN_PATH_STEP`].
+    ///
+    /// The spline can cut slightly into corners, so this is mostly suited for open areas.
+    Spline { step: f32 },
+}
+
+/// Post processing of paths returned from [`PathfindMap::find_path_with_options`].
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct PathOptions {
+    pub smoothing: PathSmoothing,
+    /// Split segments so that no two consecutive points are further apart than this, at least [`MIN_PATH_STEP`].
+    pub max_segment_length: Option<f32>,
+    /// Look up the height of every point after smoothing and splitting.
+    ///
+    /// Without this only the corners have accurate heights.
+    pub follow_terrain_height: bool,
+}
+
+/// Straight part of a path that goes through navmesh polygons of a single area.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PathSegment {
+    pub from: Vector3d,
+    pub to: Vector3d,
+    /// The polygon that the segment starts in.
+    pub polygon: PolygonRef,
+    pub area: PolygonArea,
+    pub flags: PolygonFlags,
+    /// The segment is an off-mesh connection, such as a jump, instead of a walk along the navmesh.
+    pub off_mesh_connection: bool,
+    /// The id of the [`OffMeshConnection`](crate::raw::OffMeshConnection) that the segment uses.
+    pub connection: Option<u32>,
+}
+
+impl PathfindMap {
+    /// Same as [`Self::find_path`] but with the path processed according to `options`.
+    ///
+    /// The first and last points are always the same as the ones returned from [`Self::find_path`].
+    pub fn find_path_with_options(
+        &mut self,
+        start: Vector3d,
+        stop: Vector3d,
+        options: &PathOptions,
+    ) -> Result<&[Vector3d], NamigatorError> {
+        let mut path = self.find_path(start, stop)?.to_vec();
+
+        path = match options.smoothing {
+            PathSmoothing::None | PathSmoothing::StringPull => path,
+            PathSmoothing::Spline { step } => spline(&path, step),
+        };
+
+        if let Some(max_segment_length) = options.max_segment_length {
+            path = subdivide(&path, max_segment_length);
+        }
+
+        if options.follow_terrain_height {
+            self.follow_terrain_height(&mut path)?;
+        }
+
+        self.options_path = path;
+        Ok(&self.options_path)
+    }
+
+    /// Same as [`Self::find_path`] but split into segments with information about the navmesh polygons they go through.
+    ///
+    /// Segments are split where the area changes, so a path that goes through water has separate segments
+    /// for the water and the ground on either side.
+    pub fn find_path_segments(
+        &mut self,
+        start: Vector3d,
+        stop: Vector3d,
+    ) -> Result<&[PathSegment], NamigatorError> {
+        let mut amount_of_points: c_uint = 0;
+
+        let mut result = self.call_find_path_points(start, stop, &mut amount_of_points);
+        if result == BUFFER_TOO_SMALL {
+            self.path_points.resize(
+                usize::try_from(amount_of_points).unwrap(),
+                PathPoint::default(),
+            );
+
+            result = self.call_find_path_points(start, stop, &mut amount_of_points);
+        }
+
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
+        }
+
+        let points = &self.path_points[..usize::try_from(amount_of_points).unwrap()];
+        let vector = |p: &PathPoint| Vector3d {
+            x: p.x,
+            y: p.y,
+            z: p.z,
+        };
+
+        self.path_segments.clear();
+        self.path_segments.extend(points.windows(2).map(|w| {
+            let off_mesh_connection = w[0].path_flags & STRAIGHT_PATH_OFFMESH_CONNECTION != 0;
+
+            PathSegment {
+                from: vector(&w[0]),
+                to: vector(&w[1]),
+                polygon: w[0].polygon,
+                area: PolygonArea(w[0].area),
+                flags: PolygonFlags(w[0].flags),
+                off_mesh_connection,
+                connection: off_mesh_connection.then_some(w[0].connection),
+            }
+        }));
+
+        Ok(&self.path_segments)
+    }
+
+    fn call_find_path_points(
+        &mut self,
+        start: Vector3d,
+        stop: Vector3d,
+        amount_of_points: &mut c_uint,
+    ) -> u8 {
+        if self.path_points.is_empty() {
+            self.path_points
+                .resize(INITIAL_PATH_POINTS, PathPoint::default());
+        }
+
+        unsafe {
+            pathfind_find_path_points(
+                self.map,
+                start.x,
+                start.y,
+                start.z,
+                stop.x,
+                stop.y,
+                stop.z,
+                self.path_points.as_mut_ptr(),
+                self.path_points.len() as c_uint,
+                amount_of_points as *mut c_uint,
+            )
+        }
+    }
+
+    /// Insert points every `step` yards, at least [`MIN_PATH_STEP`], along `path` with heights looked up on the terrain.
+    ///
+    /// Only the corners of paths from [`Self::find_path`] have accurate heights,
+    /// so long straight segments can otherwise cut through hills or float above valleys.
+    pub fn resample_heights(
+        &mut self,
+        path: &[Vector3d],
+        step: f32,
+    ) -> Result<Vec<Vector3d>, NamigatorError> {
+        let mut path = subdivide(path, step);
+        self.follow_terrain_height(&mut path)?;
+
+        Ok(path)
+    }
+
+    fn follow_terrain_height(&mut self, path: &mut [Vector3d]) -> Result<(), NamigatorError> {
+        for i in 1..path.len().saturating_sub(1) {
+            path[i].z = self.terrain_height(path[i - 1], path[i])?;
+        }
+
+        Ok(())
+    }
+
+    /// Height of the ground at `point` when walking there from `previous`.
+    ///
+    /// Falls back to the height closest to `point` if the ground can't be walked to,
+    /// and to the height of `point` itself if there is no ground at all.
+    fn terrain_height(
+        &mut self,
+        previous: Vector3d,
+        point: Vector3d,
+    ) -> Result<f32, NamigatorError> {
+        let position = Vector2d {
+            x: point.x,
+            y: point.y,
+        };
+
+        match self.find_height(previous, position) {
+            Ok(z) => return Ok(z),
+            Err(NamigatorError::UnknownHeight) => {}
+            Err(e) => return Err(e),
+        }
+
+        let closest = match self.find_heights(point.x, point.y) {
+            Ok(heights) => heights
+                .iter()
+                .copied()
+                .min_by(|a, b| (a - point.z).abs().total_cmp(&(b - point.z).abs())),
+            Err(NamigatorError::UnknownHeight) => None,
+            Err(e) => return Err(e),
+        };
+
+        Ok(closest.unwrap_or(point.z))
+    }
+}
+
+pub(crate) fn spline(path: &[Vector3d], step: f32) -> Vec<Vector3d> {
+    if path.len() < 3 || step <= 0.0 {
+        return path.to_vec();
+    }
+
+    let step = step.max(MIN_PATH_STEP);
+    let last = path.len() - 1;
+    let mut out = Vec::with_capacity(path.len());
+
+    for i in 0..last {
+        let p0 = path[i.saturating_sub(1)];
+        let p1 = path[i];
+        let p2 = path[i + 1];
+        let p3 = path[(i + 2).min(last)];
+
+        let samples = (distance(p1, p2) / step).ceil().max(1.0) as usize;
+        for sample in 0..samples {
+            let t = sample as f32 / samples as f32;
+            out.push(catmull_rom(p0, p1, p2, p3, t));
+        }
+    }
+
+    out.push(path[last]);
+
+    out
+}
+
+pub(crate) fn subdivide(path: &[Vector3d], max_segment_length: f32) -> Vec<Vector3d> {
+    if path.len() < 2 || max_segment_length <= 0.0 {
+        return path.to_vec();
+    }
+
+    let max_segment_length = max_segment_length.max(MIN_PATH_STEP);
+    let mut out = Vec::with_capacity(path.len());
+
+    for w in path.windows(2) {
+        let (from, to) = (w[0], w[1]);
+        let segments = (distance(from, to) / max_segment_length).ceil().max(1.0) as usize;
+
+        for segment in 0..segments {
+            let t = segment as f32 / segments as f32;
+            out.push(Vector3d {
+                x: from.x + (to.x - from.x) * t,
+                y: from.y + (to.y - from.y) * t,
+                z: from.z + (to.z - from.z) * t,
+            });
+        }
+    }
+
+    out.push(path[path.len() - 1]);
+
+    out
+}
+
+fn catmull_rom(p0: Vector3d, p1: Vector3d, p2: Vector3d, p3: Vector3d, t: f32) -> Vector3d {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    let f = |p0: f32, p1: f32, p2: f32, p3: f32| {
+        0.5 * (2.0 * p1
+            + (p2 - p0) * t
+            + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+            + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+    };
+
+    Vector3d {
+        x: f(p0.x, p1.x, p2.x, p3.x),
+        y: f(p0.y, p1.y, p2.y, p3.y),
+        z: f(p0.z, p1.z, p2.z, p3.z),
+    }
+}
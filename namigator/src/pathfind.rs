@@ -1,12 +1,16 @@
 use crate::error::{error_code_to_error, NamigatorError};
-use crate::util::path_to_cstr;
+use crate::offmesh::{read_map_off_mesh_connections, OffMeshConnection};
+use crate::path::PathSegment;
+use crate::util::{path_to_cstr, random_callback};
 use namigator_sys::{
-    pathfind_find_height, pathfind_find_heights, pathfind_find_path,
+    pathfind_distance_to_wall, pathfind_find_height, pathfind_find_heights, pathfind_find_path,
     pathfind_find_point_in_between_vectors, pathfind_find_random_point_around_circle,
-    pathfind_free_map, pathfind_get_zone_and_area, pathfind_has_adts, pathfind_is_adt_loaded,
-    pathfind_line_of_sight, pathfind_load_adt, pathfind_load_adt_at, pathfind_load_all_adts,
-    pathfind_new_map, pathfind_unload_adt, Vertex, BUFFER_TOO_SMALL, SUCCESS,
+    pathfind_find_random_point_around_circle_with_random, pathfind_free_map,
+    pathfind_get_zone_and_area, pathfind_has_adts, pathfind_is_adt_loaded, pathfind_line_of_sight,
+    pathfind_load_adt, pathfind_load_adt_at, pathfind_load_all_adts, pathfind_new_map,
+    pathfind_raycast, pathfind_unload_adt, Vertex, BUFFER_TOO_SMALL, SUCCESS,
 };
+use rand_core::RngCore;
 use std::ffi::{c_float, c_uint, CString};
 use std::path::Path;
 
@@ -15,13 +19,18 @@ use wow_world_base::shared::vector3d_vanilla_tbc_wrath::Vector3d;
 
 #[derive(Debug)]
 pub struct PathfindMap {
-    map: *const namigator_sys::Map,
+    pub(crate) map: *const namigator_sys::Map,
+    pub(crate) map_name: String,
     // Vector3d does not have repr(c) so we can't be sure that it's correctly set up
     // The benefits of having interop with a wow_world_base type far outweighs the extra
     // ~124 bytes of storage for the vec.
     // This might be replaceable with a const array if we get hard limitations from namigator
     inner_path: Vec<Vertex>,
     path: Vec<Vector3d>,
+    pub(crate) options_path: Vec<Vector3d>,
+    pub(crate) path_points: Vec<namigator_sys::PathPoint>,
+    pub(crate) path_segments: Vec<PathSegment>,
+    pub(crate) off_mesh_connections: Vec<OffMeshConnection>,
     height: Vec<f32>,
 }
 
@@ -33,15 +42,17 @@ unsafe impl Send for PathfindMap {}
 impl PathfindMap {
     pub fn new(data_path: impl AsRef<Path>, map_name: &str) -> Result<Self, NamigatorError> {
         fn inner(data_path: &Path, map_name: &str) -> Result<PathfindMap, NamigatorError> {
+            let off_mesh_connections = read_map_off_mesh_connections(data_path, map_name)?;
+
             let data_path = path_to_cstr(data_path)?;
-            let map_name = CString::new(map_name)?;
+            let c_map_name = CString::new(map_name)?;
 
             let mut result: u8 = 0;
             // SAFETY: CStrings are guaranteed to be valid pointers
             let map = unsafe {
                 pathfind_new_map(
                     data_path.as_ptr(),
-                    map_name.as_ptr(),
+                    c_map_name.as_ptr(),
                     &mut result as *mut u8,
                 )
             };
@@ -56,8 +67,13 @@ impl PathfindMap {
 
             Ok(PathfindMap {
                 map,
+                map_name: map_name.to_string(),
                 inner_path: vec![Vertex::default(); INITIAL_VEC_SIZE],
                 path: vec![Vector3d::default(); INITIAL_VEC_SIZE],
+                options_path: Vec::new(),
+                path_points: Vec::new(),
+                path_segments: Vec::new(),
+                off_mesh_connections,
                 height: vec![f32::default(); INITIAL_VEC_SIZE],
             })
         }
@@ -84,6 +100,7 @@ impl PathfindMap {
         if result != SUCCESS {
             return Err(error_code_to_error(result));
         }
+        self.load_off_mesh_connections()?;
 
         Ok(adts_loaded)
     }
@@ -102,11 +119,12 @@ impl PathfindMap {
             )
         };
 
-        if result == SUCCESS {
-            Ok((out_adt_x, out_adt_y))
-        } else {
-            Err(error_code_to_error(result))
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
         }
+        self.load_off_mesh_connections()?;
+
+        Ok((out_adt_x, out_adt_y))
     }
 
     pub fn load_adt_at(&mut self, x: f32, y: f32) -> Result<(f32, f32), NamigatorError> {
@@ -123,11 +141,12 @@ impl PathfindMap {
             )
         };
 
-        if result == SUCCESS {
-            Ok((out_adt_x, out_adt_y))
-        } else {
-            Err(error_code_to_error(result))
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
         }
+        self.load_off_mesh_connections()?;
+
+        Ok((out_adt_x, out_adt_y))
     }
 
     pub fn unload_adt(&self, x: i32, y: i32) -> Result<(), NamigatorError> {
@@ -174,6 +193,10 @@ impl PathfindMap {
         Ok((out_zone, out_area))
     }
 
+    /// Find a path from `start` to `stop`.
+    ///
+    /// The path goes through [`OffMeshConnection`]s when that is shorter,
+    /// use [`Self::find_path_segments`] to see which connections are used.
     pub fn find_path(
         &mut self,
         start: Vector3d,
@@ -333,6 +356,87 @@ impl PathfindMap {
         }
     }
 
+    /// Cast a ray along the navmesh surface from `from` towards `to`.
+    ///
+    /// Returns the position where the ray stopped and whether it hit the edge of the navmesh before reaching `to`.
+    pub fn raycast(
+        &self,
+        from: Vector3d,
+        to: Vector3d,
+    ) -> Result<(Vector3d, bool), NamigatorError> {
+        let mut out_x: c_float = 0.0;
+        let mut out_y: c_float = 0.0;
+        let mut out_z: c_float = 0.0;
+        let mut out_hit: u8 = 0;
+
+        let result = unsafe {
+            pathfind_raycast(
+                self.map,
+                from.x,
+                from.y,
+                from.z,
+                to.x,
+                to.y,
+                to.z,
+                &mut out_x as *mut c_float,
+                &mut out_y as *mut c_float,
+                &mut out_z as *mut c_float,
+                &mut out_hit as *mut u8,
+            )
+        };
+
+        if result == SUCCESS {
+            Ok((
+                Vector3d {
+                    x: out_x,
+                    y: out_y,
+                    z: out_z,
+                },
+                out_hit == 1,
+            ))
+        } else {
+            Err(error_code_to_error(result))
+        }
+    }
+
+    /// Find the distance from `position` to the closest edge of the navmesh within `max_radius` yards.
+    ///
+    /// Returns the distance, the closest point on the edge and the normal of the edge,
+    /// which points towards `position`.
+    /// If there is no edge within `max_radius` the distance is `max_radius`.
+    pub fn distance_to_wall(
+        &self,
+        position: Vector3d,
+        max_radius: f32,
+    ) -> Result<(f32, Vector3d, Vector3d), NamigatorError> {
+        let mut distance: c_float = 0.0;
+        let mut hit = Vector3d::default();
+        let mut normal = Vector3d::default();
+
+        let result = unsafe {
+            pathfind_distance_to_wall(
+                self.map,
+                position.x,
+                position.y,
+                position.z,
+                max_radius,
+                &mut distance as *mut c_float,
+                &mut hit.x as *mut c_float,
+                &mut hit.y as *mut c_float,
+                &mut hit.z as *mut c_float,
+                &mut normal.x as *mut c_float,
+                &mut normal.y as *mut c_float,
+                &mut normal.z as *mut c_float,
+            )
+        };
+
+        if result == SUCCESS {
+            Ok((distance, hit, normal))
+        } else {
+            Err(error_code_to_error(result))
+        }
+    }
+
     pub fn find_height(&self, start: Vector3d, stop: Vector2d) -> Result<f32, NamigatorError> {
         let mut out_stop_z: c_float = 0.0;
 
@@ -388,6 +492,73 @@ impl PathfindMap {
         }
     }
 
+    /// Same as [`Self::find_random_point_around_circle`] but all randomness is drawn from `rng`.
+    ///
+    /// Seeding `rng` makes the result reproducible, for example when replaying a recorded session.
+    ///
+    /// Only polygons connected to the polygon at `start` are searched, so the point can always be walked to.
+    /// Detour picks a random polygon that touches the circle, so the point itself can be slightly further away than `radius`.
+    pub fn find_random_point_around_circle_with_rng(
+        &self,
+        start: Vector3d,
+        radius: f32,
+        rng: &mut impl RngCore,
+    ) -> Result<Vector3d, NamigatorError> {
+        let mut out_x: c_float = 0.0;
+        let mut out_y: c_float = 0.0;
+        let mut out_z: c_float = 0.0;
+
+        let (random, random_state) = random_callback(rng);
+
+        // SAFETY: random_state is valid for as long as rng is borrowed, which outlives the call.
+        let result = unsafe {
+            pathfind_find_random_point_around_circle_with_random(
+                self.map,
+                start.x,
+                start.y,
+                start.z,
+                radius,
+                random,
+                random_state,
+                &mut out_x as *mut c_float,
+                &mut out_y as *mut c_float,
+                &mut out_z as *mut c_float,
+            )
+        };
+
+        if result == SUCCESS {
+            Ok(Vector3d {
+                x: out_x,
+                y: out_y,
+                z: out_z,
+            })
+        } else {
+            Err(error_code_to_error(result))
+        }
+    }
+
+    /// Random point within `radius` of `start` that can be walked to from `start`, drawn from `rng`.
+    ///
+    /// Alias of [`Self::find_random_point_around_circle_with_rng`],
+    /// which already only searches polygons connected to `start`.
+    pub fn random_reachable_point(
+        &self,
+        start: Vector3d,
+        radius: f32,
+        rng: &mut impl RngCore,
+    ) -> Result<Vector3d, NamigatorError> {
+        self.find_random_point_around_circle_with_rng(start, radius, rng)
+    }
+
+    /// Add the off-mesh connections to newly loaded tiles.
+    fn load_off_mesh_connections(&mut self) -> Result<(), NamigatorError> {
+        if self.off_mesh_connections.is_empty() {
+            return Ok(());
+        }
+
+        self.set_off_mesh_connections()
+    }
+
     fn resize_paths(&mut self, size: u32) {
         let size = usize::try_from(size).unwrap();
         self.inner_path.resize(size, Vertex::default());
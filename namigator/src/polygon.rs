@@ -0,0 +1,44 @@
+use namigator_sys::{
+    AREA_ADT, AREA_DOODAD, AREA_LIQUID, AREA_WMO, POLY_FLAG_STEEP, POLY_FLAG_WALKABLE,
+};
+
+/// Detour reference to a navmesh polygon.
+///
+/// References are only valid for as long as the tile containing the polygon stays loaded.
+pub type PolygonRef = u64;
+
+/// What kind of geometry a navmesh polygon was built from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PolygonArea(pub u8);
+
+impl PolygonArea {
+    pub const fn is_adt(&self) -> bool {
+        self.0 & AREA_ADT != 0
+    }
+
+    pub const fn is_liquid(&self) -> bool {
+        self.0 & AREA_LIQUID != 0
+    }
+
+    pub const fn is_wmo(&self) -> bool {
+        self.0 & AREA_WMO != 0
+    }
+
+    pub const fn is_doodad(&self) -> bool {
+        self.0 & AREA_DOODAD != 0
+    }
+}
+
+/// Flags of a navmesh polygon.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PolygonFlags(pub u16);
+
+impl PolygonFlags {
+    pub const fn is_walkable(&self) -> bool {
+        self.0 & POLY_FLAG_WALKABLE != 0
+    }
+
+    pub const fn is_steep(&self) -> bool {
+        self.0 & POLY_FLAG_STEEP != 0
+    }
+}
@@ -0,0 +1,335 @@
+use crate::error::{error_code_to_error, NamigatorError};
+use crate::export::NavmeshArea;
+use crate::navmesh::{NavmeshPolygon, NavmeshTile, NavmeshView};
+use crate::pathfind::PathfindMap;
+use crate::util::ADT_SIZE;
+use namigator_sys::{pathfind_find_highest_heights, SUCCESS};
+use std::ffi::c_uint;
+use std::path::Path;
+
+/// Width and height of rendered ADT images, a pixel is a little over 2 yards.
+const TILE_IMAGE_SIZE: usize = 256;
+
+/// Positions that have heights but are not covered by a walkable polygon.
+const UNWALKABLE_COLOR: [u8; 3] = [200, 40, 40];
+/// Positions without any heights, such as outside of the map.
+const BACKGROUND_COLOR: [u8; 3] = [0, 0, 0];
+
+/// Lowest heights are drawn with this fraction of the full brightness.
+const MIN_SHADE: f32 = 0.4;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// Largest amount of bytes in a stored deflate block.
+const MAX_STORED_BLOCK: usize = 0xFFFF;
+
+impl PathfindMap {
+    /// Write a top down image of the ADT at `x`, `y` to a PNG file.
+    ///
+    /// Navmesh polygons of every area in `include` are drawn in the color of that area,
+    /// with later areas drawn on top of earlier ones.
+    /// Steep polygons, and positions that have heights but no navmesh, are drawn in red.
+    /// Everything is shaded by the highest height at that position, so that images of
+    /// two builds can be compared.
+    ///
+    /// The ADT is loaded if it is not already.
+    pub fn render_tile_png(
+        &mut self,
+        x: i32,
+        y: i32,
+        include: &[NavmeshArea],
+        path: impl AsRef<Path>,
+    ) -> Result<(), NamigatorError> {
+        std::fs::write(path, self.tile_png(x, y, include)?)?;
+
+        Ok(())
+    }
+
+    /// Same as [`Self::render_tile_png`] but returns the PNG file instead of writing it.
+    pub fn tile_png(
+        &mut self,
+        x: i32,
+        y: i32,
+        include: &[NavmeshArea],
+    ) -> Result<Vec<u8>, NamigatorError> {
+        let view = self.export_view(&[(x, y)])?;
+        let heights = self.highest_heights(&PixelGrid::new((x, y), TILE_IMAGE_SIZE))?;
+
+        let rgb = render_tile(&view, (x, y), include, TILE_IMAGE_SIZE, &heights);
+
+        let size = TILE_IMAGE_SIZE as u32;
+        Ok(encode_png(size, size, &rgb))
+    }
+
+    /// Highest height at the middle of every pixel of `grid`, row by row.
+    fn highest_heights(&self, grid: &PixelGrid) -> Result<Vec<Option<f32>>, NamigatorError> {
+        let (start_x, start_y) = grid.world(0, 0);
+        let mut heights = vec![0.0; grid.size * grid.size];
+
+        // SAFETY: The buffer has room for every row and column
+        let result = unsafe {
+            pathfind_find_highest_heights(
+                self.map,
+                start_x,
+                start_y,
+                -grid.pixel,
+                -grid.pixel,
+                grid.size as c_uint,
+                grid.size as c_uint,
+                heights.as_mut_ptr(),
+            )
+        };
+
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
+        }
+
+        Ok(heights
+            .into_iter()
+            .map(|h| if h.is_nan() { None } else { Some(h) })
+            .collect())
+    }
+}
+
+/// Render the ADT at `adt` into `size` by `size` RGB pixels, with north at the top.
+///
+/// `heights` are the heights used for shading every pixel, row by row, if it has any.
+pub(crate) fn render_tile(
+    view: &NavmeshView,
+    adt: (i32, i32),
+    include: &[NavmeshArea],
+    size: usize,
+    heights: &[Option<f32>],
+) -> Vec<u8> {
+    assert_eq!(heights.len(), size * size);
+
+    let grid = PixelGrid::new(adt, size);
+
+    let mut cover = vec![None; size * size];
+    for area in include {
+        let color = area.color();
+        let color = [color[0], color[1], color[2]].map(|c| (c * 255.0) as u8);
+
+        for (tile, polygon) in view.polygons() {
+            if !area.contains(polygon) {
+                continue;
+            }
+
+            let color = if polygon.flags.is_walkable() && !polygon.flags.is_steep() {
+                color
+            } else {
+                UNWALKABLE_COLOR
+            };
+
+            grid.fill(tile, polygon, |pixel| cover[pixel] = Some(color));
+        }
+    }
+
+    let (min, max) = heights
+        .iter()
+        .flatten()
+        .fold((f32::MAX, f32::MIN), |(min, max), &h| {
+            (min.min(h), max.max(h))
+        });
+
+    let mut rgb = Vec::with_capacity(size * size * 3);
+    for (cover, height) in cover.iter().zip(heights) {
+        let color = match (cover, height) {
+            (Some(color), _) => *color,
+            (None, Some(_)) => UNWALKABLE_COLOR,
+            (None, None) => BACKGROUND_COLOR,
+        };
+
+        let shade = match height {
+            Some(h) if max > min => MIN_SHADE + (1.0 - MIN_SHADE) * (h - min) / (max - min),
+            _ => 1.0,
+        };
+
+        rgb.extend(color.iter().map(|&c| (f32::from(c) * shade) as u8));
+    }
+
+    rgb
+}
+
+/// Conversion between pixels of a rendered ADT and world positions.
+struct PixelGrid {
+    size: usize,
+    /// Yards per pixel.
+    pixel: f32,
+    /// World x of the top edge, x points north.
+    top: f32,
+    /// World y of the left edge, y points west.
+    left: f32,
+}
+
+impl PixelGrid {
+    fn new(adt: (i32, i32), size: usize) -> Self {
+        Self {
+            size,
+            pixel: ADT_SIZE / size as f32,
+            top: (32 - adt.1) as f32 * ADT_SIZE,
+            left: (32 - adt.0) as f32 * ADT_SIZE,
+        }
+    }
+
+    /// World position of the middle of a pixel.
+    fn world(&self, row: usize, column: usize) -> (f32, f32) {
+        (
+            self.top - (row as f32 + 0.5) * self.pixel,
+            self.left - (column as f32 + 0.5) * self.pixel,
+        )
+    }
+
+    /// Call `f` with the index of every pixel whose middle is inside `polygon`.
+    fn fill(&self, tile: &NavmeshTile, polygon: &NavmeshPolygon, mut f: impl FnMut(usize)) {
+        let vertices = tile
+            .polygon_vertices(polygon)
+            .map(|v| (v.x, v.y))
+            .collect::<Vec<_>>();
+
+        let (min_x, max_x, min_y, max_y) = vertices.iter().fold(
+            (f32::MAX, f32::MIN, f32::MAX, f32::MIN),
+            |(min_x, max_x, min_y, max_y), &(x, y)| {
+                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+            },
+        );
+
+        let to_pixel = |distance: f32| distance / self.pixel - 0.5;
+        let clamp = |p: f32| p.max(0.0).min(self.size as f32 - 1.0);
+
+        let first_row = to_pixel(self.top - max_x).ceil();
+        let last_row = to_pixel(self.top - min_x).floor();
+        let first_column = to_pixel(self.left - max_y).ceil();
+        let last_column = to_pixel(self.left - min_y).floor();
+
+        if last_row < 0.0
+            || last_column < 0.0
+            || first_row >= self.size as f32
+            || first_column >= self.size as f32
+        {
+            return;
+        }
+
+        for row in clamp(first_row) as usize..=clamp(last_row) as usize {
+            for column in clamp(first_column) as usize..=clamp(last_column) as usize {
+                let (x, y) = self.world(row, column);
+
+                if contains(&vertices, x, y) {
+                    f(row * self.size + column);
+                }
+            }
+        }
+    }
+}
+
+/// Even-odd test for whether `x`, `y` is inside the polygon made of `vertices`.
+fn contains(vertices: &[(f32, f32)], x: f32, y: f32) -> bool {
+    let mut inside = false;
+
+    let mut previous = vertices[vertices.len() - 1];
+    for &current in vertices {
+        let ((x0, y0), (x1, y1)) = (previous, current);
+
+        if (y0 > y) != (y1 > y) && x < x0 + (y - y0) * (x1 - x0) / (y1 - y0) {
+            inside = !inside;
+        }
+
+        previous = current;
+    }
+
+    inside
+}
+
+/// Encode 8 bit RGB pixels, row by row, as a PNG image.
+///
+/// The image data is stored without compression.
+pub(crate) fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    let row_length = width as usize * 3;
+    assert_eq!(rgb.len(), row_length * height as usize);
+
+    let mut out = PNG_SIGNATURE.to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth, RGB color type, compression, filter and interlace method.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(&mut out, b"IHDR", &header);
+
+    // Every row starts with the filter type, which is always none.
+    let mut raw = Vec::with_capacity(rgb.len() + height as usize);
+    for row in 0..height as usize {
+        raw.push(0);
+        raw.extend_from_slice(&rgb[row * row_length..(row + 1) * row_length]);
+    }
+
+    write_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut out, b"IEND", &[]);
+
+    out
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// zlib stream of `data` in uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window and no preset dictionary.
+    let mut out = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).collect::<Vec<_>>();
+    if blocks.is_empty() {
+        blocks.push(&[]);
+    }
+
+    let last = blocks.len() - 1;
+    for (i, block) in blocks.into_iter().enumerate() {
+        let length = block.len() as u16;
+
+        // BFINAL bit of the last block, the stored block type is 0.
+        out.push(u8::from(i == last));
+        out.extend_from_slice(&length.to_le_bytes());
+        out.extend_from_slice(&(!length).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+
+    out
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    const MODULO: u32 = 65521;
+
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % MODULO;
+        b = (b + a) % MODULO;
+    }
+
+    (b << 16) | a
+}
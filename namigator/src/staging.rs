@@ -0,0 +1,195 @@
+use crate::error::{error_code_to_error, NamigatorError};
+use crate::offmesh::OFF_MESH_CONNECTIONS_FILE;
+use crate::util::{files_in, path_to_cstr};
+use namigator_sys::{lock_file_acquire, lock_file_release, rename_exchange, FileLock};
+use std::path::{Path, PathBuf};
+
+/// Directory inside the output directory where maps are built before being moved into place.
+const STAGING_DIRECTORY: &str = ".staging";
+const BVH_DIRECTORY: &str = "BVH";
+const NAV_DIRECTORY: &str = "Nav";
+/// Directory inside the staging directory of a map that the previous map is moved to
+/// on platforms that can not swap directories atomically.
+const OLD_NAV_DIRECTORY: &str = "Nav.old";
+
+/// Files in the navmesh directory of a map that are not written by the build,
+/// and are kept when the map is built again.
+const SIDECAR_FILES: &[&str] = &[OFF_MESH_CONNECTIONS_FILE];
+
+/// Lock file that stops other processes from building the same map into the same output.
+///
+/// The operating system holds the lock, so it is released when dropped or when the process exits,
+/// including when a build is killed.
+/// The file itself is left in place and is locked again by the next build.
+///
+/// A map left missing by a build that was killed while being moved into place is restored when locked.
+pub(crate) struct BuildLock {
+    lock: *mut FileLock,
+}
+
+impl BuildLock {
+    pub(crate) fn acquire(output_path: &Path, map_name: &str) -> Result<Self, NamigatorError> {
+        let directory = output_path.join(NAV_DIRECTORY);
+        std::fs::create_dir_all(&directory)?;
+
+        let path = directory.join(format!("{}.lock", map_name));
+        let c_path = path_to_cstr(&path)?;
+        let mut result: u8 = 0;
+
+        // SAFETY: CStrings are guaranteed to be valid pointers and `result` outlives the call.
+        let lock = unsafe { lock_file_acquire(c_path.as_ptr(), &mut result) };
+
+        let lock = match result {
+            namigator_sys::SUCCESS if !lock.is_null() => Self { lock },
+            namigator_sys::FILE_IS_LOCKED => return Err(NamigatorError::MapBuildLocked(path)),
+            namigator_sys::SUCCESS => return Err(NamigatorError::UnknownException),
+            e => return Err(error_code_to_error(e)),
+        };
+
+        let nav = directory.join(map_name);
+        let old_nav = staging_path(output_path, map_name).join(OLD_NAV_DIRECTORY);
+        if !nav.exists() && old_nav.is_dir() {
+            std::fs::rename(old_nav, nav)?;
+        }
+
+        Ok(lock)
+    }
+}
+
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        // SAFETY: `lock` was returned by `lock_file_acquire` and is only released here.
+        unsafe { lock_file_release(self.lock) }
+    }
+}
+
+/// Output directory that a single map is built into before it is moved into the real output.
+///
+/// Must only be used while holding the [`BuildLock`] of the map.
+/// The staging directory is removed when dropped, so failed builds leave nothing behind.
+pub(crate) struct Staging {
+    output_path: PathBuf,
+    map_name: String,
+    path: PathBuf,
+}
+
+impl Staging {
+    /// Create the staging directory with the gameobject models already in `output_path`,
+    /// replacing anything left behind by a killed build.
+    pub(crate) fn new(output_path: &Path, map_name: &str) -> Result<Self, NamigatorError> {
+        let path = staging_path(output_path, map_name);
+
+        let staging = Self {
+            output_path: output_path.to_path_buf(),
+            map_name: map_name.to_string(),
+            path,
+        };
+
+        remove_dir_if_exists(&staging.path)?;
+        std::fs::create_dir_all(staging.path.join(NAV_DIRECTORY))?;
+
+        let bvh = staging.path.join(BVH_DIRECTORY);
+        std::fs::create_dir_all(&bvh)?;
+        for file in files_in(&output_path.join(BVH_DIRECTORY))? {
+            let staged = bvh.join(file.file_name().unwrap_or_default());
+
+            // Models are never changed once written so they can be shared,
+            // but the index is rewritten by every build and must not change before the build is done.
+            if is_model(&file) && std::fs::hard_link(&file, &staged).is_ok() {
+                continue;
+            }
+            std::fs::copy(&file, &staged)?;
+        }
+
+        Ok(staging)
+    }
+
+    /// Output directory to build into.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Copy the [`SIDECAR_FILES`] of the already built map into the staged map, unless the build wrote them.
+    pub(crate) fn keep_sidecar_files(&self) -> Result<(), NamigatorError> {
+        let nav = self.output_path.join(NAV_DIRECTORY).join(&self.map_name);
+        let staged_nav = self.path.join(NAV_DIRECTORY).join(&self.map_name);
+
+        for file in SIDECAR_FILES {
+            let (previous, staged) = (nav.join(file), staged_nav.join(file));
+            if previous.is_file() && !staged.exists() {
+                std::fs::copy(previous, staged)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Move the built files into the output directory.
+    ///
+    /// New models are moved first and the index replaced after,
+    /// so that the map directory only appears once everything it needs is in place.
+    ///
+    /// An already built map is swapped with the new one in a single step where the platform supports it.
+    /// Elsewhere the old map is moved out of the way first, so loading the map fails until the new map is in place,
+    /// and a process killed in between has the old map restored by the next [`BuildLock::acquire`].
+    pub(crate) fn finish(self) -> Result<(), NamigatorError> {
+        let bvh = self.output_path.join(BVH_DIRECTORY);
+        std::fs::create_dir_all(&bvh)?;
+
+        let (models, others): (Vec<_>, Vec<_>) = files_in(&self.path.join(BVH_DIRECTORY))?
+            .into_iter()
+            .partition(|f| is_model(f));
+        for file in models {
+            let target = bvh.join(file.file_name().unwrap_or_default());
+            if !target.exists() {
+                std::fs::rename(&file, target)?;
+            }
+        }
+        for file in others {
+            std::fs::rename(&file, bvh.join(file.file_name().unwrap_or_default()))?;
+        }
+
+        let nav = self.output_path.join(NAV_DIRECTORY).join(&self.map_name);
+        let staged_nav = self.path.join(NAV_DIRECTORY).join(&self.map_name);
+
+        if nav.exists() {
+            let (c_nav, c_staged_nav) = (path_to_cstr(&nav)?, path_to_cstr(&staged_nav)?);
+
+            // SAFETY: CStrings are guaranteed to be valid pointers.
+            // The old map ends up in the staging directory, which is removed when dropped.
+            if unsafe { rename_exchange(c_staged_nav.as_ptr(), c_nav.as_ptr()) } {
+                return Ok(());
+            }
+
+            std::fs::rename(&nav, self.path.join(OLD_NAV_DIRECTORY))?;
+        }
+        std::fs::rename(&staged_nav, &nav)?;
+
+        Ok(())
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+        // Only succeeds when no other map is being built.
+        let _ = std::fs::remove_dir(self.output_path.join(STAGING_DIRECTORY));
+    }
+}
+
+fn staging_path(output_path: &Path, map_name: &str) -> PathBuf {
+    output_path.join(STAGING_DIRECTORY).join(map_name)
+}
+
+fn is_model(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |e| e.eq_ignore_ascii_case("bvh"))
+}
+
+fn remove_dir_if_exists(path: &Path) -> Result<(), NamigatorError> {
+    match std::fs::remove_dir_all(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
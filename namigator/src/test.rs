@@ -1,17 +1,36 @@
 use crate::build::{build_bvh, build_map};
 use crate::error::NamigatorError;
+use crate::export::{navmesh_mesh, write_obj, ExportLayer, NavmeshArea};
+use crate::geometry::Mesh;
+use crate::gltf::write_gltf;
+use crate::manifest::{manifest_status, write_manifest};
+use crate::mpq::{archive_order, map_name_field, wdt_has_adts, Dbc, MpqArchiveSet};
+use crate::navmesh::{NavmeshPolygon, NavmeshTile, NavmeshView};
+use crate::offmesh::{format_off_mesh_connections, parse_off_mesh_connections};
+use crate::path::{spline, subdivide};
 use crate::pathfind::PathfindMap;
-use crate::raw::{bvh_files_exist, map_files_exist};
+use crate::raw::{
+    build_status, bvh_files_exist, map_files_exist, verify_map_output, BuildStatus,
+    OffMeshConnection, StaleReason, TransportNavmesh, TransportPosition,
+};
+use crate::render::{adler32, crc32, encode_png, render_tile};
+use crate::staging::{BuildLock, Staging};
+use crate::util::adts_on_line;
 use crate::{Vector2d, Vector3d};
+use rand_core::SeedableRng;
 use std::path::Path;
 
 const MAP_NAME: &str = "development";
+const DATA_DIRECTORY: &str = match option_env!("NAMIGATOR_RS_TEST_DATA") {
+    Some(data_directory) => data_directory,
+    None => "/tmp/test",
+};
 
 #[test]
 #[ignore]
 fn test_both() {
     let output_directory = "/tmp/namirstest";
-    let data_directory = option_env!("NAMIGATOR_RS_TEST_DATA").unwrap_or("/tmp/test");
+    let data_directory = DATA_DIRECTORY;
 
     let output = Path::new(output_directory);
     if output.exists() {
@@ -34,6 +53,11 @@ fn test_both() {
 fn test_build(output_directory: &str, data_directory: &str) {
     let threads = std::thread::available_parallelism().unwrap().get() as u32;
 
+    let archives = MpqArchiveSet::open(data_directory).unwrap();
+    assert!(archives
+        .has_file("World/Maps/development/development.wdt")
+        .unwrap());
+
     assert!(!bvh_files_exist(output_directory).unwrap());
     assert!(!map_files_exist(output_directory, MAP_NAME).unwrap());
 
@@ -48,6 +72,31 @@ fn test_build(output_directory: &str, data_directory: &str) {
 
     assert!(bvh_files_exist(output_directory).unwrap());
     assert!(map_files_exist(output_directory, MAP_NAME).unwrap());
+    assert_eq!(
+        build_status(output_directory, MAP_NAME).unwrap(),
+        BuildStatus::UpToDate
+    );
+
+    let report = verify_map_output(output_directory, MAP_NAME).unwrap();
+    assert!(report.is_ok(), "{:?}", report.problems);
+    assert!(report.adts_checked > 0);
+    assert!(report.models_checked > 0);
+
+    let mut walkable = 0;
+    for model in std::fs::read_dir(Path::new(output_directory).join("BVH")).unwrap() {
+        let name = model.unwrap().file_name().into_string().unwrap();
+        if !name.ends_with(".bvh") {
+            continue;
+        }
+
+        match TransportNavmesh::from_model(output_directory, &name) {
+            Ok(_) => walkable += 1,
+            // Models without anything to walk on
+            Err(NamigatorError::RecastFailure) => {}
+            Err(e) => panic!("{}: {}", name, e),
+        }
+    }
+    assert!(walkable > 0);
 }
 
 const ADT_COORD_X: i32 = 0;
@@ -72,6 +121,11 @@ const PATH_FIND_TO: Vector3d = Vector3d {
 
 const PATH_FIND_MAX_STEPS: usize = 5;
 
+const MAX_SEGMENT_LENGTH: f32 = 4.0;
+
+const OFF_MESH_CONNECTION_ID: u32 = 1;
+const OFF_MESH_CONNECTION_RADIUS: f32 = 1.0;
+
 const ZONE_AND_AREA: u32 = 22;
 
 const LINE_OF_SIGHT_SHOULD_FAIL_FROM: Vector3d = Vector3d {
@@ -129,6 +183,13 @@ const POINT: Vector3d = Vector3d {
 
 const POINT_DISTANCE: f32 = 15.0;
 
+const RANDOM_SEED: u64 = 0x6E61_6D69;
+
+const FLEE_DISTANCE: f32 = 10.0;
+
+const LANDING_MAX_DISTANCE: f32 = 25.0;
+const LANDING_MAX_PATH_RATIO: f32 = 1.5;
+
 macro_rules! test_map {
     ($ty_name:ident, $zone_and_area:expr) => {
         assert!(!$ty_name.adt_loaded(ADT_COORD_X, ADT_COORD_Y).unwrap());
@@ -150,6 +211,86 @@ macro_rules! test_map {
 
         assert!(path.len() >= PATH_FIND_MAX_STEPS);
 
+        let options = crate::raw::PathOptions {
+            smoothing: crate::raw::PathSmoothing::Spline { step: 2.0 },
+            max_segment_length: Some(MAX_SEGMENT_LENGTH),
+            follow_terrain_height: true,
+        };
+        let path = $ty_name
+            .find_path_with_options(PATH_FIND_FROM, PATH_FIND_TO, &options)
+            .unwrap();
+        for w in path.windows(2) {
+            assert!(
+                distance(w[0].x, w[0].y, w[0].z, w[1].x, w[1].y, w[1].z)
+                    <= MAX_SEGMENT_LENGTH + 0.1
+            );
+        }
+
+        let segments = $ty_name
+            .find_path_segments(PATH_FIND_FROM, PATH_FIND_TO)
+            .unwrap();
+        assert!(segments.len() + 1 >= PATH_FIND_MAX_STEPS);
+        for w in segments.windows(2) {
+            assert_eq!(w[0].to, w[1].from);
+        }
+        assert!(segments
+            .iter()
+            .all(|s| s.polygon != 0 && s.flags.is_walkable()));
+
+        $ty_name
+            .add_off_mesh_connection(OffMeshConnection {
+                id: OFF_MESH_CONNECTION_ID,
+                from: PATH_FIND_FROM,
+                to: PATH_FIND_TO,
+                bidirectional: false,
+                radius: OFF_MESH_CONNECTION_RADIUS,
+            })
+            .unwrap();
+        let segments = $ty_name
+            .find_path_segments(PATH_FIND_FROM, PATH_FIND_TO)
+            .unwrap();
+        assert!(segments
+            .iter()
+            .any(|s| s.off_mesh_connection && s.connection == Some(OFF_MESH_CONNECTION_ID)));
+        assert!(
+            $ty_name
+                .find_path(PATH_FIND_FROM, PATH_FIND_TO)
+                .unwrap()
+                .len()
+                < PATH_FIND_MAX_STEPS
+        );
+        assert!($ty_name
+            .remove_off_mesh_connection(OFF_MESH_CONNECTION_ID)
+            .unwrap());
+        assert!($ty_name.off_mesh_connections().is_empty());
+        assert!(
+            $ty_name
+                .find_path(PATH_FIND_FROM, PATH_FIND_TO)
+                .unwrap()
+                .len()
+                >= PATH_FIND_MAX_STEPS
+        );
+
+        let path = $ty_name
+            .find_path(PATH_FIND_FROM, PATH_FIND_TO)
+            .unwrap()
+            .to_vec();
+        let resampled = $ty_name
+            .resample_heights(&path, MAX_SEGMENT_LENGTH)
+            .unwrap();
+        assert!(resampled.len() >= path.len());
+        assert_eq!(resampled[0], path[0]);
+        assert_eq!(resampled[resampled.len() - 1], path[path.len() - 1]);
+
+        let options = crate::raw::PathOptions {
+            smoothing: crate::raw::PathSmoothing::StringPull,
+            ..Default::default()
+        };
+        let string_pulled = $ty_name
+            .find_path_with_options(PATH_FIND_FROM, PATH_FIND_TO, &options)
+            .unwrap();
+        assert_eq!(string_pulled, path.as_slice());
+
         let (zone, area) = $ty_name
             .get_zone_and_area(ADT_X, ADT_Y, ADT_HEIGHTS[0])
             .unwrap();
@@ -187,6 +328,123 @@ macro_rules! test_map {
         let distance = distance(out.x, out.y, out.z, POINT.x, POINT.y, POINT.z);
 
         assert!(distance < POINT_DISTANCE);
+
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(RANDOM_SEED);
+        let first = $ty_name
+            .find_random_point_around_circle_with_rng(POINT, POINT_DISTANCE, &mut rng)
+            .unwrap();
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(RANDOM_SEED);
+        let second = $ty_name
+            .find_random_point_around_circle_with_rng(POINT, POINT_DISTANCE, &mut rng)
+            .unwrap();
+        assert_eq!(first, second);
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(RANDOM_SEED);
+        let reachable = $ty_name
+            .random_reachable_point(POINT, POINT_DISTANCE, &mut rng)
+            .unwrap();
+        assert_eq!(first, reachable);
+
+        let (target, path) = $ty_name
+            .flee_point(PATH_FIND_FROM, PATH_FIND_TO, FLEE_DISTANCE)
+            .unwrap();
+        assert!(!path.is_empty());
+
+        let before = (PATH_FIND_FROM.x - PATH_FIND_TO.x).powi(2)
+            + (PATH_FIND_FROM.y - PATH_FIND_TO.y).powi(2);
+        let after = (target.x - PATH_FIND_TO.x).powi(2) + (target.y - PATH_FIND_TO.y).powi(2);
+        assert!(after > before);
+
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(RANDOM_SEED);
+        let (target, path) = $ty_name
+            .wander_point(POINT, POINT_DISTANCE, &mut rng)
+            .unwrap();
+        assert!(!path.is_empty());
+
+        let x = target.x - POINT.x;
+        let y = target.y - POINT.y;
+        assert!((x * x + y * y).sqrt() <= POINT_DISTANCE);
+
+        let landing = $ty_name
+            .find_landing_point(
+                PATH_FIND_FROM,
+                crate::raw::LandingDirection::Target(PATH_FIND_TO),
+                LANDING_MAX_DISTANCE,
+                LANDING_MAX_PATH_RATIO,
+            )
+            .unwrap();
+
+        let x = landing.x - PATH_FIND_FROM.x;
+        let y = landing.y - PATH_FIND_FROM.y;
+        assert!((x * x + y * y).sqrt() <= LANDING_MAX_DISTANCE);
+
+        let (wall_distance, _, _) = $ty_name.distance_to_wall(POINT, POINT_DISTANCE).unwrap();
+        assert!((0.0..=POINT_DISTANCE).contains(&wall_distance));
+
+        let view = $ty_name.navmesh_view().unwrap();
+        assert!(!view.tiles().is_empty());
+        for (tile, polygon) in view.polygons() {
+            assert!(polygon.indices.len() >= 3);
+            assert_eq!(polygon.neighbours.len(), polygon.indices.len());
+            assert_eq!(
+                tile.polygon_vertices(polygon).count(),
+                polygon.indices.len()
+            );
+        }
+
+        let segments = $ty_name
+            .find_path_segments(PATH_FIND_FROM, PATH_FIND_TO)
+            .unwrap()
+            .to_vec();
+        let connected = view.connected_polygons(segments[0].polygon);
+        assert!(segments.iter().all(|s| connected.contains(&s.polygon)));
+
+        let obj = std::env::temp_dir().join("namigator_export.obj");
+        $ty_name
+            .export_obj(
+                DATA_DIRECTORY,
+                &[],
+                &[ExportLayer::Navmesh, ExportLayer::Terrain],
+                &obj,
+            )
+            .unwrap();
+        let obj = std::fs::read_to_string(&obj).unwrap();
+        let (navmesh, terrain) = obj.split_once("o terrain\n").unwrap();
+        assert!(navmesh.contains("o navmesh\n"));
+        assert!(navmesh.lines().any(|l| l.starts_with("f ")));
+        assert!(terrain.lines().any(|l| l.starts_with("f ")));
+
+        let path = $ty_name
+            .find_path(PATH_FIND_FROM, PATH_FIND_TO)
+            .unwrap()
+            .to_vec();
+        let glb = std::env::temp_dir().join("namigator_export.glb");
+        $ty_name
+            .export_gltf(
+                DATA_DIRECTORY,
+                &[],
+                &[ExportLayer::Navmesh, ExportLayer::Terrain],
+                Some(&path),
+                &glb,
+            )
+            .unwrap();
+        let gltf = glb_json(&std::fs::read(&glb).unwrap());
+        assert!(gltf.contains(r#""name":"navmesh""#));
+        assert!(gltf.contains(r#""name":"terrain""#));
+        assert!(gltf.contains(r#""name":"path""#));
+
+        let png = std::env::temp_dir().join("namigator_tile.png");
+        $ty_name
+            .render_tile_png(
+                ADT_COORD_X,
+                ADT_COORD_Y,
+                &[NavmeshArea::Terrain, NavmeshArea::Wmo, NavmeshArea::Liquid],
+                &png,
+            )
+            .unwrap();
+        let (width, height, rgb) = decode_png(&std::fs::read(&png).unwrap());
+        assert_eq!((width, height), (256, 256));
+        // The ADT has terrain, so not everything is background.
+        assert!(rgb.iter().any(|&c| c != 0));
     };
 }
 
@@ -194,6 +452,12 @@ fn test_pathfind(output_directory: &str) {
     let mut map = PathfindMap::new(output_directory, MAP_NAME).unwrap();
 
     test_map!(map, ZONE_AND_AREA);
+
+    #[cfg(feature = "debug-render")]
+    {
+        let svg = crate::debug::render_query_svg(&mut map, PATH_FIND_FROM, PATH_FIND_TO).unwrap();
+        assert!(svg.contains("<polyline"));
+    }
 }
 
 #[cfg(feature = "vanilla")]
@@ -203,6 +467,18 @@ fn test_vanilla(output_directory: &str) {
             .unwrap();
 
     test_map!(map, crate::vanilla::Area::try_from(ZONE_AND_AREA).unwrap());
+
+    let statuses = crate::vanilla::VanillaMap::build_all("", output_directory, 1, |map| {
+        map == crate::vanilla::Map::DevelopmentLand
+    })
+    .unwrap();
+    assert!(matches!(
+        statuses.as_slice(),
+        [(
+            crate::vanilla::Map::DevelopmentLand,
+            crate::raw::MapBuildStatus::AlreadyBuilt
+        )]
+    ));
 }
 
 #[cfg(feature = "tbc")]
@@ -221,6 +497,971 @@ fn test_wrath(output_directory: &str) {
     test_map!(map, crate::wrath::Area::try_from(ZONE_AND_AREA).unwrap());
 }
 
+/// Builds a map of the 0.5.3 alpha client in `NAMIGATOR_RS_ALPHA_TEST_DATA`,
+/// which has every file in its own `MPQ` archive.
+#[cfg(feature = "alpha")]
+#[test]
+#[ignore]
+fn test_alpha() {
+    use crate::alpha::{AlphaMap, Map};
+    use crate::mpq::list_client_maps;
+
+    let output_directory = "/tmp/namirstest_alpha";
+    let data_directory = option_env!("NAMIGATOR_RS_ALPHA_TEST_DATA").unwrap_or("/tmp/test_alpha");
+
+    if Path::new(output_directory).exists() {
+        std::fs::remove_dir_all(output_directory).unwrap();
+    }
+
+    let client_maps = list_client_maps(data_directory).unwrap();
+    for client_map in &client_maps {
+        if let Ok(map) = Map::try_from(client_map.id) {
+            assert_eq!(map.directory_name(), client_map.directory);
+        }
+    }
+    assert!(client_maps.iter().any(|m| m.id == 0 && m.has_adts));
+
+    let mut map = AlphaMap::build_gameobjects_and_map(
+        data_directory,
+        output_directory,
+        Map::StormwindJail,
+        1,
+    )
+    .unwrap();
+    map.load_all_adts().unwrap();
+
+    assert_eq!(
+        build_status(output_directory, Map::StormwindJail.directory_name()).unwrap(),
+        BuildStatus::UpToDate
+    );
+    assert!(
+        verify_map_output(output_directory, Map::StormwindJail.directory_name())
+            .unwrap()
+            .is_ok()
+    );
+}
+
+#[cfg(feature = "alpha")]
+#[test]
+fn test_alpha_map() {
+    use crate::alpha::{Area, Map};
+
+    let maps = (0..=u32::from(u16::MAX))
+        .filter_map(|id| Map::try_from(id).ok())
+        .collect::<Vec<_>>();
+    assert_eq!(maps, Map::variants());
+
+    for map in maps {
+        assert_eq!(Map::try_from(map.as_int()).unwrap(), map);
+    }
+    assert!(matches!(
+        Map::try_from(530),
+        Err(NamigatorError::MapIdNotFound)
+    ));
+
+    assert_eq!(Area::from(12), Area(12));
+}
+
+#[test]
+fn test_subdivide() {
+    let path = [
+        Vector3d::default(),
+        Vector3d {
+            x: 10.0,
+            y: 0.0,
+            z: 0.0,
+        },
+    ];
+
+    let subdivided = subdivide(&path, MAX_SEGMENT_LENGTH);
+    assert_eq!(subdivided.len(), 4);
+    assert_eq!(subdivided[0], path[0]);
+    assert_eq!(subdivided[3], path[1]);
+
+    for w in subdivided.windows(2) {
+        assert!(w[1].x - w[0].x <= MAX_SEGMENT_LENGTH);
+    }
+
+    let subdivided = subdivide(&path, 1e-30);
+    assert_eq!(subdivided.len(), 101);
+}
+
+#[test]
+fn test_spline() {
+    let path = [
+        Vector3d::default(),
+        Vector3d {
+            x: 10.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        Vector3d {
+            x: 10.0,
+            y: 10.0,
+            z: 0.0,
+        },
+    ];
+
+    let splined = spline(&path, 1.0);
+    assert_eq!(splined.len(), 21);
+    assert_eq!(splined[0], path[0]);
+    assert_eq!(splined[10], path[1]);
+    assert_eq!(splined[20], path[2]);
+
+    let splined = spline(&path, f32::MIN_POSITIVE);
+    assert_eq!(splined.len(), 201);
+}
+
+#[test]
+fn test_off_mesh_connections_file() {
+    let connections = [
+        OffMeshConnection {
+            id: 1,
+            from: PATH_FIND_FROM,
+            to: PATH_FIND_TO,
+            bidirectional: true,
+            radius: OFF_MESH_CONNECTION_RADIUS,
+        },
+        OffMeshConnection {
+            id: 2,
+            from: POINT,
+            to: FIND_HEIGHT_START,
+            bidirectional: false,
+            radius: 2.5,
+        },
+    ];
+
+    let s = format_off_mesh_connections(&connections);
+    assert_eq!(parse_off_mesh_connections(&s).unwrap(), connections);
+
+    let s = format!("# Elevator\n\n{}", s);
+    assert_eq!(parse_off_mesh_connections(&s).unwrap(), connections);
+
+    match parse_off_mesh_connections("1,0,0,0,1,1,1,2,1") {
+        Err(NamigatorError::InvalidOffMeshConnectionFile(1)) => {}
+        e => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn test_transport_navmesh() {
+    let corner = |x: f32, y: f32| Vector3d { x, y, z: 0.0 };
+    let deck = [
+        corner(0.0, 0.0),
+        corner(20.0, 0.0),
+        corner(20.0, 20.0),
+        corner(0.0, 20.0),
+    ];
+
+    let mut transport = TransportNavmesh::from_triangles(&deck, &[0, 1, 2, 0, 2, 3]).unwrap();
+
+    let path = transport
+        .find_path(corner(2.0, 2.0), corner(18.0, 18.0))
+        .unwrap();
+    assert!(path.len() >= 2);
+
+    let above = |x: f32, y: f32, z: f32| Vector3d { x, y, z };
+    assert!(transport
+        .line_of_sight(above(2.0, 2.0, 1.0), above(18.0, 18.0, 1.0))
+        .unwrap());
+    assert!(!transport
+        .line_of_sight(above(2.0, 2.0, 1.0), above(2.0, 2.0, -1.0))
+        .unwrap());
+
+    for indices in [&[0, 1, 2, 0, 2][..], &[0, 1, 4]] {
+        assert!(matches!(
+            TransportNavmesh::from_triangles(&deck, indices),
+            Err(NamigatorError::RecastFailure)
+        ));
+    }
+
+    let output = std::env::temp_dir().join("namigator_transport_model");
+    std::fs::create_dir_all(output.join("BVH")).unwrap();
+    std::fs::write(output.join("BVH").join("corrupt.bvh"), b"not a model").unwrap();
+    for model in ["corrupt.bvh", "missing.bvh"] {
+        assert!(matches!(
+            TransportNavmesh::from_model(&output, model),
+            Err(NamigatorError::CouldNotDeserializeDoodad)
+        ));
+    }
+    std::fs::remove_dir_all(output).unwrap();
+}
+
+#[test]
+fn test_transport_position() {
+    let transport = TransportPosition {
+        position: POINT,
+        orientation: std::f32::consts::FRAC_PI_2,
+    };
+
+    let local = Vector3d {
+        x: 1.0,
+        y: 0.0,
+        z: 2.0,
+    };
+    let world = transport.to_world(local);
+    assert!((world.x - POINT.x).abs() < 0.01);
+    assert!((world.y - (POINT.y + 1.0)).abs() < 0.01);
+    assert!((world.z - (POINT.z + 2.0)).abs() < 0.01);
+
+    let back = transport.to_local(world);
+    assert!(distance(back.x, back.y, back.z, local.x, local.y, local.z) < 0.01);
+}
+
+/// One tile with a terrain square next to a liquid triangle.
+fn test_view() -> NavmeshView {
+    let vertex = |x: f32, y: f32| Vector3d { x, y, z: 0.0 };
+    let polygon = |reference, indices: &[u16], area| NavmeshPolygon {
+        reference,
+        indices: indices.to_vec(),
+        neighbours: vec![None; indices.len()],
+        area: crate::raw::PolygonArea(area),
+        flags: crate::raw::PolygonFlags(namigator_sys::POLY_FLAG_WALKABLE),
+        off_mesh_connection: false,
+    };
+
+    NavmeshView::from_tiles(vec![NavmeshTile {
+        x: 0,
+        y: 0,
+        layer: 0,
+        vertices: vec![
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+            vertex(0.0, 1.0),
+            vertex(2.0, 0.0),
+        ],
+        polygons: vec![
+            polygon(1, &[0, 1, 2, 3], namigator_sys::AREA_ADT),
+            polygon(2, &[1, 4, 2], namigator_sys::AREA_LIQUID),
+        ],
+    }])
+}
+
+/// Single collision triangle.
+fn test_triangle() -> Mesh {
+    Mesh {
+        vertices: vec![
+            Vector3d::default(),
+            Vector3d {
+                x: 1.0,
+                y: 0.0,
+                z: 2.0,
+            },
+            Vector3d {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            },
+        ],
+        indices: vec![0, 1, 2],
+    }
+}
+
+#[test]
+fn test_write_obj() {
+    let count = |s: &str, prefix: &str| s.lines().filter(|l| l.starts_with(prefix)).count();
+
+    let navmesh = navmesh_mesh(&test_view(), &[]);
+    // The square is split into two triangles.
+    assert_eq!(navmesh.vertices.len(), 5);
+    assert_eq!(navmesh.indices, [0, 1, 2, 0, 2, 3, 1, 4, 2]);
+
+    let mut out = Vec::new();
+    write_obj(
+        &[
+            (ExportLayer::Navmesh, navmesh),
+            (ExportLayer::Wmo, Mesh::default()),
+            (ExportLayer::Terrain, test_triangle()),
+        ],
+        &mut out,
+    )
+    .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(count(&out, "o "), 3);
+    assert_eq!(count(&out, "v "), 8);
+    assert_eq!(count(&out, "f "), 4);
+    // Terrain indices continue after the navmesh vertices.
+    assert!(out.contains("o terrain\nv -0 0 -0\nv -0 2 -1\nv -1 0 -0\nf 6 7 8\n"));
+}
+
+#[test]
+fn test_write_gltf() {
+    let count = |s: &str, pattern: &str| s.matches(pattern).count();
+
+    let glb = write_gltf(
+        &[
+            (ExportLayer::Navmesh, navmesh_mesh(&test_view(), &[])),
+            (ExportLayer::Liquid, test_triangle()),
+            (ExportLayer::Wmo, Mesh::default()),
+        ],
+        None,
+    );
+    let gltf = glb_json(&glb);
+    assert!(gltf.starts_with(r#"{"asset":{"version":"2.0""#));
+    // No WMO triangles so no mesh for it.
+    assert_eq!(count(&gltf, r#""mode":4"#), 2);
+    assert_eq!(count(&gltf, r#""alphaMode":"BLEND""#), 1);
+    // The navmesh square is two triangles next to the liquid polygon.
+    assert!(gltf.contains(r#""count":5,"type":"VEC3""#));
+    assert!(gltf.contains(r#""count":9,"type":"SCALAR""#));
+    assert!(gltf.contains(r#""count":3,"type":"VEC3""#));
+    assert!(gltf.contains(r#""count":3,"type":"SCALAR""#));
+    assert!(gltf.contains(r#""buffers":[{"byteLength":144}]"#));
+    let bin = 20 + gltf_chunk_length(&glb, 12);
+    assert_eq!(gltf_chunk_length(&glb, bin), 144);
+    assert_eq!(&glb[bin + 4..bin + 8], b"BIN\0");
+    assert_eq!(glb.len(), bin + 8 + 144);
+
+    let path = [
+        Vector3d::default(),
+        Vector3d {
+            x: 1.0,
+            y: 1.0,
+            z: 0.0,
+        },
+    ];
+    let gltf = glb_json(&write_gltf(&[], Some(&path)));
+    assert_eq!(count(&gltf, r#""mode":3"#), 1);
+    assert!(!gltf.contains(r#""indices""#));
+
+    let glb = write_gltf(&[(ExportLayer::Doodads, Mesh::default())], None);
+    let gltf = glb_json(&glb);
+    assert!(gltf.contains(r#""scenes":[{"nodes":[]}]"#));
+    assert!(!gltf.contains(r#""buffers""#));
+    assert_eq!(glb.len(), 20 + gltf_chunk_length(&glb, 12));
+}
+
+fn gltf_chunk_length(glb: &[u8], offset: usize) -> usize {
+    u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap()) as usize
+}
+
+/// JSON chunk of a binary glTF file, after checking the header.
+fn glb_json(glb: &[u8]) -> String {
+    assert_eq!(&glb[0..4], b"glTF");
+    assert_eq!(gltf_chunk_length(glb, 4), 2);
+    assert_eq!(gltf_chunk_length(glb, 8), glb.len());
+
+    let length = gltf_chunk_length(glb, 12);
+    assert_eq!(length % 4, 0);
+    assert_eq!(&glb[16..20], b"JSON");
+
+    String::from_utf8(glb[20..20 + length].to_vec())
+        .unwrap()
+        .trim_end()
+        .to_string()
+}
+
+#[test]
+fn test_render_tile() {
+    // Pixels are a little over half a yard.
+    const SIZE: usize = 1066;
+    let pixel = |rgb: &[u8], row: usize, column: usize| {
+        let i = (row * SIZE + column) * 3;
+        [rgb[i], rgb[i + 1], rgb[i + 2]]
+    };
+
+    // The test view is in the south east corner of ADT 31, 31.
+    let rgb = render_tile(
+        &test_view(),
+        (31, 31),
+        &[NavmeshArea::Terrain],
+        SIZE,
+        &vec![None; SIZE * SIZE],
+    );
+    assert_eq!(rgb.len(), SIZE * SIZE * 3);
+    assert_eq!(pixel(&rgb, SIZE - 1, SIZE - 1), [76, 153, 51]);
+    assert_eq!(pixel(&rgb, SIZE - 3, SIZE - 1), [0, 0, 0]);
+    assert_eq!(pixel(&rgb, 0, 0), [0, 0, 0]);
+
+    // Liquid is not included so it is unwalkable ground.
+    let rgb = render_tile(
+        &test_view(),
+        (31, 31),
+        &[NavmeshArea::Terrain],
+        SIZE,
+        &vec![Some(0.0); SIZE * SIZE],
+    );
+    assert_eq!(pixel(&rgb, SIZE - 1, SIZE - 1), [76, 153, 51]);
+    assert_eq!(pixel(&rgb, SIZE - 3, SIZE - 1), [200, 40, 40]);
+
+    let rgb = render_tile(
+        &test_view(),
+        (31, 31),
+        &[NavmeshArea::Liquid],
+        SIZE,
+        // Higher in the north half.
+        &(0..SIZE * SIZE)
+            .map(|pixel| Some(if pixel / SIZE < SIZE / 2 { 10.0 } else { 0.0 }))
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(pixel(&rgb, SIZE - 3, SIZE - 1), [10, 30, 91]);
+    assert_eq!(pixel(&rgb, 0, 0), [200, 40, 40]);
+}
+
+#[test]
+fn test_encode_png() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+
+    let png = encode_png(1, 1, &[255, 0, 0]);
+    assert!(png.starts_with(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n']));
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..24], &[0, 0, 0, 1, 0, 0, 0, 1]);
+    assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+
+    // One stored block with the filter byte and the pixel.
+    let idat = &png[37..];
+    assert_eq!(&idat[..4], b"IDAT");
+    assert_eq!(&idat[4..13], &[0x78, 0x01, 1, 4, 0, 0xFB, 0xFF, 0, 255]);
+
+    let png = encode_png(200, 200, &vec![0; 200 * 200 * 3]);
+    let length = u32::from_be_bytes([png[33], png[34], png[35], png[36]]) as usize;
+    // 120200 bytes of image data in two stored blocks.
+    assert_eq!(length, 2 + 120_200 + 2 * 5 + 4);
+
+    let rgb = (0..300 * 100 * 3)
+        .map(|i| (i * 7 % 251) as u8)
+        .collect::<Vec<_>>();
+    assert_eq!(decode_png(&encode_png(300, 100, &rgb)), (300, 100, rgb));
+    assert_eq!(
+        decode_png(&encode_png(1, 1, &[1, 2, 3])),
+        (1, 1, vec![1, 2, 3])
+    );
+}
+
+/// Decode a PNG image written by `encode_png`, checking every checksum and length on the way.
+///
+/// Only stored deflate blocks and rows without filters are supported, which is what `encode_png` writes.
+fn decode_png(png: &[u8]) -> (u32, u32, Vec<u8>) {
+    let be = |bytes: &[u8]| u32::from_be_bytes(bytes.try_into().unwrap());
+
+    assert!(png.starts_with(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n']));
+
+    let mut header = None;
+    let mut zlib = Vec::new();
+    let mut rest = &png[8..];
+    loop {
+        let length = be(&rest[..4]) as usize;
+        let (kind, data) = (&rest[4..8], &rest[8..8 + length]);
+        assert_eq!(
+            be(&rest[8 + length..12 + length]),
+            crc32(&rest[4..8 + length])
+        );
+        rest = &rest[12 + length..];
+
+        match kind {
+            b"IHDR" => header = Some((be(&data[..4]), be(&data[4..8]), data[8..].to_vec())),
+            b"IDAT" => zlib.extend_from_slice(data),
+            b"IEND" => break,
+            kind => panic!("unexpected chunk {:?}", kind),
+        }
+    }
+    assert!(rest.is_empty());
+
+    let (width, height, format) = header.unwrap();
+    assert_eq!(format, [8, 2, 0, 0, 0]);
+
+    assert_eq!(zlib[0] & 0x0F, 8);
+    assert_eq!((u16::from(zlib[0]) << 8 | u16::from(zlib[1])) % 31, 0);
+
+    let mut raw = Vec::new();
+    let mut block = &zlib[2..];
+    loop {
+        let last = block[0] & 1 == 1;
+        assert_eq!(block[0] >> 1, 0, "only stored blocks");
+
+        let length = u16::from_le_bytes([block[1], block[2]]);
+        assert_eq!(!length, u16::from_le_bytes([block[3], block[4]]));
+        raw.extend_from_slice(&block[5..5 + length as usize]);
+        block = &block[5 + length as usize..];
+
+        if last {
+            break;
+        }
+    }
+    assert_eq!(block, adler32(&raw).to_be_bytes());
+
+    let row_length = width as usize * 3;
+    assert_eq!(raw.len(), (row_length + 1) * height as usize);
+
+    let mut rgb = Vec::new();
+    for row in raw.chunks(row_length + 1) {
+        assert_eq!(row[0], 0, "only rows without filters");
+        rgb.extend_from_slice(&row[1..]);
+    }
+
+    (width, height, rgb)
+}
+
+#[cfg(feature = "debug-render")]
+#[test]
+fn test_write_svg() {
+    use crate::debug::{write_svg, Probe};
+
+    let vertex = |x: f32, y: f32| Vector3d { x, y, z: 0.0 };
+    let probes = [
+        Probe {
+            from: vertex(0.5, 0.5),
+            to: vertex(1.5, 0.25),
+            line_of_sight: true,
+        },
+        Probe {
+            from: vertex(1.5, 0.25),
+            to: vertex(0.5, 0.5),
+            line_of_sight: false,
+        },
+    ];
+    let path = [vertex(0.5, 0.5), vertex(1.0, 0.5), vertex(1.5, 0.25)];
+
+    let svg = write_svg(
+        &test_view(),
+        vertex(0.5, 0.5),
+        vertex(1.5, 0.25),
+        Ok(&path),
+        &probes,
+    );
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<polygon").count(), 2);
+    assert_eq!(svg.matches("<line").count(), 2);
+    assert!(svg.contains(r##"fill="#4c9933""##));
+    assert!(svg.contains(r##"fill="#194ce5""##));
+    assert!(svg.contains(r#"<polyline points="-0.5,-0.5 -0.5,-1 -0.25,-1.5""#));
+
+    let svg = write_svg(
+        &test_view(),
+        vertex(0.5, 0.5),
+        vertex(1.5, 0.25),
+        Err(&NamigatorError::UnknownPath),
+        &[],
+    );
+    assert!(!svg.contains("<polyline"));
+    assert!(svg.contains("<text"));
+
+    let mut tiles = test_view().tiles().to_vec();
+    tiles[0].polygons[0].flags = crate::raw::PolygonFlags(
+        namigator_sys::POLY_FLAG_WALKABLE | namigator_sys::POLY_FLAG_STEEP,
+    );
+    let svg = write_svg(
+        &NavmeshView::from_tiles(tiles),
+        vertex(0.5, 0.5),
+        vertex(1.5, 0.25),
+        Ok(&path),
+        &[],
+    );
+    assert!(svg.contains(r##"fill="#c82828""##));
+    assert!(svg.contains(r##"fill="#194ce5""##));
+}
+
+#[test]
+fn test_adts_on_line() {
+    let position = |x: f32, y: f32| Vector3d { x, y, z: 0.0 };
+
+    let inside = position(-10.0, -10.0);
+    assert_eq!(adts_on_line(inside, inside), [(32, 32)]);
+
+    // Crosses the edge between west and east first, then the one between north and south.
+    assert_eq!(
+        adts_on_line(position(10.0, 10.0), position(-10.0, -30.0)),
+        [(31, 31), (32, 31), (32, 32)]
+    );
+    assert_eq!(
+        adts_on_line(position(-10.0, -30.0), position(10.0, 10.0)),
+        [(32, 32), (32, 31), (31, 31)]
+    );
+
+    assert_eq!(
+        adts_on_line(position(-10.0, -10.0), position(-10.0, -1000.0)),
+        [(32, 32), (33, 32)]
+    );
+    // The ADT in between is included even though neither end is in it.
+    assert_eq!(
+        adts_on_line(position(-10.0, -10.0), position(-10.0, -1100.0)),
+        [(32, 32), (33, 32), (34, 32)]
+    );
+}
+
+#[test]
+fn test_build_manifest() {
+    let directory = std::env::temp_dir().join("namigator_manifest");
+    let _ = std::fs::remove_dir_all(&directory);
+    let data = directory.join("Data");
+    let output = directory.join("output");
+    let csv = directory.join("gameobjects.csv");
+    std::fs::create_dir_all(data.join("enUS")).unwrap();
+    std::fs::create_dir_all(output.join("Nav").join(MAP_NAME)).unwrap();
+    std::fs::write(data.join("dbc.MPQ"), b"dbc").unwrap();
+    std::fs::write(data.join("enUS").join("patch-enUS.mpq"), b"patch").unwrap();
+    std::fs::write(&csv, b"1,model.wmo").unwrap();
+
+    let status = || manifest_status(&output, MAP_NAME).unwrap();
+
+    assert_eq!(status(), BuildStatus::Stale(StaleReason::NoManifest));
+    assert!(!status().needs_build());
+
+    write_manifest(&data, &output, MAP_NAME, &csv).unwrap();
+    assert_eq!(status(), BuildStatus::UpToDate);
+    assert!(!status().needs_build());
+
+    let manifest = output.join("Nav").join(MAP_NAME).join("BuildManifest.txt");
+    let contents = std::fs::read_to_string(&manifest).unwrap();
+    let version = format!("format_version\t{}\n", namigator_sys::FILE_FORMAT_VERSION);
+    std::fs::write(&manifest, contents.replace(&version, "format_version\t0\n")).unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::FormatVersionChanged(Some(0)))
+    );
+    assert!(status().needs_build());
+    std::fs::write(&manifest, contents.replace(&version, "")).unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::FormatVersionChanged(None))
+    );
+
+    let version = format!("namigator_sys_version\t{}\n", namigator_sys::VERSION);
+    std::fs::write(
+        &manifest,
+        contents.replace(&version, "namigator_sys_version\t0.0.0\n"),
+    )
+    .unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::NamigatorVersionChanged(Some(
+            "0.0.0".to_string()
+        )))
+    );
+    assert!(status().needs_build());
+    std::fs::write(&manifest, contents.replace(&version, "")).unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::NamigatorVersionChanged(None))
+    );
+    write_manifest(&data, &output, MAP_NAME, &csv).unwrap();
+
+    std::fs::write(&csv, b"1,other_model.wmo").unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::GameobjectCsvChanged)
+    );
+    write_manifest(&data, &output, MAP_NAME, &csv).unwrap();
+
+    let connections = output
+        .join("Nav")
+        .join(MAP_NAME)
+        .join("OffMeshConnections.csv");
+    std::fs::write(&connections, b"1,0,0,0,1,1,1,0,2\n").unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::OffMeshConnectionsChanged)
+    );
+    write_manifest(&data, &output, MAP_NAME, &csv).unwrap();
+    assert_eq!(status(), BuildStatus::UpToDate);
+
+    std::fs::remove_file(&connections).unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::OffMeshConnectionsChanged)
+    );
+    write_manifest(&data, &output, MAP_NAME, &csv).unwrap();
+
+    std::fs::write(data.join("enUS").join("patch-enUS.mpq"), b"patch 2").unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::MpqChanged("enUS/patch-enUS.mpq".to_string()))
+    );
+    write_manifest(&data, &output, MAP_NAME, &csv).unwrap();
+
+    std::fs::write(data.join("patch.MPQ"), b"patch").unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::MpqAdded("patch.MPQ".to_string()))
+    );
+    write_manifest(&data, &output, MAP_NAME, &csv).unwrap();
+
+    std::fs::remove_file(data.join("dbc.MPQ")).unwrap();
+    assert_eq!(
+        status(),
+        BuildStatus::Stale(StaleReason::MpqRemoved("dbc.MPQ".to_string()))
+    );
+
+    // Built maps can still be checked without the client.
+    std::fs::remove_dir_all(&data).unwrap();
+    assert_eq!(status(), BuildStatus::UpToDate);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_staging() {
+    let output = std::env::temp_dir().join("namigator_staging");
+    let _ = std::fs::remove_dir_all(&output);
+    let nav = output.join("Nav").join(MAP_NAME);
+    let bvh = output.join("BVH");
+    std::fs::create_dir_all(&nav).unwrap();
+    std::fs::create_dir_all(&bvh).unwrap();
+    std::fs::write(nav.join("old.nav"), b"old").unwrap();
+    std::fs::write(nav.join("OffMeshConnections.csv"), b"old").unwrap();
+    std::fs::write(bvh.join("old.bvh"), b"old").unwrap();
+    std::fs::write(bvh.join("bvh.idx"), b"old").unwrap();
+
+    // A lock file left behind by a killed build is not locked.
+    std::fs::write(output.join("Nav").join(format!("{}.lock", MAP_NAME)), b"").unwrap();
+    let lock = BuildLock::acquire(&output, MAP_NAME).unwrap();
+    match BuildLock::acquire(&output, MAP_NAME) {
+        Err(NamigatorError::MapBuildLocked(_)) => {}
+        e => panic!("{:?}", e.map(|_| ())),
+    }
+
+    // A failed build leaves the output untouched.
+    let staging = Staging::new(&output, MAP_NAME).unwrap();
+    std::fs::write(staging.path().join("BVH").join("bvh.idx"), b"new").unwrap();
+    drop(staging);
+    assert_eq!(std::fs::read(bvh.join("bvh.idx")).unwrap(), b"old");
+    assert!(nav.join("old.nav").exists());
+
+    let staging = Staging::new(&output, MAP_NAME).unwrap();
+    let staged_nav = staging.path().join("Nav").join(MAP_NAME);
+    std::fs::create_dir_all(&staged_nav).unwrap();
+    std::fs::write(staged_nav.join("new.nav"), b"new").unwrap();
+    std::fs::write(staging.path().join("BVH").join("new.bvh"), b"new").unwrap();
+    std::fs::write(staging.path().join("BVH").join("bvh.idx"), b"new").unwrap();
+    staging.keep_sidecar_files().unwrap();
+    staging.finish().unwrap();
+
+    assert!(!nav.join("old.nav").exists());
+    assert!(nav.join("new.nav").exists());
+    assert_eq!(
+        std::fs::read(nav.join("OffMeshConnections.csv")).unwrap(),
+        b"old"
+    );
+    assert!(bvh.join("old.bvh").exists());
+    assert!(bvh.join("new.bvh").exists());
+    assert_eq!(std::fs::read(bvh.join("bvh.idx")).unwrap(), b"new");
+    assert!(!output.join(".staging").exists());
+
+    // A build killed between moving the old map away and moving the new map into place.
+    let old_nav = output.join(".staging").join(MAP_NAME).join("Nav.old");
+    std::fs::create_dir_all(old_nav.parent().unwrap()).unwrap();
+    std::fs::rename(&nav, &old_nav).unwrap();
+
+    drop(lock);
+    drop(BuildLock::acquire(&output, MAP_NAME).unwrap());
+    assert!(nav.join("new.nav").exists());
+    assert!(output
+        .join("Nav")
+        .join(format!("{}.lock", MAP_NAME))
+        .exists());
+
+    std::fs::remove_dir_all(&output).unwrap();
+}
+
+#[test]
+fn test_archive_order() {
+    let data = Path::new("/WoW/Data");
+    let paths = [
+        "common.MPQ",
+        "patch.MPQ",
+        "enUS/patch-enUS-2.MPQ",
+        "expansion.MPQ",
+        "patch-3.MPQ",
+        "patch-1.MPQ",
+        "enUS/locale-enUS.MPQ",
+        "patch-A.MPQ",
+        "enUS/patch-enUS.MPQ",
+        "patch-2.MPQ",
+    ]
+    .iter()
+    .map(|p| data.join(p))
+    .collect::<Vec<_>>();
+
+    let order = archive_order(data, paths)
+        .into_iter()
+        .map(|p| p.strip_prefix(data).unwrap().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        order,
+        [
+            "enUS/patch-enUS-2.MPQ",
+            "enUS/patch-enUS.MPQ",
+            "patch-A.MPQ",
+            "patch-3.MPQ",
+            "patch-2.MPQ",
+            "patch-1.MPQ",
+            "patch.MPQ",
+            "enUS/locale-enUS.MPQ",
+            "expansion.MPQ",
+            "common.MPQ",
+        ]
+    );
+}
+
+#[test]
+fn test_archive_order_matches_namigator() {
+    use crate::util::path_to_cstr;
+    use std::ffi::CString;
+
+    const FILE_NAME: &str = "Test\\order.bin";
+
+    let data = std::env::temp_dir().join("namigator_archive_order");
+    let _ = std::fs::remove_dir_all(&data);
+    std::fs::create_dir_all(data.join("enUS")).unwrap();
+
+    let names = [
+        "common.MPQ",
+        "expansion.MPQ",
+        "patch.MPQ",
+        "patch-1.MPQ",
+        "patch-2.MPQ",
+        "patch-A.MPQ",
+        "enUS/locale-enUS.MPQ",
+        "enUS/patch-enUS.MPQ",
+        "enUS/patch-enUS-2.MPQ",
+    ];
+    let file_name = CString::new(FILE_NAME).unwrap();
+    for (index, name) in names.iter().enumerate() {
+        let path = path_to_cstr(&data.join(name)).unwrap();
+        let contents = (index as u32).to_le_bytes();
+        let result = unsafe {
+            namigator_sys::mpq_create_archive(
+                path.as_ptr(),
+                file_name.as_ptr(),
+                contents.as_ptr(),
+                contents.len() as u32,
+            )
+        };
+        assert_eq!(result, namigator_sys::SUCCESS);
+    }
+
+    // Removing the archive that the file was read from must make both read the same archive next.
+    let c_data = path_to_cstr(&data).unwrap();
+    for _ in names {
+        let archives = MpqArchiveSet::open(&data).unwrap();
+        let ours = archives.read_file(FILE_NAME).unwrap();
+        let ours = u32::from_le_bytes(ours[..4].try_into().unwrap());
+        drop(archives);
+
+        let mut namigators = 0;
+        let result = unsafe {
+            namigator_sys::mpq_manager_read_u32(
+                c_data.as_ptr(),
+                file_name.as_ptr(),
+                &mut namigators,
+            )
+        };
+        assert_eq!(result, namigator_sys::SUCCESS);
+        assert_eq!(ours, namigators, "{}", names[ours as usize]);
+
+        std::fs::remove_file(data.join(names[ours as usize])).unwrap();
+    }
+
+    std::fs::remove_dir_all(&data).unwrap();
+}
+
+#[test]
+fn test_dbc() {
+    let mut data = b"WDBC".to_vec();
+    for v in [2_u32, 3, 12, 10] {
+        data.extend(v.to_le_bytes());
+    }
+    for v in [0_u32, 1, 0, 1, 9, 0] {
+        data.extend(v.to_le_bytes());
+    }
+    data.extend(b"\0Azeroth\0\0");
+
+    let dbc = Dbc::parse(&data).unwrap();
+    assert_eq!(dbc.record_count(), 2);
+    assert_eq!(dbc.field_count(), 3);
+    assert_eq!(dbc.u32(1, 0), Some(1));
+    assert_eq!(dbc.string(0, 1), Some("Azeroth"));
+    assert_eq!(dbc.string(1, 1), Some(""));
+    assert_eq!(dbc.f32(0, 2), Some(0.0));
+    assert_eq!(dbc.u32(2, 0), None);
+    assert_eq!(dbc.u32(0, 3), None);
+
+    match Dbc::parse(&data[..data.len() - 1]) {
+        Err(NamigatorError::UnrecognizedDbcFile) => {}
+        e => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn test_map_name_field() {
+    let strings = b"\0Azeroth\0Eastern Kingdoms\0Kalimdor\0";
+    // Id, directory, instance type, name.
+    let records = [[0_u32, 1, 0, 9], [1, 26, 2, 0]];
+
+    let mut data = b"WDBC".to_vec();
+    for v in [2_u32, 4, 16, strings.len() as u32] {
+        data.extend(v.to_le_bytes());
+    }
+    for v in records.iter().flatten() {
+        data.extend(v.to_le_bytes());
+    }
+    data.extend(strings);
+
+    let dbc = Dbc::parse(&data).unwrap();
+    assert_eq!(map_name_field(&dbc), Some(3));
+    assert_eq!(dbc.string(0, 3), Some("Eastern Kingdoms"));
+}
+
+#[test]
+fn test_wdt_has_adts() {
+    fn wdt(entry_size: usize, adt: Option<(usize, u32)>) -> Vec<u8> {
+        let mut main = vec![0_u8; 64 * 64 * entry_size];
+        if let Some((index, value)) = adt {
+            main[index * entry_size..index * entry_size + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        let mut wdt = Vec::new();
+        for (magic, data) in [(b"REVM", &18_u32.to_le_bytes()[..]), (b"NIAM", &main[..])] {
+            wdt.extend(magic);
+            wdt.extend((data.len() as u32).to_le_bytes());
+            wdt.extend(data);
+        }
+        wdt
+    }
+
+    assert!(wdt_has_adts(&wdt(8, Some((32 * 64 + 48, 1)))).unwrap());
+    assert!(!wdt_has_adts(&wdt(8, None)).unwrap());
+    assert!(!wdt_has_adts(&wdt(8, Some((0, 2)))).unwrap());
+    assert!(wdt_has_adts(&wdt(16, Some((100, 0x1234)))).unwrap());
+    assert!(!wdt_has_adts(&wdt(16, None)).unwrap());
+    assert!(wdt_has_adts(include_bytes!("../test_data/alpha/Azeroth.wdt")).unwrap());
+
+    match wdt_has_adts(b"REVM\x04\0\0\0\x12\0\0\0") {
+        Err(NamigatorError::MainNotFound) => {}
+        e => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn test_alpha_client_maps() {
+    use crate::mpq::{list_client_maps, ClientMap};
+
+    // Azeroth has the WDT under its full name, Test under only its file name and ScottTest has no WDT.
+    let data = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test_data")
+        .join("alpha")
+        .join("Data");
+    let map = |id, directory: &str, name: &str, has_adts| ClientMap {
+        id,
+        directory: directory.to_string(),
+        name: name.to_string(),
+        has_adts,
+    };
+
+    assert_eq!(
+        list_client_maps(data).unwrap(),
+        [
+            map(0, "Azeroth", "Eastern Kingdoms", true),
+            map(13, "Test", "Test", false),
+            map(25, "ScottTest", "Scott Test", false),
+        ]
+    );
+}
+
 fn distance(from_x: f32, from_y: f32, from_z: f32, to_x: f32, to_y: f32, to_z: f32) -> f32 {
     let x = (from_x - to_x).powi(2);
     let y = (from_y - to_y).powi(2);
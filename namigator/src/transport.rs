@@ -0,0 +1,211 @@
+use crate::error::{error_code_to_error, NamigatorError};
+use crate::util::path_to_cstr;
+use crate::Vector3d;
+use namigator_sys::{
+    pathfind_transport_find_path, pathfind_transport_free, pathfind_transport_line_of_sight,
+    pathfind_transport_new, pathfind_transport_new_from_model, Vertex, BUFFER_TOO_SMALL, SUCCESS,
+};
+use std::ffi::{c_float, c_uint};
+use std::path::Path;
+
+const INITIAL_VEC_SIZE: usize = 10;
+
+/// Navmesh for the deck of a transport, such as a boat or zeppelin.
+///
+/// All positions are local to the transport, use [`TransportPosition`] to convert to and from world positions.
+#[derive(Debug)]
+pub struct TransportNavmesh {
+    transport: *mut namigator_sys::Transport,
+    inner_path: Vec<Vertex>,
+    path: Vec<Vector3d>,
+}
+
+// SAFETY: The transport is only accessed through `&self` and `&mut self`.
+unsafe impl Send for TransportNavmesh {}
+
+impl TransportNavmesh {
+    /// Build a navmesh from a gameobject model written by [`build_bvh`](crate::raw::build_bvh).
+    ///
+    /// `model` is the name of the model file inside the `BVH` directory of `output_path`.
+    /// Returns [`NamigatorError::CouldNotDeserializeDoodad`] when the model can not be read
+    /// or has triangles referring to vertices it does not have.
+    pub fn from_model(output_path: impl AsRef<Path>, model: &str) -> Result<Self, NamigatorError> {
+        fn inner(output_path: &Path, model: &str) -> Result<TransportNavmesh, NamigatorError> {
+            let model_path = path_to_cstr(&output_path.join("BVH").join(model))?;
+
+            let mut result: u8 = 0;
+            // SAFETY: CStrings are guaranteed to be valid pointers
+            let transport = unsafe {
+                pathfind_transport_new_from_model(model_path.as_ptr(), &mut result as *mut u8)
+            };
+
+            TransportNavmesh::from_raw(transport, result)
+        }
+
+        inner(output_path.as_ref(), model)
+    }
+
+    /// Build a navmesh from triangles in coordinates local to the transport.
+    ///
+    /// Every three `indices` into `vertices` make up a triangle.
+    /// Returns [`NamigatorError::RecastFailure`] when an index is out of range
+    /// or the indices do not make up whole triangles.
+    pub fn from_triangles(vertices: &[Vector3d], indices: &[u32]) -> Result<Self, NamigatorError> {
+        let vertices = vertices
+            .iter()
+            .flat_map(|v| [v.x, v.y, v.z])
+            .collect::<Vec<c_float>>();
+
+        let mut result: u8 = 0;
+        // SAFETY: The lengths are those of the slices the pointers come from
+        let transport = unsafe {
+            pathfind_transport_new(
+                vertices.as_ptr(),
+                (vertices.len() / 3) as c_uint,
+                indices.as_ptr(),
+                indices.len() as c_uint,
+                &mut result as *mut u8,
+            )
+        };
+
+        Self::from_raw(transport, result)
+    }
+
+    fn from_raw(
+        transport: *mut namigator_sys::Transport,
+        result: u8,
+    ) -> Result<Self, NamigatorError> {
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
+        }
+
+        if transport.is_null() {
+            return Err(NamigatorError::MapIsNullPointer);
+        }
+
+        Ok(Self {
+            transport,
+            inner_path: vec![Vertex::default(); INITIAL_VEC_SIZE],
+            path: Vec::with_capacity(INITIAL_VEC_SIZE),
+        })
+    }
+
+    pub fn find_path(
+        &mut self,
+        start: Vector3d,
+        stop: Vector3d,
+    ) -> Result<&[Vector3d], NamigatorError> {
+        let mut amount_of_vertices: c_uint = 0;
+
+        let mut result = self.call_find_path(start, stop, &mut amount_of_vertices);
+        if result == BUFFER_TOO_SMALL {
+            self.inner_path.resize(
+                usize::try_from(amount_of_vertices).unwrap(),
+                Vertex::default(),
+            );
+
+            result = self.call_find_path(start, stop, &mut amount_of_vertices);
+        }
+
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
+        }
+
+        self.path.clear();
+        self.path.extend(
+            self.inner_path[..usize::try_from(amount_of_vertices).unwrap()]
+                .iter()
+                .map(|v| Vector3d {
+                    x: v.x,
+                    y: v.y,
+                    z: v.z,
+                }),
+        );
+
+        Ok(&self.path)
+    }
+
+    fn call_find_path(
+        &mut self,
+        start: Vector3d,
+        stop: Vector3d,
+        amount_of_vertices: &mut c_uint,
+    ) -> u8 {
+        unsafe {
+            pathfind_transport_find_path(
+                self.transport,
+                start.x,
+                start.y,
+                start.z,
+                stop.x,
+                stop.y,
+                stop.z,
+                self.inner_path.as_mut_ptr(),
+                self.inner_path.len() as c_uint,
+                amount_of_vertices as *mut c_uint,
+            )
+        }
+    }
+
+    /// Whether the straight line between `from` and `to` is blocked by the transport model.
+    pub fn line_of_sight(&self, from: Vector3d, to: Vector3d) -> Result<bool, NamigatorError> {
+        let mut line_of_sight: u8 = 0;
+
+        let result = unsafe {
+            pathfind_transport_line_of_sight(
+                self.transport,
+                from.x,
+                from.y,
+                from.z,
+                to.x,
+                to.y,
+                to.z,
+                &mut line_of_sight as *mut u8,
+            )
+        };
+
+        if result != SUCCESS {
+            return Err(error_code_to_error(result));
+        }
+
+        Ok(line_of_sight == 1)
+    }
+}
+
+impl Drop for TransportNavmesh {
+    fn drop(&mut self) {
+        unsafe { pathfind_transport_free(self.transport) }
+    }
+}
+
+/// Position of a transport in the world, used for converting between local and world positions.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct TransportPosition {
+    pub position: Vector3d,
+    /// Rotation around the z axis in radians.
+    pub orientation: f32,
+}
+
+impl TransportPosition {
+    pub fn to_local(&self, world: Vector3d) -> Vector3d {
+        let (sin, cos) = self.orientation.sin_cos();
+        let x = world.x - self.position.x;
+        let y = world.y - self.position.y;
+
+        Vector3d {
+            x: x * cos + y * sin,
+            y: y * cos - x * sin,
+            z: world.z - self.position.z,
+        }
+    }
+
+    pub fn to_world(&self, local: Vector3d) -> Vector3d {
+        let (sin, cos) = self.orientation.sin_cos();
+
+        Vector3d {
+            x: self.position.x + local.x * cos - local.y * sin,
+            y: self.position.y + local.x * sin + local.y * cos,
+            z: self.position.z + local.z,
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use crate::error::NamigatorError;
-use std::ffi::CString;
-use std::path::Path;
+use crate::Vector3d;
+use namigator_sys::RandomCallback;
+use rand_core::RngCore;
+use std::ffi::{c_float, c_void, CString};
+use std::path::{Path, PathBuf};
 
 pub fn path_to_cstr(p: &Path) -> Result<CString, NamigatorError> {
     let e = match p.to_str() {
@@ -11,7 +14,102 @@ pub fn path_to_cstr(p: &Path) -> Result<CString, NamigatorError> {
     Ok(CString::new(e)?)
 }
 
-#[cfg(any(feature = "vanilla", feature = "tbc", feature = "wrath"))]
+/// Files directly inside `directory`, sorted, or none if it does not exist.
+pub(crate) fn files_in(directory: &Path) -> Result<Vec<PathBuf>, NamigatorError> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+pub(crate) fn distance(from: Vector3d, to: Vector3d) -> f32 {
+    let x = from.x - to.x;
+    let y = from.y - to.y;
+    let z = from.z - to.z;
+
+    (x * x + y * y + z * z).sqrt()
+}
+
+pub(crate) fn path_length(path: &[Vector3d]) -> f32 {
+    path.windows(2).map(|w| distance(w[0], w[1])).sum()
+}
+
+/// Width and height of an ADT in yards.
+pub(crate) const ADT_SIZE: f32 = 533.333_3;
+
+/// ADT coordinates, as used by [`PathfindMap::load_adt`](crate::raw::PathfindMap::load_adt), of a world position.
+pub fn adt_coordinates(x: f32, y: f32) -> (i32, i32) {
+    let middle = 32.0 * ADT_SIZE;
+
+    (
+        ((middle - y) / ADT_SIZE).floor() as i32,
+        ((middle - x) / ADT_SIZE).floor() as i32,
+    )
+}
+
+/// ADT coordinates of every ADT that the straight line between `from` and `to` passes through,
+/// in the order that the line passes through them.
+pub fn adts_on_line(from: Vector3d, to: Vector3d) -> Vec<(i32, i32)> {
+    let middle = 32.0 * ADT_SIZE;
+    let grid = |v: Vector3d| ((middle - v.y) / ADT_SIZE, (middle - v.x) / ADT_SIZE);
+    let (from_grid, to_grid) = (grid(from), grid(to));
+
+    // Fractions of the line where it crosses from one ADT into the next.
+    let mut crossings = vec![0.0, 1.0];
+    for (a, b) in [(from_grid.0, to_grid.0), (from_grid.1, to_grid.1)] {
+        let first = a.min(b).floor() as i32 + 1;
+        let last = a.max(b).ceil() as i32 - 1;
+
+        crossings.extend((first..=last).map(|edge| (edge as f32 - a) / (b - a)));
+    }
+    crossings.sort_by(f32::total_cmp);
+
+    let mut adts = Vec::new();
+    for pair in crossings.windows(2) {
+        let t = (pair[0] + pair[1]) / 2.0;
+        let adt = adt_coordinates(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t);
+
+        if !adts.contains(&adt) {
+            adts.push(adt);
+        }
+    }
+
+    adts
+}
+
+/// Returns a callback and state pointer that draws floats in `[0.0, 1.0)` from `rng`.
+///
+/// The state pointer is only valid for as long as `rng` is borrowed.
+pub(crate) fn random_callback<R: RngCore>(rng: &mut R) -> (RandomCallback, *mut c_void) {
+    extern "C" fn next_float<R: RngCore>(state: *mut c_void) -> c_float {
+        // SAFETY: state is always the `&mut R` passed to `random_callback`.
+        let rng = unsafe { &mut *(state as *mut R) };
+
+        // An f32 can only represent 24 bits of precision in [0.0, 1.0).
+        (rng.next_u32() >> 8) as f32 / (1_u32 << 24) as f32
+    }
+
+    (next_float::<R>, rng as *mut R as *mut c_void)
+}
+
+#[cfg(any(
+    feature = "vanilla",
+    feature = "tbc",
+    feature = "wrath",
+    feature = "alpha"
+))]
 macro_rules! specific_pathfind {
     ($map:ty, $ty_name:ident, $zone_ty:ty, $area_ty:ty) => {
         pub use $area_ty;
@@ -76,7 +174,8 @@ macro_rules! specific_pathfind {
                 ) -> Result<$ty_name, $crate::NamigatorError> {
                     $ty_name::build_gameobjects(data_path, output_path, threads)?;
 
-                    if !$crate::raw::map_files_exist(output_path, map.directory_name())? {
+                    let status = $crate::raw::build_status(output_path, map.directory_name())?;
+                    if status.needs_build() {
                         $crate::build::build_map(
                             data_path,
                             output_path,
@@ -92,6 +191,60 @@ macro_rules! specific_pathfind {
                 inner(data_path.as_ref(), output_path.as_ref(), map, threads)
             }
 
+            /// Build the gameobjects and every map accepted by `filter`
+            /// that is not built from the current inputs.
+            ///
+            /// A map failing to build does not stop the remaining maps from being built,
+            /// the status of every map accepted by `filter` is returned instead.
+            /// Only failing to build the gameobjects is returned as an error.
+            ///
+            /// Maps that were built without a manifest are reported as
+            /// [`MapBuildStatus::NoManifest`]($crate::raw::MapBuildStatus::NoManifest)
+            /// and are left as they are.
+            pub fn build_all(
+                data_path: impl AsRef<std::path::Path>,
+                output_path: impl AsRef<std::path::Path>,
+                threads: u32,
+                mut filter: impl FnMut($map) -> bool,
+            ) -> Result<Vec<($map, $crate::raw::MapBuildStatus)>, $crate::NamigatorError> {
+                let data_path = data_path.as_ref();
+                let output_path = output_path.as_ref();
+
+                $ty_name::build_gameobjects(data_path, output_path, threads)?;
+
+                let mut statuses = Vec::new();
+                for map in <$map>::variants() {
+                    if !filter(map) {
+                        continue;
+                    }
+
+                    let name = map.directory_name();
+                    let status = match $crate::raw::build_status(output_path, name) {
+                        Ok($crate::raw::BuildStatus::UpToDate) => {
+                            $crate::raw::MapBuildStatus::AlreadyBuilt
+                        }
+                        Ok($crate::raw::BuildStatus::Stale(
+                            $crate::raw::StaleReason::NoManifest,
+                        )) => $crate::raw::MapBuildStatus::NoManifest,
+                        Ok(_) => match $crate::build::build_map(
+                            data_path,
+                            output_path,
+                            name,
+                            "",
+                            threads,
+                        ) {
+                            Ok(()) => $crate::raw::MapBuildStatus::Built,
+                            Err(e) => $crate::raw::MapBuildStatus::Failed(e),
+                        },
+                        Err(e) => $crate::raw::MapBuildStatus::Failed(e),
+                    };
+
+                    statuses.push((map, status));
+                }
+
+                Ok(statuses)
+            }
+
             pub fn load_all_adts(&mut self) -> Result<u32, $crate::error::NamigatorError> {
                 self.map.load_all_adts()
             }
@@ -143,6 +296,49 @@ macro_rules! specific_pathfind {
                 self.map.find_path(start, stop)
             }
 
+            pub fn add_off_mesh_connection(
+                &mut self,
+                connection: $crate::raw::OffMeshConnection,
+            ) -> Result<(), $crate::error::NamigatorError> {
+                self.map.add_off_mesh_connection(connection)
+            }
+
+            pub fn remove_off_mesh_connection(
+                &mut self,
+                id: u32,
+            ) -> Result<bool, $crate::error::NamigatorError> {
+                self.map.remove_off_mesh_connection(id)
+            }
+
+            pub fn off_mesh_connections(&self) -> &[$crate::raw::OffMeshConnection] {
+                self.map.off_mesh_connections()
+            }
+
+            pub fn find_path_with_options(
+                &mut self,
+                start: $crate::Vector3d,
+                stop: $crate::Vector3d,
+                options: &$crate::raw::PathOptions,
+            ) -> Result<&[$crate::Vector3d], $crate::error::NamigatorError> {
+                self.map.find_path_with_options(start, stop, options)
+            }
+
+            pub fn find_path_segments(
+                &mut self,
+                start: $crate::Vector3d,
+                stop: $crate::Vector3d,
+            ) -> Result<&[$crate::raw::PathSegment], $crate::error::NamigatorError> {
+                self.map.find_path_segments(start, stop)
+            }
+
+            pub fn resample_heights(
+                &mut self,
+                path: &[$crate::Vector3d],
+                step: f32,
+            ) -> Result<Vec<$crate::Vector3d>, $crate::error::NamigatorError> {
+                self.map.resample_heights(path, step)
+            }
+
             pub fn find_heights(
                 &mut self,
                 x: f32,
@@ -174,8 +370,126 @@ macro_rules! specific_pathfind {
             ) -> Result<$crate::Vector3d, $crate::error::NamigatorError> {
                 self.map.find_random_point_around_circle(start, radius)
             }
+
+            pub fn find_random_point_around_circle_with_rng(
+                &self,
+                start: $crate::Vector3d,
+                radius: f32,
+                rng: &mut impl rand_core::RngCore,
+            ) -> Result<$crate::Vector3d, $crate::error::NamigatorError> {
+                self.map
+                    .find_random_point_around_circle_with_rng(start, radius, rng)
+            }
+
+            pub fn random_reachable_point(
+                &self,
+                start: $crate::Vector3d,
+                radius: f32,
+                rng: &mut impl rand_core::RngCore,
+            ) -> Result<$crate::Vector3d, $crate::error::NamigatorError> {
+                self.map.random_reachable_point(start, radius, rng)
+            }
+
+            pub fn flee_point(
+                &mut self,
+                from: $crate::Vector3d,
+                threat: $crate::Vector3d,
+                distance: f32,
+            ) -> Result<($crate::Vector3d, &[$crate::Vector3d]), $crate::error::NamigatorError>
+            {
+                self.map.flee_point(from, threat, distance)
+            }
+
+            pub fn wander_point(
+                &mut self,
+                home: $crate::Vector3d,
+                radius: f32,
+                rng: &mut impl rand_core::RngCore,
+            ) -> Result<($crate::Vector3d, &[$crate::Vector3d]), $crate::error::NamigatorError>
+            {
+                self.map.wander_point(home, radius, rng)
+            }
+
+            pub fn find_landing_point(
+                &mut self,
+                from: $crate::Vector3d,
+                direction: $crate::raw::LandingDirection,
+                max_distance: f32,
+                max_path_ratio: f32,
+            ) -> Result<$crate::Vector3d, $crate::error::NamigatorError> {
+                self.map
+                    .find_landing_point(from, direction, max_distance, max_path_ratio)
+            }
+
+            pub fn raycast(
+                &self,
+                from: $crate::Vector3d,
+                to: $crate::Vector3d,
+            ) -> Result<($crate::Vector3d, bool), $crate::error::NamigatorError> {
+                self.map.raycast(from, to)
+            }
+
+            pub fn distance_to_wall(
+                &self,
+                position: $crate::Vector3d,
+                max_radius: f32,
+            ) -> Result<(f32, $crate::Vector3d, $crate::Vector3d), $crate::error::NamigatorError>
+            {
+                self.map.distance_to_wall(position, max_radius)
+            }
+
+            pub fn navmesh_view(
+                &self,
+            ) -> Result<$crate::raw::NavmeshView, $crate::error::NamigatorError> {
+                self.map.navmesh_view()
+            }
+
+            pub fn export_obj(
+                &mut self,
+                data_path: impl AsRef<std::path::Path>,
+                tiles: &[(i32, i32)],
+                include: &[$crate::raw::ExportLayer],
+                path: impl AsRef<std::path::Path>,
+            ) -> Result<(), $crate::error::NamigatorError> {
+                self.map.export_obj(data_path, tiles, include, path)
+            }
+
+            pub fn export_gltf(
+                &mut self,
+                data_path: impl AsRef<std::path::Path>,
+                tiles: &[(i32, i32)],
+                include: &[$crate::raw::ExportLayer],
+                path: Option<&[$crate::Vector3d]>,
+                out: impl AsRef<std::path::Path>,
+            ) -> Result<(), $crate::error::NamigatorError> {
+                self.map.export_gltf(data_path, tiles, include, path, out)
+            }
+
+            pub fn render_tile_png(
+                &mut self,
+                x: i32,
+                y: i32,
+                include: &[$crate::raw::NavmeshArea],
+                path: impl AsRef<std::path::Path>,
+            ) -> Result<(), $crate::error::NamigatorError> {
+                self.map.render_tile_png(x, y, include, path)
+            }
+
+            pub fn tile_png(
+                &mut self,
+                x: i32,
+                y: i32,
+                include: &[$crate::raw::NavmeshArea],
+            ) -> Result<Vec<u8>, $crate::error::NamigatorError> {
+                self.map.tile_png(x, y, include)
+            }
         }
     };
 }
-#[cfg(any(feature = "vanilla", feature = "tbc", feature = "wrath"))]
+#[cfg(any(
+    feature = "vanilla",
+    feature = "tbc",
+    feature = "wrath",
+    feature = "alpha"
+))]
 pub(crate) use specific_pathfind;
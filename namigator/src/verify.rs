@@ -0,0 +1,164 @@
+use crate::error::{error_code_to_error, NamigatorError};
+use crate::pathfind::PathfindMap;
+use crate::util::{files_in, path_to_cstr};
+use namigator_sys::{pathfind_verify_model, SUCCESS};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Amount of ADTs along each axis of a map.
+const ADTS_PER_AXIS: i32 = 64;
+
+/// Files of a built map that can not be used, found by [`verify_map_output`].
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    /// Amount of ADT tiles that were loaded.
+    pub adts_checked: u32,
+    /// Amount of gameobject models in the `BVH` directory that were read.
+    pub models_checked: u32,
+    pub problems: Vec<VerificationProblem>,
+}
+
+impl VerificationReport {
+    /// Whether no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub enum VerificationProblem {
+    /// The map file could not be loaded, so no ADTs were checked.
+    Map(NamigatorError),
+    /// The global WMO of a map without ADTs could not be loaded.
+    GlobalWmo(NamigatorError),
+    Adt {
+        x: i32,
+        y: i32,
+        error: NamigatorError,
+    },
+    /// A gameobject model could not be read, `name` is the file name inside the `BVH` directory.
+    Model { name: String, error: NamigatorError },
+    /// A file in the output was left empty, which usually means that a build was interrupted.
+    EmptyFile(PathBuf),
+}
+
+impl Display for VerificationProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerificationProblem::Map(e) => write!(f, "map file: {}", e),
+            VerificationProblem::GlobalWmo(e) => write!(f, "global WMO: {}", e),
+            VerificationProblem::Adt { x, y, error } => write!(f, "ADT ({}, {}): {}", x, y, error),
+            VerificationProblem::Model { name, error } => write!(f, "model '{}': {}", name, error),
+            VerificationProblem::EmptyFile(path) => write!(f, "empty file '{}'", path.display()),
+        }
+    }
+}
+
+/// Open every file of `map_name` in `output_path` and every gameobject model, and list the files that can not be used.
+///
+/// The files are read the same way as by [`PathfindMap`], so problems that would otherwise only show up as
+/// [`NamigatorError::IncorrectFileSignature`], [`NamigatorError::IncorrectFileVersion`] or
+/// [`NamigatorError::InvalidMapFile`] when an ADT is loaded at runtime are found up front.
+/// Every ADT is unloaded again after being checked.
+///
+/// Only failing to list the output directories is returned as an error.
+pub fn verify_map_output(
+    output_path: impl AsRef<Path>,
+    map_name: &str,
+) -> Result<VerificationReport, NamigatorError> {
+    fn inner(output_path: &Path, map_name: &str) -> Result<VerificationReport, NamigatorError> {
+        let mut report = VerificationReport::default();
+
+        for directory in [
+            output_path.join("Nav").join(map_name),
+            output_path.join("BVH"),
+        ] {
+            for path in files_in(&directory)? {
+                if std::fs::metadata(&path)?.len() == 0 {
+                    report.problems.push(VerificationProblem::EmptyFile(path));
+                }
+            }
+        }
+
+        verify_map(output_path, map_name, &mut report);
+
+        for path in files_in(&output_path.join("BVH"))? {
+            if !path
+                .extension()
+                .map_or(false, |e| e.eq_ignore_ascii_case("bvh"))
+            {
+                continue;
+            }
+
+            report.models_checked += 1;
+            if let Err(error) = verify_model(&path) {
+                report.problems.push(VerificationProblem::Model {
+                    name: path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                    error,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    inner(output_path.as_ref(), map_name)
+}
+
+fn verify_map(output_path: &Path, map_name: &str, report: &mut VerificationReport) {
+    let mut map = match PathfindMap::new(output_path, map_name) {
+        Ok(map) => map,
+        Err(e) => {
+            report.problems.push(VerificationProblem::Map(e));
+            return;
+        }
+    };
+
+    match map.has_adts() {
+        Ok(true) => {}
+        Ok(false) => {
+            if let Err(e) = map.load_all_adts() {
+                report.problems.push(VerificationProblem::GlobalWmo(e));
+            }
+            return;
+        }
+        Err(e) => {
+            report.problems.push(VerificationProblem::Map(e));
+            return;
+        }
+    }
+
+    for x in 0..ADTS_PER_AXIS {
+        for y in 0..ADTS_PER_AXIS {
+            let result = map.load_adt(x, y).and_then(|_| map.unload_adt(x, y));
+
+            match result {
+                Ok(()) => report.adts_checked += 1,
+                Err(NamigatorError::MapDoesNotHaveAdt) => {}
+                Err(error) => {
+                    report.adts_checked += 1;
+                    report
+                        .problems
+                        .push(VerificationProblem::Adt { x, y, error });
+                }
+            }
+        }
+    }
+}
+
+fn verify_model(path: &Path) -> Result<(), NamigatorError> {
+    let path = path_to_cstr(path)?;
+
+    // SAFETY: CStrings are guaranteed to be valid pointers
+    let result = unsafe { pathfind_verify_model(path.as_ptr()) };
+
+    if result == SUCCESS {
+        Ok(())
+    } else {
+        Err(error_code_to_error(result))
+    }
+}